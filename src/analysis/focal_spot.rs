@@ -0,0 +1,273 @@
+use autd3::driver::geometry::Point3;
+
+use crate::{EmulatorError, Grid};
+
+/// Result of the focal spot analysis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FocalSpot {
+    /// Flat index of the grid point with the maximum value.
+    pub peak_index: usize,
+    /// Position of the peak interpolated by fitting a parabola along each axis \[mm\].
+    pub peak_position: Point3,
+    /// Peak value interpolated in the same way as [`FocalSpot::peak_position`].
+    pub peak_value: f32,
+    /// Full width at half maximum along each axis of the grid \[mm\].
+    /// `None` if the value does not fall below half of the peak value on both sides within the grid.
+    pub fwhm: Vec<Option<f32>>,
+    /// Ratio of the highest local maximum other than the peak to the peak value.
+    /// `None` if there is no other local maximum.
+    pub side_lobe_level: Option<f32>,
+    /// Distance between [`FocalSpot::peak_position`] and the target \[mm\]. `None` if no target is given.
+    pub focus_error: Option<f32>,
+}
+
+impl Grid {
+    fn neighbors(&self, index: &[usize]) -> impl Iterator<Item = usize> {
+        let offsets = (0..3usize.pow(self.axes.len() as u32))
+            .map(|mut n| {
+                self.axes
+                    .iter()
+                    .map(|_| {
+                        let o = (n % 3) as isize - 1;
+                        n /= 3;
+                        o
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .filter(|o| o.iter().any(|&o| o != 0));
+        offsets.filter_map(move |o| {
+            let index = index
+                .iter()
+                .zip(o.iter())
+                .zip(self.axes.iter())
+                .map(|((&i, &o), axis)| i.checked_add_signed(o).filter(|&i| i < axis.len))
+                .collect::<Option<Vec<_>>>()?;
+            Some(self.flat_index(&index))
+        })
+    }
+
+    fn check_field_len(&self, values: &[f32]) -> Result<(), EmulatorError> {
+        if values.len() != self.len() {
+            return Err(EmulatorError::FieldLengthMismatch {
+                expected: self.len(),
+                actual: values.len(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the flat indices of the local maxima of the field on the grid in descending order of the value.
+    ///
+    /// A point is a local maximum if its value is not less than those of all adjacent points including diagonal ones.
+    /// On a plateau, only the point with the smallest index is reported.
+    pub fn local_maxima(&self, values: &[f32]) -> Result<Vec<usize>, EmulatorError> {
+        self.check_field_len(values)?;
+        let mut maxima = (0..self.len())
+            .filter(|&i| {
+                let v = values[i];
+                self.neighbors(&self.unflat_index(i))
+                    .all(|j| if j < i { v > values[j] } else { v >= values[j] })
+            })
+            .collect::<Vec<_>>();
+        maxima.sort_by(|&a, &b| values[b].total_cmp(&values[a]));
+        Ok(maxima)
+    }
+
+    /// Analyzes the focal spot of the field on the grid, e.g., the result of [`Rms::next`].
+    ///
+    /// If `target` is given, the distance between the target and the peak is also calculated.
+    /// Returns [`EmulatorError::NoPeak`] if the field has no local maximum, e.g., the grid is empty.
+    ///
+    /// [`Rms::next`]: crate::Rms::next
+    pub fn focal_spot(
+        &self,
+        values: &[f32],
+        target: Option<Point3>,
+    ) -> Result<FocalSpot, EmulatorError> {
+        let maxima = self.local_maxima(values)?;
+        let peak_index = *maxima.first().ok_or(EmulatorError::NoPeak)?;
+        let peak = self.unflat_index(peak_index);
+        let value_at = |axis: usize, i: usize| {
+            let mut index = peak.clone();
+            index[axis] = i;
+            values[self.flat_index(&index)]
+        };
+
+        let v0 = values[peak_index];
+        let (offsets, corrections): (Vec<_>, Vec<_>) = self
+            .axes
+            .iter()
+            .enumerate()
+            .map(|(a, axis)| {
+                let i = peak[a];
+                if i == 0 || i + 1 >= axis.len {
+                    return (0., 0.);
+                }
+                let vm = value_at(a, i - 1);
+                let vp = value_at(a, i + 1);
+                let denom = vm - 2. * v0 + vp;
+                if denom >= 0. {
+                    return (0., 0.);
+                }
+                let delta = (0.5 * (vm - vp) / denom).clamp(-0.5, 0.5);
+                (delta, -0.25 * (vm - vp) * delta)
+            })
+            .unzip();
+        let peak_position = self.position(
+            &peak
+                .iter()
+                .zip(offsets.iter())
+                .map(|(&i, &d)| i as f32 + d)
+                .collect::<Vec<_>>(),
+        );
+        let peak_value = v0 + corrections.iter().sum::<f32>();
+
+        let half = peak_value / 2.;
+        let fwhm = self
+            .axes
+            .iter()
+            .enumerate()
+            .map(|(a, axis)| {
+                let i = peak[a];
+                let left = (0..i).rev().find(|&j| value_at(a, j) < half).map(|j| {
+                    let (v, vn) = (value_at(a, j), value_at(a, j + 1));
+                    j as f32 + (half - v) / (vn - v)
+                })?;
+                let right = (i + 1..axis.len)
+                    .find(|&j| value_at(a, j) < half)
+                    .map(|j| {
+                        let (v, vp) = (value_at(a, j), value_at(a, j - 1));
+                        j as f32 - (half - v) / (vp - v)
+                    })?;
+                Some((right - left) * axis.step.norm())
+            })
+            .collect();
+
+        Ok(FocalSpot {
+            peak_index,
+            peak_position,
+            peak_value,
+            fwhm,
+            side_lobe_level: maxima.get(1).map(|&i| values[i] / peak_value),
+            focus_error: target.map(|t| (t - peak_position).norm()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::driver::geometry::Vector3;
+
+    use super::*;
+    use crate::GridAxis;
+
+    fn grid() -> Grid {
+        Grid {
+            origin: Point3::new(-10., -10., 0.),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0.5, 0., 0.),
+                    len: 41,
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0.5, 0.),
+                    len: 41,
+                },
+            ],
+        }
+    }
+
+    fn gaussian(grid: &Grid, center: Point3, sigma: f32, amp: f32) -> Vec<f32> {
+        (0..grid.len())
+            .map(|i| {
+                let index = grid.unflat_index(i);
+                let p = grid.position(&index.iter().map(|&i| i as f32).collect::<Vec<_>>());
+                amp * (-(p - center).norm().powi(2) / (2. * sigma * sigma)).exp()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn focal_spot() -> Result<(), EmulatorError> {
+        let grid = grid();
+        let center = Point3::new(1.2, -0.7, 0.);
+        let side_lobe = Point3::new(-7., 6., 0.);
+        let sigma = 1.5;
+        let values = gaussian(&grid, center, sigma, 1.)
+            .into_iter()
+            .zip(gaussian(&grid, side_lobe, 1., 0.2))
+            .map(|(a, b)| a + b)
+            .collect::<Vec<_>>();
+
+        let spot = grid.focal_spot(&values, Some(Point3::new(1., -1., 0.)))?;
+
+        assert_eq!(grid.flat_index(&[22, 19]), spot.peak_index);
+        approx::assert_abs_diff_eq!(center.x, spot.peak_position.x, epsilon = 0.05);
+        approx::assert_abs_diff_eq!(center.y, spot.peak_position.y, epsilon = 0.05);
+        approx::assert_relative_eq!(1., spot.peak_value, max_relative = 1e-2);
+        spot.fwhm.iter().for_each(|fwhm| {
+            approx::assert_relative_eq!(
+                2. * (2. * 2f32.ln()).sqrt() * sigma,
+                fwhm.unwrap(),
+                max_relative = 2e-2
+            );
+        });
+        approx::assert_relative_eq!(0.2, spot.side_lobe_level.unwrap(), max_relative = 1e-2);
+        approx::assert_abs_diff_eq!(
+            (0.2f32.powi(2) + 0.3f32.powi(2)).sqrt(),
+            spot.focus_error.unwrap(),
+            epsilon = 0.05
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn focal_spot_at_edge() -> Result<(), EmulatorError> {
+        let grid = grid();
+        let values = gaussian(&grid, Point3::new(-10., 0., 0.), 1.5, 1.);
+
+        let spot = grid.focal_spot(&values, None)?;
+
+        assert_eq!(Point3::new(-10., 0., 0.), spot.peak_position);
+        assert_eq!(None, spot.fwhm[0]);
+        assert!(spot.fwhm[1].is_some());
+        assert_eq!(None, spot.side_lobe_level);
+        assert_eq!(None, spot.focus_error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn local_maxima_plateau() -> Result<(), EmulatorError> {
+        let grid = grid();
+        assert_eq!(vec![0], grid.local_maxima(&vec![1.; grid.len()])?);
+        Ok(())
+    }
+
+    #[test]
+    fn field_length_mismatch() {
+        assert!(matches!(
+            grid().focal_spot(&[0.; 10], None),
+            Err(EmulatorError::FieldLengthMismatch {
+                expected: 1681,
+                actual: 10
+            })
+        ));
+    }
+
+    #[test]
+    fn focal_spot_empty_grid() {
+        let grid = Grid {
+            origin: Point3::origin(),
+            axes: vec![GridAxis {
+                step: Vector3::new(1., 0., 0.),
+                len: 0,
+            }],
+        };
+        assert!(matches!(
+            grid.focal_spot(&[], None),
+            Err(EmulatorError::NoPeak)
+        ));
+    }
+}
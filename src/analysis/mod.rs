@@ -0,0 +1,3 @@
+mod focal_spot;
+
+pub use focal_spot::FocalSpot;
@@ -12,12 +12,60 @@ pub enum EmulatorError {
     InvalidDuration,
     /// Error when the time step is not a divisor of the ultrasound period.
     InvalidTimeStep,
+    /// Error when the averaging window or its step is not a non-zero multiple of the ultrasound period.
+    InvalidWindow,
+    /// Error when the order of the harmonic is zero.
+    InvalidHarmonic,
     /// Error when requesting data outside the recorded range.
     NotRecorded,
+    /// Error when the percentile is not in \[0, 100\].
+    InvalidPercentile(f32),
+    /// Error when the length of the field does not match the number of points of the grid.
+    FieldLengthMismatch {
+        /// Number of points of the grid.
+        expected: usize,
+        /// Length of the field.
+        actual: usize,
+    },
+    /// Error when the field has no local maximum, e.g., the grid is empty.
+    NoPeak,
+    /// Error when the mesh file is malformed.
+    InvalidMesh(String),
+    /// Error when the range or the options are not supported by the angular spectrum method.
+    InvalidAngularSpectrum(String),
+    /// Error when the grid or the maximum depth of [`AdaptiveRange`] is invalid.
+    ///
+    /// [`AdaptiveRange`]: crate::AdaptiveRange
+    InvalidAdaptiveRange(String),
+    /// Error when the data which must be kept on memory exceeds the memory limits hint, e.g., the scattered field of the scatterers.
+    MemoryLimitsExceeded {
+        /// Size of the data \[byte\].
+        size: usize,
+        /// Memory limits hint \[byte\].
+        limit: usize,
+    },
+    #[allow(missing_docs)]
+    Io(std::io::Error),
     #[allow(missing_docs)]
     SamplingConfig(SamplingConfigError),
     #[allow(missing_docs)]
     Driver(AUTDDriverError),
+    /// Error when a buffer exceeds the limits of the GPU.
+    #[cfg(feature = "gpu")]
+    GpuBufferTooLarge {
+        /// Size of the buffer \[byte\].
+        size: u64,
+        /// Maximum size of a buffer on the GPU \[byte\].
+        limit: u64,
+    },
+    /// Error when the adapter of the index is not found.
+    #[cfg(feature = "gpu")]
+    GpuAdapterNotFound {
+        /// Index of the adapter.
+        index: usize,
+        /// Number of the available adapters.
+        count: usize,
+    },
     #[allow(missing_docs)]
     #[cfg(feature = "gpu")]
     RequestDeviceError(wgpu::RequestDeviceError),
@@ -47,10 +95,62 @@ impl std::fmt::Display for EmulatorError {
             EmulatorError::InvalidTimeStep => {
                 write!(f, "Time step must divide {:?}", ULTRASOUND_PERIOD)
             }
+            EmulatorError::InvalidWindow => {
+                write!(
+                    f,
+                    "Window and its step must be non-zero multiples of {:?}",
+                    ULTRASOUND_PERIOD
+                )
+            }
+            EmulatorError::InvalidHarmonic => {
+                write!(f, "Order of the harmonic must be positive")
+            }
             EmulatorError::NotRecorded => write!(f, "Not recorded"),
+            EmulatorError::InvalidPercentile(q) => {
+                write!(f, "Percentile ({}) must be in [0, 100]", q)
+            }
+            EmulatorError::FieldLengthMismatch { expected, actual } => {
+                write!(
+                    f,
+                    "Length of the field ({}) does not match the number of grid points ({})",
+                    actual, expected
+                )
+            }
+            EmulatorError::NoPeak => write!(f, "Field has no peak"),
+            EmulatorError::InvalidMesh(msg) => write!(f, "Invalid mesh: {}", msg),
+            EmulatorError::InvalidAdaptiveRange(msg) => {
+                write!(f, "Invalid adaptive range: {}", msg)
+            }
+            EmulatorError::InvalidAngularSpectrum(msg) => {
+                write!(f, "Angular spectrum method is not applicable: {}", msg)
+            }
+            EmulatorError::MemoryLimitsExceeded { size, limit } => {
+                write!(
+                    f,
+                    "Required memory ({} bytes) exceeds the memory limits hint ({} bytes)",
+                    size, limit
+                )
+            }
+            EmulatorError::Io(e) => write!(f, "{}", e),
             EmulatorError::SamplingConfig(e) => write!(f, "{}", e),
             EmulatorError::Driver(e) => write!(f, "{}", e),
             #[cfg(feature = "gpu")]
+            EmulatorError::GpuBufferTooLarge { size, limit } => {
+                write!(
+                    f,
+                    "Buffer size ({} bytes) exceeds the limit of the GPU ({} bytes)",
+                    size, limit
+                )
+            }
+            #[cfg(feature = "gpu")]
+            EmulatorError::GpuAdapterNotFound { index, count } => {
+                write!(
+                    f,
+                    "Adapter index ({}) is out of range ({} adapters available)",
+                    index, count
+                )
+            }
+            #[cfg(feature = "gpu")]
             EmulatorError::RequestDeviceError(e) => write!(f, "{}", e),
             #[cfg(feature = "gpu")]
             EmulatorError::BufferAsyncError(e) => write!(f, "{}", e),
@@ -67,6 +167,7 @@ impl std::fmt::Display for EmulatorError {
 impl std::error::Error for EmulatorError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
+            EmulatorError::Io(e) => Some(e),
             EmulatorError::SamplingConfig(e) => Some(e),
             EmulatorError::Driver(e) => Some(e),
             #[cfg(feature = "gpu")]
@@ -84,6 +185,12 @@ impl std::error::Error for EmulatorError {
     }
 }
 
+impl From<std::io::Error> for EmulatorError {
+    fn from(e: std::io::Error) -> Self {
+        EmulatorError::Io(e)
+    }
+}
+
 impl From<SamplingConfigError> for EmulatorError {
     fn from(e: SamplingConfigError) -> Self {
         EmulatorError::SamplingConfig(e)
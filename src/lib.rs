@@ -5,17 +5,26 @@
 
 //! This crate provides a emulator for autd3 that calculates sound field, emulates of firmware, etc.
 
+mod analysis;
 mod error;
 mod option;
 mod record;
 mod utils;
 
+pub use analysis::*;
 pub use error::EmulatorError;
 pub use option::*;
 #[cfg(feature = "polars")]
 use polars::{df, frame::DataFrame};
 use record::TransducerRecord;
-pub use record::{Instant, InstantRecordOption, Record, Rms, RmsRecordOption};
+pub use record::{
+    AdaptiveField, AdaptiveNode, AdaptiveRange, Directivity, EmissionModel, Field, Gorkov,
+    GorkovRecordOption, Instant, InstantRecordOption, Interpolation, Lobe, OutputUnit, Phasor,
+    PhasorFormat, PhasorRecordOption, Precision, Propagation, Record, Reflector, Rms,
+    RmsRecordOption, Scatterer, Statistic,
+};
+#[cfg(feature = "gpu")]
+pub use record::{GpuContext, GpuOption};
 
 use std::time::Duration;
 
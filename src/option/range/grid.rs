@@ -0,0 +1,122 @@
+use autd3::driver::geometry::{Point3, Vector3};
+
+/// An axis of [`Grid`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridAxis {
+    /// Displacement between adjacent points along the axis \[mm\].
+    pub step: Vector3,
+    /// Number of points along the axis.
+    pub len: usize,
+}
+
+/// A regular grid of points.
+///
+/// The axes are ordered from the slowest to the fastest varying one in [`Range::points`], which is the same as [`Range::axes`].
+///
+/// [`Range::points`]: crate::Range::points
+/// [`Range::axes`]: crate::Range::axes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    /// Position of the first point.
+    pub origin: Point3,
+    /// Axes of the grid.
+    pub axes: Vec<GridAxis>,
+}
+
+impl Grid {
+    /// Returns the number of points.
+    pub fn len(&self) -> usize {
+        self.axes.iter().map(|axis| axis.len).product()
+    }
+
+    /// Returns true if the grid has no points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the flat index of the point at the specified index along each axis.
+    pub fn flat_index(&self, index: &[usize]) -> usize {
+        index
+            .iter()
+            .zip(self.axes.iter())
+            .fold(0, |acc, (&i, axis)| acc * axis.len + i)
+    }
+
+    /// Returns the index along each axis of the point at the specified flat index.
+    pub fn unflat_index(&self, mut index: usize) -> Vec<usize> {
+        let mut indices = self
+            .axes
+            .iter()
+            .rev()
+            .map(|axis| {
+                let i = index % axis.len;
+                index /= axis.len;
+                i
+            })
+            .collect::<Vec<_>>();
+        indices.reverse();
+        indices
+    }
+
+    // Returns the difference of the flat index between adjacent points along the axis.
+    pub(crate) fn stride(&self, axis: usize) -> usize {
+        self.axes[axis + 1..].iter().map(|axis| axis.len).product()
+    }
+
+    /// Returns the position at the specified (possibly fractional) index along each axis.
+    pub fn position(&self, index: &[f32]) -> Point3 {
+        index
+            .iter()
+            .zip(self.axes.iter())
+            .fold(self.origin, |acc, (&i, axis)| acc + axis.step * i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> Grid {
+        Grid {
+            origin: Point3::new(1., 2., 3.),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.),
+                    len: 3,
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 2.),
+                    len: 2,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn len() {
+        assert_eq!(6, grid().len());
+        assert!(!grid().is_empty());
+    }
+
+    #[rstest::rstest]
+    #[case(0, vec![0, 0])]
+    #[case(4, vec![2, 0])]
+    #[case(3, vec![1, 1])]
+    #[test]
+    fn flat_index(#[case] flat: usize, #[case] index: Vec<usize>) {
+        let grid = grid();
+        assert_eq!(flat, grid.flat_index(&index));
+        assert_eq!(index, grid.unflat_index(flat));
+    }
+
+    #[test]
+    fn stride() {
+        assert_eq!(2, grid().stride(0));
+        assert_eq!(1, grid().stride(1));
+    }
+
+    #[test]
+    fn position() {
+        assert_eq!(Point3::new(2.5, 2., 5.), grid().position(&[1.5, 1.]));
+    }
+}
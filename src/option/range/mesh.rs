@@ -0,0 +1,876 @@
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    path::Path,
+};
+
+use autd3::driver::geometry::Point3;
+
+use crate::{EmulatorError, utils::aabb::Aabb};
+
+use super::Range;
+
+/// Where the field is observed on a [`MeshRange`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum MeshSampling {
+    /// At each vertex.
+    #[default]
+    Vertices,
+    /// At the centroid of each face.
+    Centroids,
+}
+
+/// A range of points on a triangle mesh.
+///
+/// The mesh can be loaded from OBJ, STL (ASCII or binary) and PLY (ASCII or binary) files.
+/// The coordinates in the file are interpreted in millimeters.
+#[derive(Clone, Debug, Default)]
+pub struct MeshRange {
+    /// Vertices of the mesh.
+    pub vertices: Vec<Point3>,
+    /// Triangular faces as indices into `vertices`.
+    pub faces: Vec<[usize; 3]>,
+    /// Where the field is observed.
+    pub sampling: MeshSampling,
+}
+
+fn invalid(msg: impl Into<String>) -> EmulatorError {
+    EmulatorError::InvalidMesh(msg.into())
+}
+
+fn parse<T: std::str::FromStr>(token: Option<&str>) -> Result<T, EmulatorError> {
+    let token = token.ok_or_else(|| invalid("unexpected end of line"))?;
+    token
+        .parse()
+        .map_err(|_| invalid(format!("failed to parse \"{}\"", token)))
+}
+
+// Converts a vertex index or a list length in PLY, which is at most `uint` (32 bits), into `usize`.
+fn ply_index(v: f64) -> Result<usize, EmulatorError> {
+    if !(0. ..=u32::MAX as f64).contains(&v) || v.fract() != 0. {
+        return Err(invalid(format!("invalid index or length \"{}\"", v)));
+    }
+    Ok(v as usize)
+}
+
+fn triangulate(polygon: &[usize], faces: &mut Vec<[usize; 3]>) {
+    (1..polygon.len().saturating_sub(1))
+        .for_each(|i| faces.push([polygon[0], polygon[i], polygon[i + 1]]));
+}
+
+impl MeshRange {
+    /// Loads a mesh from the file. The format is determined by the extension (`obj`, `stl` or `ply`).
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, EmulatorError> {
+        let path = path.as_ref();
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+        let open =
+            || -> Result<_, EmulatorError> { Ok(BufReader::new(std::fs::File::open(path)?)) };
+        match ext.as_deref() {
+            Some("obj") => Self::read_obj(open()?),
+            Some("stl") => Self::read_stl(open()?),
+            Some("ply") => Self::read_ply(open()?),
+            _ => Err(invalid(format!(
+                "unsupported file extension: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Reads a mesh in Wavefront OBJ format. Polygonal faces are triangulated as a fan.
+    pub fn read_obj(reader: impl BufRead) -> Result<Self, EmulatorError> {
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("v") => {
+                    let x = parse(tokens.next())?;
+                    let y = parse(tokens.next())?;
+                    let z = parse(tokens.next())?;
+                    vertices.push(Point3::new(x, y, z));
+                }
+                Some("f") => {
+                    let polygon = tokens
+                        .map(|token| {
+                            let i: isize = parse(token.split('/').next())?;
+                            let idx = match i {
+                                i if i > 0 => i - 1,
+                                i if i < 0 => vertices.len() as isize + i,
+                                _ => return Err(invalid("vertex index must not be 0")),
+                            };
+                            if idx < 0 || idx as usize >= vertices.len() {
+                                return Err(invalid(format!("vertex index {} out of range", i)));
+                            }
+                            Ok(idx as usize)
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    triangulate(&polygon, &mut faces);
+                }
+                _ => {}
+            }
+        }
+        Ok(Self {
+            vertices,
+            faces,
+            sampling: MeshSampling::default(),
+        })
+    }
+
+    /// Reads a mesh in STL format (ASCII or binary). Coincident vertices are merged.
+    pub fn read_stl(mut reader: impl Read) -> Result<Self, EmulatorError> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let is_binary = buf.len() >= 84 && {
+            let n = u32::from_le_bytes([buf[80], buf[81], buf[82], buf[83]]) as usize;
+            buf.len() == 84 + 50 * n
+        };
+
+        let triangles = if is_binary {
+            buf[84..]
+                .chunks_exact(50)
+                .map(|tri| {
+                    let f = |i: usize| {
+                        let o = 12 + 4 * i;
+                        f32::from_le_bytes([tri[o], tri[o + 1], tri[o + 2], tri[o + 3]])
+                    };
+                    [
+                        Point3::new(f(0), f(1), f(2)),
+                        Point3::new(f(3), f(4), f(5)),
+                        Point3::new(f(6), f(7), f(8)),
+                    ]
+                })
+                .collect::<Vec<_>>()
+        } else {
+            let text = std::str::from_utf8(&buf).map_err(|_| invalid("invalid STL file"))?;
+            let points = text
+                .lines()
+                .map(|line| line.split_whitespace())
+                .filter_map(|mut tokens| (tokens.next() == Some("vertex")).then_some(tokens))
+                .map(|mut tokens| {
+                    let x = parse(tokens.next())?;
+                    let y = parse(tokens.next())?;
+                    let z = parse(tokens.next())?;
+                    Ok(Point3::new(x, y, z))
+                })
+                .collect::<Result<Vec<_>, EmulatorError>>()?;
+            if points.len() % 3 != 0 {
+                return Err(invalid("number of vertices is not a multiple of 3"));
+            }
+            points
+                .chunks_exact(3)
+                .map(|tri| [tri[0], tri[1], tri[2]])
+                .collect()
+        };
+
+        let mut vertices = Vec::new();
+        let mut table = HashMap::new();
+        let faces = triangles
+            .into_iter()
+            .map(|tri| {
+                tri.map(|p| {
+                    *table
+                        .entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+                        .or_insert_with(|| {
+                            vertices.push(p);
+                            vertices.len() - 1
+                        })
+                })
+            })
+            .collect();
+        Ok(Self {
+            vertices,
+            faces,
+            sampling: MeshSampling::default(),
+        })
+    }
+
+    /// Reads a mesh in PLY format (ASCII or binary). Polygonal faces are triangulated as a fan.
+    pub fn read_ply(mut reader: impl BufRead) -> Result<Self, EmulatorError> {
+        let header = ply::Header::read(&mut reader)?;
+        let mut body = ply::Body::new(header.format, reader);
+
+        let mut vertices = Vec::new();
+        let mut faces = Vec::new();
+        for element in header.elements.iter() {
+            match element.name.as_str() {
+                "vertex" => {
+                    let index_of = |name: &str| {
+                        element
+                            .properties
+                            .iter()
+                            .position(|p| p.name == name)
+                            .ok_or_else(|| invalid(format!("vertex has no property \"{}\"", name)))
+                    };
+                    let (ix, iy, iz) = (index_of("x")?, index_of("y")?, index_of("z")?);
+                    for _ in 0..element.count {
+                        let mut v = [0.; 3];
+                        for (i, property) in element.properties.iter().enumerate() {
+                            let values = body.read_property(property)?;
+                            if let Some(dst) = [ix, iy, iz].iter().position(|&j| j == i) {
+                                v[dst] = values[0] as f32;
+                            }
+                        }
+                        vertices.push(Point3::new(v[0], v[1], v[2]));
+                    }
+                }
+                "face" => {
+                    for _ in 0..element.count {
+                        for property in element.properties.iter() {
+                            let values = body.read_property(property)?;
+                            if matches!(property.name.as_str(), "vertex_indices" | "vertex_index") {
+                                let polygon = values
+                                    .into_iter()
+                                    .map(ply_index)
+                                    .collect::<Result<Vec<_>, _>>()?;
+                                triangulate(&polygon, &mut faces);
+                            }
+                        }
+                    }
+                }
+                _ => {
+                    for _ in 0..element.count {
+                        for property in element.properties.iter() {
+                            body.read_property(property)?;
+                        }
+                    }
+                }
+            }
+        }
+
+        if faces.iter().flatten().any(|&i| i >= vertices.len()) {
+            return Err(invalid("vertex index out of range"));
+        }
+        Ok(Self {
+            vertices,
+            faces,
+            sampling: MeshSampling::default(),
+        })
+    }
+
+    /// Returns the centroids of the faces.
+    pub fn centroids(&self) -> impl Iterator<Item = Point3> + '_ {
+        self.faces.iter().map(|f| {
+            Point3::from(
+                (self.vertices[f[0]].coords
+                    + self.vertices[f[1]].coords
+                    + self.vertices[f[2]].coords)
+                    / 3.,
+            )
+        })
+    }
+
+    /// Returns the number of observation points.
+    pub fn len(&self) -> usize {
+        match self.sampling {
+            MeshSampling::Vertices => self.vertices.len(),
+            MeshSampling::Centroids => self.faces.len(),
+        }
+    }
+
+    /// Returns true if there are no observation points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Converts values at the observation points into per-vertex values.
+    ///
+    /// If the field is observed at the centroids, the value of each vertex is the mean of the values of the faces sharing it.
+    pub fn vertex_attribute(&self, values: &[f32]) -> Result<Vec<f32>, EmulatorError> {
+        if values.len() != self.len() {
+            return Err(EmulatorError::FieldLengthMismatch {
+                expected: self.len(),
+                actual: values.len(),
+            });
+        }
+        match self.sampling {
+            MeshSampling::Vertices => Ok(values.to_vec()),
+            MeshSampling::Centroids => {
+                let mut sum = vec![0.; self.vertices.len()];
+                let mut count = vec![0usize; self.vertices.len()];
+                self.faces.iter().zip(values).for_each(|(f, &v)| {
+                    f.iter().for_each(|&i| {
+                        sum[i] += v;
+                        count[i] += 1;
+                    })
+                });
+                Ok(sum
+                    .into_iter()
+                    .zip(count)
+                    .map(|(s, c)| if c == 0 { f32::NAN } else { s / c as f32 })
+                    .collect())
+            }
+        }
+    }
+
+    /// Writes the mesh in ASCII PLY format with the values at the observation points as per-vertex float properties.
+    pub fn write_ply(
+        &self,
+        mut writer: impl Write,
+        attributes: &[(&str, &[f32])],
+    ) -> Result<(), EmulatorError> {
+        let attributes = attributes
+            .iter()
+            .map(|(name, values)| Ok((*name, self.vertex_attribute(values)?)))
+            .collect::<Result<Vec<_>, EmulatorError>>()?;
+
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format ascii 1.0")?;
+        writeln!(writer, "element vertex {}", self.vertices.len())?;
+        writeln!(writer, "property float x")?;
+        writeln!(writer, "property float y")?;
+        writeln!(writer, "property float z")?;
+        attributes
+            .iter()
+            .try_for_each(|(name, _)| writeln!(writer, "property float {}", name))?;
+        writeln!(writer, "element face {}", self.faces.len())?;
+        writeln!(writer, "property list uchar int vertex_indices")?;
+        writeln!(writer, "end_header")?;
+        self.vertices.iter().enumerate().try_for_each(|(i, v)| {
+            write!(writer, "{} {} {}", v.x, v.y, v.z)?;
+            attributes
+                .iter()
+                .try_for_each(|(_, values)| write!(writer, " {}", values[i]))?;
+            writeln!(writer)
+        })?;
+        self.faces
+            .iter()
+            .try_for_each(|f| writeln!(writer, "3 {} {} {}", f[0], f[1], f[2]))?;
+        Ok(())
+    }
+
+    /// Saves the mesh in ASCII PLY format with per-vertex attributes. See [`MeshRange::write_ply`].
+    pub fn save_ply(
+        &self,
+        path: impl AsRef<Path>,
+        attributes: &[(&str, &[f32])],
+    ) -> Result<(), EmulatorError> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_ply(&mut writer, attributes)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Range for MeshRange {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        let vertices = match self.sampling {
+            MeshSampling::Vertices => self.vertices.as_slice(),
+            MeshSampling::Centroids => &[],
+        };
+        let centroids = match self.sampling {
+            MeshSampling::Vertices => None,
+            MeshSampling::Centroids => Some(self.centroids()),
+        };
+        vertices
+            .iter()
+            .copied()
+            .chain(centroids.into_iter().flatten())
+            .map(|p| (p.x, p.y, p.z))
+    }
+
+    fn aabb(&self) -> Aabb {
+        self.points().fold(Aabb::empty(), |aabb, (x, y, z)| {
+            aabb.grow(Point3::new(x, y, z))
+        })
+    }
+}
+
+mod ply {
+    use std::io::BufRead;
+
+    use crate::EmulatorError;
+
+    use super::{invalid, parse, ply_index};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub(super) enum Format {
+        Ascii,
+        BinaryLittleEndian,
+        BinaryBigEndian,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub(super) enum Scalar {
+        I8,
+        U8,
+        I16,
+        U16,
+        I32,
+        U32,
+        F32,
+        F64,
+    }
+
+    impl Scalar {
+        fn from_name(name: &str) -> Result<Self, EmulatorError> {
+            Ok(match name {
+                "char" | "int8" => Scalar::I8,
+                "uchar" | "uint8" => Scalar::U8,
+                "short" | "int16" => Scalar::I16,
+                "ushort" | "uint16" => Scalar::U16,
+                "int" | "int32" => Scalar::I32,
+                "uint" | "uint32" => Scalar::U32,
+                "float" | "float32" => Scalar::F32,
+                "double" | "float64" => Scalar::F64,
+                _ => return Err(invalid(format!("unknown PLY type \"{}\"", name))),
+            })
+        }
+
+        const fn size(&self) -> usize {
+            match self {
+                Scalar::I8 | Scalar::U8 => 1,
+                Scalar::I16 | Scalar::U16 => 2,
+                Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+                Scalar::F64 => 8,
+            }
+        }
+    }
+
+    #[derive(Clone, Debug)]
+    pub(super) struct Property {
+        pub name: String,
+        pub list: Option<Scalar>,
+        pub ty: Scalar,
+    }
+
+    #[derive(Clone, Debug)]
+    pub(super) struct Element {
+        pub name: String,
+        pub count: usize,
+        pub properties: Vec<Property>,
+    }
+
+    #[derive(Clone, Debug)]
+    pub(super) struct Header {
+        pub format: Format,
+        pub elements: Vec<Element>,
+    }
+
+    impl Header {
+        pub fn read(reader: &mut impl BufRead) -> Result<Self, EmulatorError> {
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            if line.trim() != "ply" {
+                return Err(invalid("invalid PLY file"));
+            }
+            let mut format = None;
+            let mut elements: Vec<Element> = Vec::new();
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 {
+                    return Err(invalid("unexpected end of PLY header"));
+                }
+                let mut tokens = line.split_whitespace();
+                match tokens.next() {
+                    Some("format") => {
+                        format = Some(match tokens.next() {
+                            Some("ascii") => Format::Ascii,
+                            Some("binary_little_endian") => Format::BinaryLittleEndian,
+                            Some("binary_big_endian") => Format::BinaryBigEndian,
+                            _ => return Err(invalid("unknown PLY format")),
+                        })
+                    }
+                    Some("element") => {
+                        let name = tokens
+                            .next()
+                            .ok_or_else(|| invalid("element has no name"))?
+                            .to_owned();
+                        let count = parse(tokens.next())?;
+                        elements.push(Element {
+                            name,
+                            count,
+                            properties: Vec::new(),
+                        });
+                    }
+                    Some("property") => {
+                        let element = elements
+                            .last_mut()
+                            .ok_or_else(|| invalid("property without element"))?;
+                        let property = match tokens.next() {
+                            Some("list") => {
+                                let len = Scalar::from_name(tokens.next().unwrap_or_default())?;
+                                let ty = Scalar::from_name(tokens.next().unwrap_or_default())?;
+                                Property {
+                                    name: tokens.next().unwrap_or_default().to_owned(),
+                                    list: Some(len),
+                                    ty,
+                                }
+                            }
+                            ty => Property {
+                                ty: Scalar::from_name(ty.unwrap_or_default())?,
+                                name: tokens.next().unwrap_or_default().to_owned(),
+                                list: None,
+                            },
+                        };
+                        element.properties.push(property);
+                    }
+                    Some("end_header") => break,
+                    _ => {}
+                }
+            }
+            Ok(Self {
+                format: format.ok_or_else(|| invalid("PLY format is not specified"))?,
+                elements,
+            })
+        }
+    }
+
+    pub(super) struct Body<R: BufRead> {
+        format: Format,
+        reader: R,
+        tokens: std::collections::VecDeque<String>,
+    }
+
+    impl<R: BufRead> Body<R> {
+        pub fn new(format: Format, reader: R) -> Self {
+            Self {
+                format,
+                reader,
+                tokens: std::collections::VecDeque::new(),
+            }
+        }
+
+        fn read_scalar(&mut self, ty: Scalar) -> Result<f64, EmulatorError> {
+            if self.format == Format::Ascii {
+                while self.tokens.is_empty() {
+                    let mut line = String::new();
+                    if self.reader.read_line(&mut line)? == 0 {
+                        return Err(invalid("unexpected end of PLY file"));
+                    }
+                    self.tokens
+                        .extend(line.split_whitespace().map(str::to_owned));
+                }
+                let token = self.tokens.pop_front();
+                return parse(token.as_deref());
+            }
+
+            let mut buf = [0u8; 8];
+            let buf = &mut buf[..ty.size()];
+            self.reader.read_exact(buf).map_err(|e| match e.kind() {
+                std::io::ErrorKind::UnexpectedEof => invalid("unexpected end of PLY file"),
+                _ => e.into(),
+            })?;
+            if self.format == Format::BinaryBigEndian {
+                buf.reverse();
+            }
+            Ok(match ty {
+                Scalar::I8 => buf[0] as i8 as f64,
+                Scalar::U8 => buf[0] as f64,
+                Scalar::I16 => i16::from_le_bytes([buf[0], buf[1]]) as f64,
+                Scalar::U16 => u16::from_le_bytes([buf[0], buf[1]]) as f64,
+                Scalar::I32 => i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                Scalar::U32 => u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                Scalar::F32 => f32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]) as f64,
+                Scalar::F64 => f64::from_le_bytes([
+                    buf[0], buf[1], buf[2], buf[3], buf[4], buf[5], buf[6], buf[7],
+                ]),
+            })
+        }
+
+        pub fn read_property(&mut self, property: &Property) -> Result<Vec<f64>, EmulatorError> {
+            match property.list {
+                Some(len) => {
+                    let len = ply_index(self.read_scalar(len)?)?;
+                    (0..len).map(|_| self.read_scalar(property.ty)).collect()
+                }
+                None => Ok(vec![self.read_scalar(property.ty)?]),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OBJ: &str = "# square
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vn 0 0 1
+f 1//1 2//1 3//1 4//1
+";
+
+    fn square() -> MeshRange {
+        MeshRange {
+            vertices: vec![
+                Point3::new(0., 0., 0.),
+                Point3::new(1., 0., 0.),
+                Point3::new(1., 1., 0.),
+                Point3::new(0., 1., 0.),
+            ],
+            faces: vec![[0, 1, 2], [0, 2, 3]],
+            sampling: MeshSampling::Vertices,
+        }
+    }
+
+    fn assert_mesh_eq(expect: &MeshRange, actual: &MeshRange) {
+        assert_eq!(expect.vertices, actual.vertices);
+        assert_eq!(expect.faces, actual.faces);
+    }
+
+    #[test]
+    fn read_obj() -> Result<(), EmulatorError> {
+        assert_mesh_eq(&square(), &MeshRange::read_obj(OBJ.as_bytes())?);
+        assert_mesh_eq(
+            &square(),
+            &MeshRange::read_obj(
+                "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf -4 -3 -2\nf 1 3 4\n".as_bytes(),
+            )?,
+        );
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("f 0 1 2")]
+    #[case("f 1 2 5")]
+    #[case("v 0 0")]
+    #[case("v 0 0 a")]
+    fn read_obj_invalid(#[case] line: &str) {
+        let src = format!("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n{}\n", line);
+        assert!(MeshRange::read_obj(src.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn read_stl_ascii() -> Result<(), EmulatorError> {
+        let src = "solid square
+facet normal 0 0 1
+ outer loop
+  vertex 0 0 0
+  vertex 1 0 0
+  vertex 1 1 0
+ endloop
+endfacet
+facet normal 0 0 1
+ outer loop
+  vertex 0 0 0
+  vertex 1 1 0
+  vertex 0 1 0
+ endloop
+endfacet
+endsolid square
+";
+        assert_mesh_eq(&square(), &MeshRange::read_stl(src.as_bytes())?);
+        Ok(())
+    }
+
+    #[test]
+    fn read_stl_binary() -> Result<(), EmulatorError> {
+        let mesh = square();
+        let mut buf = b"solid".to_vec();
+        buf.resize(80, 0);
+        buf.extend_from_slice(&(mesh.faces.len() as u32).to_le_bytes());
+        mesh.faces.iter().for_each(|f| {
+            [0f32, 0., 1.]
+                .into_iter()
+                .chain(
+                    f.iter().flat_map(|&i| {
+                        [mesh.vertices[i].x, mesh.vertices[i].y, mesh.vertices[i].z]
+                    }),
+                )
+                .for_each(|v| buf.extend_from_slice(&v.to_le_bytes()));
+            buf.extend_from_slice(&[0, 0]);
+        });
+        assert_mesh_eq(&mesh, &MeshRange::read_stl(buf.as_slice())?);
+        Ok(())
+    }
+
+    #[test]
+    fn read_ply_ascii() -> Result<(), EmulatorError> {
+        let src = "ply
+format ascii 1.0
+comment square
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255
+1 0 0 255
+1 1 0 255
+0 1 0 255
+4 0 1 2 3
+";
+        assert_mesh_eq(&square(), &MeshRange::read_ply(src.as_bytes())?);
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("binary_little_endian", false)]
+    #[case("binary_big_endian", true)]
+    fn read_ply_binary(
+        #[case] format: &str,
+        #[case] big_endian: bool,
+    ) -> Result<(), EmulatorError> {
+        let mesh = square();
+        let mut buf = format!(
+            "ply\nformat {} 1.0\nelement vertex 4\nproperty double w\nproperty float x\nproperty float y\nproperty float z\nelement face 2\nproperty list uchar int vertex_indices\nend_header\n",
+            format
+        )
+        .into_bytes();
+        let mut push = |mut bytes: Vec<u8>| {
+            if big_endian {
+                bytes.reverse();
+            }
+            buf.extend_from_slice(&bytes);
+        };
+        mesh.vertices.iter().for_each(|v| {
+            push(1f64.to_le_bytes().to_vec());
+            [v.x, v.y, v.z]
+                .into_iter()
+                .for_each(|v| push(v.to_le_bytes().to_vec()));
+        });
+        mesh.faces.iter().for_each(|f| {
+            push(vec![3]);
+            f.iter()
+                .for_each(|&i| push((i as i32).to_le_bytes().to_vec()));
+        });
+        assert_mesh_eq(&mesh, &MeshRange::read_ply(buf.as_slice())?);
+        Ok(())
+    }
+
+    #[rstest::rstest]
+    #[case("obj\n")]
+    #[case("ply\nend_header\n")]
+    #[case("ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n0\n")]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nend_header\n0 0\n"
+    )]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n3 0 1 2\n"
+    )]
+    #[case("ply\nformat ascii 1.0\nelement vertex 1\nproperty half x\nend_header\n0\n")]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 -1\n"
+    )]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 1.5\n"
+    )]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n-3 0 1 2\n"
+    )]
+    #[case(
+        "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n0 0 0\n1 0 0\n0 1 0\n1e300 0 1 2\n"
+    )]
+    fn read_ply_invalid(#[case] src: &str) {
+        assert!(MeshRange::read_ply(src.as_bytes()).is_err());
+    }
+
+    #[rstest::rstest]
+    #[case("ascii")]
+    #[case("binary_little_endian")]
+    #[case("binary_big_endian")]
+    fn read_ply_truncated(#[case] format: &str) {
+        let src = format!(
+            "ply\nformat {} 1.0\nelement vertex {}\nproperty float x\nproperty float y\nproperty float z\nend_header\n",
+            format,
+            usize::MAX
+        );
+        assert!(matches!(
+            MeshRange::read_ply(src.as_bytes()),
+            Err(EmulatorError::InvalidMesh(_))
+        ));
+    }
+
+    #[test]
+    fn points() {
+        let mut mesh = square();
+        assert_eq!(4, mesh.len());
+        assert_eq!(
+            (
+                vec![0., 1., 1., 0.],
+                vec![0., 0., 1., 1.],
+                vec![0., 0., 0., 0.]
+            ),
+            mesh.points().collect()
+        );
+
+        mesh.sampling = MeshSampling::Centroids;
+        assert_eq!(2, mesh.len());
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = mesh.points().collect();
+        approx::assert_abs_diff_eq!(&[2. / 3., 1. / 3.][..], x.as_slice());
+        approx::assert_abs_diff_eq!(&[1. / 3., 2. / 3.][..], y.as_slice());
+        assert_eq!(vec![0., 0.], z);
+    }
+
+    #[rstest::rstest]
+    #[case(
+        MeshSampling::Vertices,
+        Point3::new(0., 0., 0.),
+        Point3::new(1., 1., 0.)
+    )]
+    #[case(MeshSampling::Centroids, Point3::new(1. / 3., 1. / 3., 0.), Point3::new(2. / 3., 2. / 3., 0.))]
+    fn aabb(#[case] sampling: MeshSampling, #[case] min: Point3, #[case] max: Point3) {
+        let mesh = MeshRange {
+            sampling,
+            ..square()
+        };
+        let aabb = mesh.aabb();
+        approx::assert_abs_diff_eq!(min.x, aabb.min.x);
+        approx::assert_abs_diff_eq!(min.y, aabb.min.y);
+        approx::assert_abs_diff_eq!(max.x, aabb.max.x);
+        approx::assert_abs_diff_eq!(max.y, aabb.max.y);
+        assert_eq!(0., aabb.min.z);
+        assert_eq!(0., aabb.max.z);
+    }
+
+    #[test]
+    fn vertex_attribute() -> Result<(), EmulatorError> {
+        let mut mesh = square();
+        assert_eq!(
+            vec![1., 2., 3., 4.],
+            mesh.vertex_attribute(&[1., 2., 3., 4.])?
+        );
+
+        mesh.sampling = MeshSampling::Centroids;
+        assert_eq!(vec![2., 1., 2., 3.], mesh.vertex_attribute(&[1., 3.])?);
+
+        assert!(mesh.vertex_attribute(&[1., 2., 3., 4.]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn write_ply() -> Result<(), EmulatorError> {
+        let mesh = MeshRange {
+            sampling: MeshSampling::Centroids,
+            ..square()
+        };
+        let mut buf = Vec::new();
+        mesh.write_ply(&mut buf, &[("p", &[1., 3.])])?;
+
+        let text = String::from_utf8(buf.clone()).unwrap();
+        assert!(text.contains("property float p\n"));
+        assert!(text.contains("\n1 0 0 1\n"));
+        assert_mesh_eq(&mesh, &MeshRange::read_ply(buf.as_slice())?);
+        Ok(())
+    }
+
+    #[test]
+    fn load() -> Result<(), EmulatorError> {
+        let dir = std::env::temp_dir().join(format!("autd3-emulator-mesh-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+
+        let obj = dir.join("square.obj");
+        std::fs::write(&obj, OBJ)?;
+        assert_mesh_eq(&square(), &MeshRange::load(&obj)?);
+
+        let ply = dir.join("square.ply");
+        square().save_ply(&ply, &[])?;
+        assert_mesh_eq(&square(), &MeshRange::load(&ply)?);
+
+        assert!(MeshRange::load(dir.join("square.txt")).is_err());
+        assert!(MeshRange::load(dir.join("none.obj")).is_err());
+
+        std::fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -1,19 +1,111 @@
+mod grid;
+mod mesh;
 mod range_0d;
 mod range_1d;
 mod range_2d;
 mod range_3d;
 mod range_iter;
+mod range_oriented;
+mod range_polar;
 
+pub use grid::{Grid, GridAxis};
+pub use mesh::{MeshRange, MeshSampling};
 pub use range_1d::*;
 pub use range_2d::*;
 pub use range_3d::*;
+pub use range_oriented::*;
+pub use range_polar::*;
+
+use autd3::driver::geometry::Vector3;
 
 use crate::utils::aabb::Aabb;
 
+/// An axis of a structured [`Range`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct RangeAxis {
+    /// The name of the axis with its unit, e.g., `x[mm]`.
+    pub name: &'static str,
+    /// The coordinates along the axis.
+    pub coords: Vec<f32>,
+    /// The difference of the index in [`Range::points`] between adjacent points along the axis.
+    pub stride: usize,
+}
+
+impl RangeAxis {
+    /// Returns the number of points along the axis.
+    pub fn len(&self) -> usize {
+        self.coords.len()
+    }
+
+    /// Returns true if the axis has no points.
+    pub fn is_empty(&self) -> bool {
+        self.coords.is_empty()
+    }
+}
+
 /// Trait for range.
 pub trait Range {
     /// Gets points in the range.
     fn points(&self) -> impl Iterator<Item = (f32, f32, f32)>;
     /// Gets axis-aligned bounding box of the range.
     fn aabb(&self) -> Aabb;
+
+    /// Gets the axes of the range from the slowest to the fastest varying one when the points are arranged in row-major order.
+    /// The axes of [`RangePlane`] and [`RangeBox`] correspond to [`Grid::axes`] of the range.
+    ///
+    /// Axis-aligned ranges always have three axes, `z[mm]`, `y[mm]` and `x[mm]`, regardless of the iteration order, where fixed coordinates are axes of length 1.
+    /// Unstructured ranges have a single axis `index`.
+    fn axes(&self) -> Vec<RangeAxis> {
+        vec![RangeAxis {
+            name: "index",
+            coords: (0..self.points().count()).map(|i| i as f32).collect(),
+            stride: 1,
+        }]
+    }
+
+    /// Gets the number of points along each axis of [`Range::axes`].
+    fn shape(&self) -> Vec<usize> {
+        self.axes().iter().map(RangeAxis::len).collect()
+    }
+}
+
+pub(crate) fn linspace(start: f32, n: usize, resolution: f32) -> Vec<f32> {
+    (0..n).map(|i| start + resolution * i as f32).collect()
+}
+
+// The axes of a range on `grid`, where `axes` are the name and the coordinate of the first point of each axis of the grid.
+pub(crate) fn grid_axes(grid: &Grid, axes: &[(&'static str, f32)]) -> Vec<RangeAxis> {
+    grid.axes
+        .iter()
+        .zip(axes.iter())
+        .enumerate()
+        .map(|(a, (axis, &(name, start)))| RangeAxis {
+            name,
+            coords: linspace(start, axis.len, axis.step.norm()),
+            stride: grid.stride(a),
+        })
+        .collect()
+}
+
+// The axes of an axis-aligned range on `grid`, which are always `z`, `y` and `x` with the fixed coordinates of `grid.origin` as axes of length 1.
+pub(crate) fn cartesian_axes(grid: &Grid) -> Vec<RangeAxis> {
+    [("z[mm]", 2), ("y[mm]", 1), ("x[mm]", 0)]
+        .into_iter()
+        .map(|(name, c)| {
+            let component = |v: Vector3| [v.x, v.y, v.z][c];
+            let start = component(grid.origin.coords);
+            match grid.axes.iter().position(|axis| component(axis.step) != 0.) {
+                Some(a) => RangeAxis {
+                    name,
+                    coords: linspace(start, grid.axes[a].len, component(grid.axes[a].step)),
+                    stride: grid.stride(a),
+                },
+                None => RangeAxis {
+                    name,
+                    coords: vec![start],
+                    stride: 1,
+                },
+            }
+        })
+        .collect()
 }
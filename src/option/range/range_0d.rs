@@ -2,7 +2,7 @@ use autd3::driver::geometry::Point3;
 
 use crate::utils::aabb::Aabb;
 
-use super::Range;
+use super::{Grid, Range, RangeAxis, cartesian_axes};
 
 impl Range for Point3 {
     fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
@@ -15,6 +15,13 @@ impl Range for Point3 {
             max: *self,
         }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid {
+            origin: *self,
+            axes: Vec::new(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -1,8 +1,8 @@
 use crate::utils::aabb::Aabb;
 
-use autd3::driver::geometry::Vector3;
+use autd3::driver::geometry::{Point3, Vector3};
 
-use super::Range;
+use super::{Grid, GridAxis, Range, RangeAxis, cartesian_axes};
 
 /// A range of 1D space along the x axis.
 #[derive(Clone, Debug)]
@@ -41,6 +41,10 @@ impl Range for RangeX {
         let max = Vector3::new(*self.x.end(), self.y, self.z).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 1D space along the y axis.
@@ -81,6 +85,10 @@ impl Range for RangeY {
         let max = Vector3::new(self.x, *self.y.end(), self.z).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 1D space along the z axis.
@@ -121,6 +129,46 @@ impl Range for RangeZ {
         let max = Vector3::new(self.x, self.y, *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
+}
+
+impl From<&RangeX> for Grid {
+    fn from(range: &RangeX) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), range.y, range.z),
+            axes: vec![GridAxis {
+                step: Vector3::new(1., 0., 0.) * range.resolution,
+                len: range.nx(),
+            }],
+        }
+    }
+}
+
+impl From<&RangeY> for Grid {
+    fn from(range: &RangeY) -> Self {
+        Self {
+            origin: Point3::new(range.x, *range.y.start(), range.z),
+            axes: vec![GridAxis {
+                step: Vector3::new(0., 1., 0.) * range.resolution,
+                len: range.ny(),
+            }],
+        }
+    }
+}
+
+impl From<&RangeZ> for Grid {
+    fn from(range: &RangeZ) -> Self {
+        Self {
+            origin: Point3::new(range.x, range.y, *range.z.start()),
+            axes: vec![GridAxis {
+                step: Vector3::new(0., 0., 1.) * range.resolution,
+                len: range.nz(),
+            }],
+        }
+    }
 }
 
 #[cfg(test)]
@@ -264,4 +312,42 @@ mod tests {
         assert_eq!(iter.next(), Some((1.0, 2.0, 2.0)));
         assert_eq!(iter.next(), None);
     }
+
+    fn check_grid<R: Range>(range: R)
+    where
+        for<'a> Grid: From<&'a R>,
+    {
+        let grid = Grid::from(&range);
+        assert_eq!(range.points().count(), grid.len());
+        range.points().enumerate().for_each(|(i, (x, y, z))| {
+            let index = grid
+                .unflat_index(i)
+                .into_iter()
+                .map(|i| i as f32)
+                .collect::<Vec<_>>();
+            assert_eq!(Point3::new(x, y, z), grid.position(&index));
+        });
+    }
+
+    #[test]
+    fn test_grid() {
+        check_grid(RangeX {
+            x: 0.0..=2.,
+            y: 1.,
+            z: 2.,
+            resolution: 1.,
+        });
+        check_grid(RangeY {
+            x: 1.,
+            y: 0.0..=2.,
+            z: 2.,
+            resolution: 1.,
+        });
+        check_grid(RangeZ {
+            x: 1.,
+            y: 2.,
+            z: 0.0..=2.,
+            resolution: 1.,
+        });
+    }
 }
@@ -1,8 +1,8 @@
 use crate::utils::aabb::Aabb;
 
-use autd3::driver::geometry::Vector3;
+use autd3::driver::geometry::{Point3, Vector3};
 
-use super::Range;
+use super::{Grid, GridAxis, Range, RangeAxis, cartesian_axes};
 
 /// A range of 2D space iterating in the order of x-y.
 #[derive(Clone, Debug)]
@@ -50,6 +50,10 @@ impl Range for RangeXY {
         let max = Vector3::new(*self.x.end(), *self.y.end(), self.z).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 2D space iterating in the order of x-z.
@@ -98,6 +102,10 @@ impl Range for RangeXZ {
         let max = Vector3::new(*self.x.end(), self.y, *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 2D space iterating in the order of y-x.
@@ -146,6 +154,10 @@ impl Range for RangeYX {
         let max = Vector3::new(*self.x.end(), *self.y.end(), self.z).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 2D space iterating in the order of y-z.
@@ -194,6 +206,10 @@ impl Range for RangeYZ {
         let max = Vector3::new(self.x, *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 2D space iterating in the order of z-x.
@@ -242,6 +258,10 @@ impl Range for RangeZX {
         let max = Vector3::new(*self.x.end(), self.y, *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 2D space iterating in the order of z-y.
@@ -290,6 +310,118 @@ impl Range for RangeZY {
         let max = Vector3::new(self.x, *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
+}
+
+impl From<&RangeXY> for Grid {
+    fn from(range: &RangeXY) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), range.z),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeXZ> for Grid {
+    fn from(range: &RangeXZ) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), range.y, *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeYX> for Grid {
+    fn from(range: &RangeYX) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), range.z),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeYZ> for Grid {
+    fn from(range: &RangeYZ) -> Self {
+        Self {
+            origin: Point3::new(range.x, *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeZX> for Grid {
+    fn from(range: &RangeZX) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), range.y, *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeZY> for Grid {
+    fn from(range: &RangeZY) -> Self {
+        Self {
+            origin: Point3::new(range.x, *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+            ],
+        }
+    }
 }
 
 #[cfg(test)]
@@ -677,4 +809,90 @@ mod tests {
         assert_eq!(iter.next(), Some((5.0, 1.0, 1.0)));
         assert_eq!(iter.next(), None);
     }
+
+    fn check_grid<R: Range>(range: R)
+    where
+        for<'a> Grid: From<&'a R>,
+    {
+        let grid = Grid::from(&range);
+        assert_eq!(range.points().count(), grid.len());
+        range.points().enumerate().for_each(|(i, (x, y, z))| {
+            let index = grid
+                .unflat_index(i)
+                .into_iter()
+                .map(|i| i as f32)
+                .collect::<Vec<_>>();
+            assert_eq!(Point3::new(x, y, z), grid.position(&index));
+        });
+    }
+
+    #[test]
+    fn test_grid() {
+        check_grid(RangeXY {
+            x: 1.0..=2.,
+            y: 1.0..=2.,
+            z: 3.,
+            resolution: 0.5,
+        });
+        check_grid(RangeXZ {
+            x: 1.0..=2.,
+            y: 3.,
+            z: 1.0..=2.,
+            resolution: 0.5,
+        });
+        check_grid(RangeYX {
+            x: 1.0..=2.,
+            y: 1.0..=2.,
+            z: 3.,
+            resolution: 0.5,
+        });
+        check_grid(RangeYZ {
+            x: 3.,
+            y: 1.0..=2.,
+            z: 1.0..=2.,
+            resolution: 0.5,
+        });
+        check_grid(RangeZX {
+            x: 1.0..=2.,
+            y: 3.,
+            z: 1.0..=2.,
+            resolution: 0.5,
+        });
+        check_grid(RangeZY {
+            x: 3.,
+            y: 1.0..=2.,
+            z: 1.0..=2.,
+            resolution: 0.5,
+        });
+    }
+
+    #[test]
+    fn test_axes() {
+        let range = RangeYX {
+            x: 1.0..=2.,
+            y: 0.0..=1.,
+            z: 3.,
+            resolution: 0.5,
+        };
+        let axes = range.axes();
+        assert_eq!(
+            vec!["z[mm]", "y[mm]", "x[mm]"],
+            axes.iter().map(|a| a.name).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![3.], axes[0].coords);
+        assert_eq!(vec![0., 0.5, 1.], axes[1].coords);
+        assert_eq!(vec![1., 1.5, 2.], axes[2].coords);
+        assert_eq!(1, axes[1].stride);
+        assert_eq!(3, axes[2].stride);
+        assert_eq!(vec![1, 3, 3], range.shape());
+
+        let (x, y, _): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+        (0..3).for_each(|iy| {
+            (0..3).for_each(|ix| {
+                let i = iy * axes[1].stride + ix * axes[2].stride;
+                assert_eq!(axes[1].coords[iy], y[i]);
+                assert_eq!(axes[2].coords[ix], x[i]);
+            })
+        });
+    }
 }
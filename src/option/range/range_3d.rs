@@ -1,8 +1,8 @@
 use crate::utils::aabb::Aabb;
 
-use autd3::driver::geometry::Vector3;
+use autd3::driver::geometry::{Point3, Vector3};
 
-use super::Range;
+use super::{Grid, GridAxis, Range, RangeAxis, cartesian_axes};
 
 /// A range of 3D space iterating in the order of x-y-z.
 #[derive(Clone, Debug)]
@@ -58,6 +58,10 @@ impl Range for RangeXYZ {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 3D space iterating in the order of x-z-y.
@@ -114,6 +118,10 @@ impl Range for RangeXZY {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 3D space iterating in the order of y-x-z.
@@ -170,6 +178,10 @@ impl Range for RangeYXZ {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 3D space iterating in the order of y-z-x.
@@ -226,6 +238,10 @@ impl Range for RangeYZX {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 3D space iterating in the order of z-x-y.
@@ -282,6 +298,10 @@ impl Range for RangeZXY {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
 }
 
 /// A range of 3D space iterating in the order of z-y-x.
@@ -338,6 +358,142 @@ impl Range for RangeZYX {
         let max = Vector3::new(*self.x.end(), *self.y.end(), *self.z.end()).into();
         Aabb { min, max }
     }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        cartesian_axes(&Grid::from(self))
+    }
+}
+
+impl From<&RangeXYZ> for Grid {
+    fn from(range: &RangeXYZ) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeXZY> for Grid {
+    fn from(range: &RangeXZY) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeYXZ> for Grid {
+    fn from(range: &RangeYXZ) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeYZX> for Grid {
+    fn from(range: &RangeYZX) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeZXY> for Grid {
+    fn from(range: &RangeZXY) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+            ],
+        }
+    }
+}
+
+impl From<&RangeZYX> for Grid {
+    fn from(range: &RangeZYX) -> Self {
+        Self {
+            origin: Point3::new(*range.x.start(), *range.y.start(), *range.z.start()),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(1., 0., 0.) * range.resolution,
+                    len: range.nx(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 1., 0.) * range.resolution,
+                    len: range.ny(),
+                },
+                GridAxis {
+                    step: Vector3::new(0., 0., 1.) * range.resolution,
+                    len: range.nz(),
+                },
+            ],
+        }
+    }
 }
 
 #[cfg(test)]
@@ -628,4 +784,60 @@ mod tests {
         assert_eq!(iter.next(), Some((1.0, 1.0, 1.0)));
         assert_eq!(iter.next(), None);
     }
+
+    fn check_grid<R: Range>(range: R)
+    where
+        for<'a> Grid: From<&'a R>,
+    {
+        let grid = Grid::from(&range);
+        assert_eq!(range.points().count(), grid.len());
+        range.points().enumerate().for_each(|(i, (x, y, z))| {
+            let index = grid
+                .unflat_index(i)
+                .into_iter()
+                .map(|i| i as f32)
+                .collect::<Vec<_>>();
+            assert_eq!(Point3::new(x, y, z), grid.position(&index));
+        });
+    }
+
+    #[test]
+    fn test_grid() {
+        check_grid(RangeXYZ {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+        check_grid(RangeXZY {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+        check_grid(RangeYXZ {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+        check_grid(RangeYZX {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+        check_grid(RangeZXY {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+        check_grid(RangeZYX {
+            x: 0.0..=1.,
+            y: 2.0..=4.,
+            z: 5.0..=5.5,
+            resolution: 0.5,
+        });
+    }
 }
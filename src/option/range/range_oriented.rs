@@ -0,0 +1,348 @@
+use crate::utils::aabb::Aabb;
+
+use autd3::driver::geometry::{Device, Point3, UnitVector3};
+
+use super::{Grid, GridAxis, Range, RangeAxis, grid_axes};
+
+fn len(range: &std::ops::RangeInclusive<f32>, resolution: f32) -> usize {
+    ((range.end() - range.start()) / resolution).floor() as usize + 1
+}
+
+fn grid_points(grid: Grid) -> impl Iterator<Item = (f32, f32, f32)> {
+    (0..grid.len()).map(move |i| {
+        let index = grid
+            .unflat_index(i)
+            .into_iter()
+            .map(|i| i as f32)
+            .collect::<Vec<_>>();
+        let p = grid.position(&index);
+        (p.x, p.y, p.z)
+    })
+}
+
+fn grid_aabb(grid: &Grid) -> Aabb {
+    grid.axes.iter().fold(
+        Aabb {
+            min: grid.origin,
+            max: grid.origin,
+        },
+        |aabb, axis| {
+            let d = axis.step * axis.len.saturating_sub(1) as f32;
+            aabb.grow(aabb.min + d).grow(aabb.max + d)
+        },
+    )
+}
+
+/// A range of 2D space on an arbitrarily oriented plane iterating in the order of u-v.
+///
+/// The points are `origin + u * u_axis + v * v_axis`.
+#[derive(Clone, Debug)]
+pub struct RangePlane {
+    /// The origin of the plane.
+    pub origin: Point3,
+    /// The direction of the u axis.
+    pub u_axis: UnitVector3,
+    /// The direction of the v axis.
+    pub v_axis: UnitVector3,
+    /// The range along the u axis.
+    pub u: std::ops::RangeInclusive<f32>,
+    /// The range along the v axis.
+    pub v: std::ops::RangeInclusive<f32>,
+    /// The resolution of the range.
+    pub resolution: f32,
+}
+
+impl RangePlane {
+    /// Creates a range on the plane parallel to the transducer surface of the device at the specified height.
+    ///
+    /// The u, v axes are the x, y axes of the device and the origin is the position of the first transducer.
+    pub fn from_device(
+        dev: &Device,
+        u: std::ops::RangeInclusive<f32>,
+        v: std::ops::RangeInclusive<f32>,
+        height: f32,
+        resolution: f32,
+    ) -> Self {
+        Self {
+            origin: dev[0].position() + dev.axial_direction().into_inner() * height,
+            u_axis: dev.x_direction(),
+            v_axis: dev.y_direction(),
+            u,
+            v,
+            resolution,
+        }
+    }
+}
+
+impl From<&RangePlane> for Grid {
+    fn from(range: &RangePlane) -> Self {
+        Self {
+            origin: range.origin
+                + range.u_axis.into_inner() * *range.u.start()
+                + range.v_axis.into_inner() * *range.v.start(),
+            axes: vec![
+                GridAxis {
+                    step: range.v_axis.into_inner() * range.resolution,
+                    len: len(&range.v, range.resolution),
+                },
+                GridAxis {
+                    step: range.u_axis.into_inner() * range.resolution,
+                    len: len(&range.u, range.resolution),
+                },
+            ],
+        }
+    }
+}
+
+impl Range for RangePlane {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        grid_points(Grid::from(self))
+    }
+
+    fn aabb(&self) -> Aabb {
+        grid_aabb(&Grid::from(self))
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        grid_axes(
+            &Grid::from(self),
+            &[("v[mm]", *self.v.start()), ("u[mm]", *self.u.start())],
+        )
+    }
+}
+
+/// A range of 3D space in an arbitrarily oriented box iterating in the order of u-v-w.
+///
+/// The points are `origin + u * u_axis + v * v_axis + w * w_axis`.
+#[derive(Clone, Debug)]
+pub struct RangeBox {
+    /// The origin of the box.
+    pub origin: Point3,
+    /// The direction of the u axis.
+    pub u_axis: UnitVector3,
+    /// The direction of the v axis.
+    pub v_axis: UnitVector3,
+    /// The direction of the w axis.
+    pub w_axis: UnitVector3,
+    /// The range along the u axis.
+    pub u: std::ops::RangeInclusive<f32>,
+    /// The range along the v axis.
+    pub v: std::ops::RangeInclusive<f32>,
+    /// The range along the w axis.
+    pub w: std::ops::RangeInclusive<f32>,
+    /// The resolution of the range.
+    pub resolution: f32,
+}
+
+impl RangeBox {
+    /// Creates a range in the local frame of the device.
+    ///
+    /// The u, v, w axes are the x, y and axial directions of the device and the origin is the position of the first transducer.
+    pub fn from_device(
+        dev: &Device,
+        u: std::ops::RangeInclusive<f32>,
+        v: std::ops::RangeInclusive<f32>,
+        w: std::ops::RangeInclusive<f32>,
+        resolution: f32,
+    ) -> Self {
+        Self {
+            origin: dev[0].position(),
+            u_axis: dev.x_direction(),
+            v_axis: dev.y_direction(),
+            w_axis: dev.axial_direction(),
+            u,
+            v,
+            w,
+            resolution,
+        }
+    }
+}
+
+impl From<&RangeBox> for Grid {
+    fn from(range: &RangeBox) -> Self {
+        Self {
+            origin: range.origin
+                + range.u_axis.into_inner() * *range.u.start()
+                + range.v_axis.into_inner() * *range.v.start()
+                + range.w_axis.into_inner() * *range.w.start(),
+            axes: vec![
+                GridAxis {
+                    step: range.w_axis.into_inner() * range.resolution,
+                    len: len(&range.w, range.resolution),
+                },
+                GridAxis {
+                    step: range.v_axis.into_inner() * range.resolution,
+                    len: len(&range.v, range.resolution),
+                },
+                GridAxis {
+                    step: range.u_axis.into_inner() * range.resolution,
+                    len: len(&range.u, range.resolution),
+                },
+            ],
+        }
+    }
+}
+
+impl Range for RangeBox {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        grid_points(Grid::from(self))
+    }
+
+    fn aabb(&self) -> Aabb {
+        grid_aabb(&Grid::from(self))
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        grid_axes(
+            &Grid::from(self),
+            &[
+                ("w[mm]", *self.w.start()),
+                ("v[mm]", *self.v.start()),
+                ("u[mm]", *self.u.start()),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::prelude::{AUTD3, EulerAngle, UnitQuaternion, deg};
+
+    use autd3::driver::geometry::Vector3;
+
+    use crate::{RangeXY, RangeXYZ};
+
+    use super::*;
+
+    fn assert_points_eq(expect: &impl Range, actual: &impl Range) {
+        let expect = expect.points().collect::<Vec<_>>();
+        let actual = actual.points().collect::<Vec<_>>();
+        assert_eq!(expect.len(), actual.len());
+        expect.iter().zip(actual.iter()).for_each(|(e, a)| {
+            approx::assert_abs_diff_eq!(e.0, a.0, epsilon = 1e-4);
+            approx::assert_abs_diff_eq!(e.1, a.1, epsilon = 1e-4);
+            approx::assert_abs_diff_eq!(e.2, a.2, epsilon = 1e-4);
+        });
+    }
+
+    fn assert_tight_aabb(range: &impl Range) {
+        let aabb = range.aabb();
+        let expect = range.points().fold(Aabb::empty(), |aabb, (x, y, z)| {
+            aabb.grow(Point3::new(x, y, z))
+        });
+        approx::assert_abs_diff_eq!(expect.min.x, aabb.min.x, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(expect.min.y, aabb.min.y, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(expect.min.z, aabb.min.z, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(expect.max.x, aabb.max.x, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(expect.max.y, aabb.max.y, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(expect.max.z, aabb.max.z, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn plane_axis_aligned() {
+        let range = RangePlane {
+            origin: Point3::new(0., 0., 3.),
+            u_axis: Vector3::x_axis(),
+            v_axis: Vector3::y_axis(),
+            u: 1.0..=2.,
+            v: -1.0..=2.,
+            resolution: 0.5,
+        };
+        assert_points_eq(
+            &RangeXY {
+                x: 1.0..=2.,
+                y: -1.0..=2.,
+                z: 3.,
+                resolution: 0.5,
+            },
+            &range,
+        );
+        assert_tight_aabb(&range);
+    }
+
+    #[test]
+    fn box_axis_aligned() {
+        let range = RangeBox {
+            origin: Point3::new(1., 2., 3.),
+            u_axis: Vector3::x_axis(),
+            v_axis: Vector3::y_axis(),
+            w_axis: Vector3::z_axis(),
+            u: 0.0..=1.,
+            v: -1.0..=1.,
+            w: 0.0..=2.,
+            resolution: 0.5,
+        };
+        assert_points_eq(
+            &RangeXYZ {
+                x: 1.0..=2.,
+                y: 1.0..=3.,
+                z: 3.0..=5.,
+                resolution: 0.5,
+            },
+            &range,
+        );
+        assert_tight_aabb(&range);
+    }
+
+    #[test]
+    fn plane_rotated() {
+        let s = 1. / 2f32.sqrt();
+        let range = RangePlane {
+            origin: Point3::new(1., 2., 3.),
+            u_axis: UnitVector3::new_normalize(Vector3::new(1., 1., 0.)),
+            v_axis: Vector3::z_axis(),
+            u: -1.0..=1.,
+            v: 0.0..=1.,
+            resolution: 1.,
+        };
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+        let expect_x = [1. - s, 1., 1. + s, 1. - s, 1., 1. + s];
+        let expect_y = [2. - s, 2., 2. + s, 2. - s, 2., 2. + s];
+        let expect_z = [3., 3., 3., 4., 4., 4.];
+        approx::assert_abs_diff_eq!(&expect_x[..], x.as_slice(), epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(&expect_y[..], y.as_slice(), epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(&expect_z[..], z.as_slice(), epsilon = 1e-4);
+
+        let aabb = range.aabb();
+        approx::assert_abs_diff_eq!(1. - s, aabb.min.x, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(2. + s, aabb.max.y, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(3., aabb.min.z, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(4., aabb.max.z, epsilon = 1e-4);
+    }
+
+    #[rstest::rstest]
+    #[case(UnitQuaternion::identity())]
+    #[case(EulerAngle::ZYZ(90. * deg, 30. * deg, 0. * deg).into())]
+    #[case(EulerAngle::ZYZ(45. * deg, -60. * deg, 10. * deg).into())]
+    fn from_device(#[case] rot: UnitQuaternion) {
+        let dev: Device = AUTD3 {
+            pos: Point3::new(10., 20., 30.),
+            rot,
+        }
+        .into();
+
+        let to_local = |(x, y, z): (f32, f32, f32)| {
+            let d = Point3::new(x, y, z) - dev[0].position();
+            Vector3::new(
+                d.dot(&dev.x_direction()),
+                d.dot(&dev.y_direction()),
+                d.dot(&dev.axial_direction()),
+            )
+        };
+
+        let plane = RangePlane::from_device(&dev, 0.0..=100., -10.0..=50., 150., 10.);
+        plane.points().map(to_local).for_each(|local| {
+            approx::assert_abs_diff_eq!(150., local.z, epsilon = 1e-3);
+        });
+        assert_tight_aabb(&plane);
+
+        let range = RangeBox::from_device(&dev, 0.0..=20., 0.0..=10., 0.0..=10., 5.);
+        let local = range.points().map(to_local).collect::<Vec<_>>();
+        assert_eq!(5 * 3 * 3, local.len());
+        approx::assert_abs_diff_eq!(0., local[0].x, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(5., local[1].x, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(5., local[5].y, epsilon = 1e-3);
+        approx::assert_abs_diff_eq!(5., local[15].z, epsilon = 1e-3);
+        assert_tight_aabb(&range);
+    }
+}
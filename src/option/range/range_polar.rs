@@ -0,0 +1,465 @@
+use crate::utils::aabb::Aabb;
+
+use autd3::driver::{
+    common::Angle,
+    geometry::{Point3, Vector3},
+};
+
+use super::{Range, RangeAxis};
+
+fn len(start: f32, end: f32, resolution: f32) -> usize {
+    ((end - start) / resolution).floor() as usize + 1
+}
+
+fn angles(range: &std::ops::RangeInclusive<Angle>, resolution: Angle) -> Vec<Angle> {
+    let start = range.start().radian();
+    let n = len(start, range.end().radian(), resolution.radian());
+    (0..n)
+        .map(|i| Angle::from_radian(start + resolution.radian() * i as f32))
+        .collect()
+}
+
+fn lengths(range: &std::ops::RangeInclusive<f32>, resolution: f32) -> Vec<f32> {
+    let start = *range.start();
+    let n = len(start, *range.end(), resolution);
+    (0..n).map(|i| start + resolution * i as f32).collect()
+}
+
+fn spherical(center: Point3, r: f32, theta: Angle, phi: Angle) -> (f32, f32, f32) {
+    let (st, ct) = theta.radian().sin_cos();
+    let (sp, cp) = phi.radian().sin_cos();
+    let p = center + Vector3::new(r * st * cp, r * st * sp, r * ct);
+    (p.x, p.y, p.z)
+}
+
+fn aabb_of(points: impl Iterator<Item = (f32, f32, f32)>) -> Aabb {
+    points.fold(Aabb::empty(), |aabb, (x, y, z)| {
+        aabb.grow(Point3::new(x, y, z))
+    })
+}
+
+/// A range of points on an arc.
+///
+/// The points are `center + radius * (sin(θ)cos(φ), sin(θ)sin(φ), cos(θ))` with the polar angle θ swept and the azimuthal angle φ fixed.
+#[derive(Clone, Debug)]
+pub struct RangeArc {
+    /// The center of the arc.
+    pub center: Point3,
+    /// The radius of the arc.
+    pub radius: f32,
+    /// The range of the polar angle measured from the z axis.
+    pub theta: std::ops::RangeInclusive<Angle>,
+    /// The azimuthal angle measured from the x axis.
+    pub phi: Angle,
+    /// The angular resolution of the range.
+    pub resolution: Angle,
+}
+
+impl RangeArc {
+    /// Returns the polar angles of the points.
+    pub fn thetas(&self) -> Vec<Angle> {
+        angles(&self.theta, self.resolution)
+    }
+}
+
+impl Range for RangeArc {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        let center = self.center;
+        let radius = self.radius;
+        let phi = self.phi;
+        self.thetas()
+            .into_iter()
+            .map(move |theta| spherical(center, radius, theta, phi))
+    }
+
+    fn aabb(&self) -> Aabb {
+        aabb_of(self.points())
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        vec![RangeAxis {
+            name: "theta[rad]",
+            coords: self.thetas().into_iter().map(Angle::radian).collect(),
+            stride: 1,
+        }]
+    }
+}
+
+/// A range of points on a sphere iterating in the order of θ-φ.
+///
+/// The points are `center + radius * (sin(θ)cos(φ), sin(θ)sin(φ), cos(θ))`.
+#[derive(Clone, Debug)]
+pub struct RangeSphere {
+    /// The center of the sphere.
+    pub center: Point3,
+    /// The radius of the sphere.
+    pub radius: f32,
+    /// The range of the polar angle measured from the z axis.
+    pub theta: std::ops::RangeInclusive<Angle>,
+    /// The range of the azimuthal angle measured from the x axis.
+    pub phi: std::ops::RangeInclusive<Angle>,
+    /// The angular resolution of the range.
+    pub resolution: Angle,
+}
+
+impl RangeSphere {
+    /// Returns the polar angles, which vary fastest in [`Range::points`].
+    pub fn thetas(&self) -> Vec<Angle> {
+        angles(&self.theta, self.resolution)
+    }
+
+    /// Returns the azimuthal angles.
+    pub fn phis(&self) -> Vec<Angle> {
+        angles(&self.phi, self.resolution)
+    }
+}
+
+impl Range for RangeSphere {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        let center = self.center;
+        let radius = self.radius;
+        let thetas = self.thetas();
+        self.phis().into_iter().flat_map(move |phi| {
+            thetas
+                .clone()
+                .into_iter()
+                .map(move |theta| spherical(center, radius, theta, phi))
+        })
+    }
+
+    fn aabb(&self) -> Aabb {
+        aabb_of(self.points())
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        let thetas = self.thetas();
+        vec![
+            RangeAxis {
+                name: "phi[rad]",
+                coords: self.phis().into_iter().map(Angle::radian).collect(),
+                stride: thetas.len(),
+            },
+            RangeAxis {
+                name: "theta[rad]",
+                coords: thetas.into_iter().map(Angle::radian).collect(),
+                stride: 1,
+            },
+        ]
+    }
+}
+
+/// A range of directions in the far field.
+///
+/// The directions are `(sin(θ)cos(φ), sin(θ)sin(φ), cos(θ))` as in [`RangeSphere`]. Unlike the other ranges, this is not a [`Range`] of points but the directions for [`Record::directivity`].
+///
+/// [`Record::directivity`]: crate::Record::directivity
+#[derive(Clone, Debug)]
+pub struct DirectivityRange {
+    /// The range of the polar angle measured from the z axis.
+    pub theta: std::ops::RangeInclusive<Angle>,
+    /// The range of the azimuthal angle measured from the x axis.
+    pub phi: std::ops::RangeInclusive<Angle>,
+    /// The angular resolution of the range.
+    pub resolution: Angle,
+}
+
+impl DirectivityRange {
+    /// Returns the polar angles.
+    pub fn thetas(&self) -> Vec<Angle> {
+        angles(&self.theta, self.resolution)
+    }
+
+    /// Returns the azimuthal angles.
+    pub fn phis(&self) -> Vec<Angle> {
+        angles(&self.phi, self.resolution)
+    }
+}
+
+/// A range of points on a cylinder around an axis parallel to the z axis iterating in the order of φ-height.
+///
+/// The points are `center + (radius * cos(φ), radius * sin(φ), height)`.
+#[derive(Clone, Debug)]
+pub struct RangeCylinder {
+    /// The center of the cylinder.
+    pub center: Point3,
+    /// The radius of the cylinder.
+    pub radius: f32,
+    /// The range of the azimuthal angle measured from the x axis.
+    pub phi: std::ops::RangeInclusive<Angle>,
+    /// The range of the height relative to the center.
+    pub height: std::ops::RangeInclusive<f32>,
+    /// The angular resolution of the range.
+    pub phi_resolution: Angle,
+    /// The resolution of the height.
+    pub height_resolution: f32,
+}
+
+impl RangeCylinder {
+    /// Returns the azimuthal angles, which vary fastest in [`Range::points`].
+    pub fn phis(&self) -> Vec<Angle> {
+        angles(&self.phi, self.phi_resolution)
+    }
+
+    /// Returns the heights.
+    pub fn heights(&self) -> Vec<f32> {
+        lengths(&self.height, self.height_resolution)
+    }
+}
+
+impl Range for RangeCylinder {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        let center = self.center;
+        let radius = self.radius;
+        let phis = self.phis();
+        self.heights().into_iter().flat_map(move |h| {
+            phis.clone().into_iter().map(move |phi| {
+                let (s, c) = phi.radian().sin_cos();
+                (center.x + radius * c, center.y + radius * s, center.z + h)
+            })
+        })
+    }
+
+    fn aabb(&self) -> Aabb {
+        aabb_of(self.points())
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        let phis = self.phis();
+        vec![
+            RangeAxis {
+                name: "height[mm]",
+                coords: self.heights(),
+                stride: phis.len(),
+            },
+            RangeAxis {
+                name: "phi[rad]",
+                coords: phis.into_iter().map(Angle::radian).collect(),
+                stride: 1,
+            },
+        ]
+    }
+}
+
+/// A range of points on a half plane containing the z axis iterating in the order of r-θ.
+///
+/// The points are `center + r * (sin(θ)cos(φ), sin(θ)sin(φ), cos(θ))` with the azimuthal angle φ fixed.
+#[derive(Clone, Debug)]
+pub struct RangePolarPlane {
+    /// The center of the polar coordinates.
+    pub center: Point3,
+    /// The range of the distance from the center.
+    pub r: std::ops::RangeInclusive<f32>,
+    /// The range of the polar angle measured from the z axis.
+    pub theta: std::ops::RangeInclusive<Angle>,
+    /// The azimuthal angle of the plane measured from the x axis.
+    pub phi: Angle,
+    /// The resolution of the distance.
+    pub r_resolution: f32,
+    /// The angular resolution of the range.
+    pub theta_resolution: Angle,
+}
+
+impl RangePolarPlane {
+    /// Returns the distances, which vary fastest in [`Range::points`].
+    pub fn rs(&self) -> Vec<f32> {
+        lengths(&self.r, self.r_resolution)
+    }
+
+    /// Returns the polar angles.
+    pub fn thetas(&self) -> Vec<Angle> {
+        angles(&self.theta, self.theta_resolution)
+    }
+}
+
+impl Range for RangePolarPlane {
+    fn points(&self) -> impl Iterator<Item = (f32, f32, f32)> {
+        let center = self.center;
+        let phi = self.phi;
+        let rs = self.rs();
+        self.thetas().into_iter().flat_map(move |theta| {
+            rs.clone()
+                .into_iter()
+                .map(move |r| spherical(center, r, theta, phi))
+        })
+    }
+
+    fn aabb(&self) -> Aabb {
+        aabb_of(self.points())
+    }
+
+    fn axes(&self) -> Vec<RangeAxis> {
+        let rs = self.rs();
+        vec![
+            RangeAxis {
+                name: "theta[rad]",
+                coords: self.thetas().into_iter().map(Angle::radian).collect(),
+                stride: rs.len(),
+            },
+            RangeAxis {
+                name: "r[mm]",
+                coords: rs,
+                stride: 1,
+            },
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::prelude::deg;
+
+    use super::*;
+
+    fn assert_points(expect: Vec<Point3>, range: &impl Range) {
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+        assert_eq!(expect.len(), x.len());
+        expect
+            .iter()
+            .zip(x.iter().zip(y.iter()).zip(z.iter()))
+            .for_each(|(e, ((&x, &y), &z))| {
+                approx::assert_abs_diff_eq!(e.x, x, epsilon = 1e-4);
+                approx::assert_abs_diff_eq!(e.y, y, epsilon = 1e-4);
+                approx::assert_abs_diff_eq!(e.z, z, epsilon = 1e-4);
+            });
+    }
+
+    fn assert_aabb(min: Point3, max: Point3, range: &impl Range) {
+        let aabb = range.aabb();
+        approx::assert_abs_diff_eq!(min.x, aabb.min.x, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(min.y, aabb.min.y, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(min.z, aabb.min.z, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(max.x, aabb.max.x, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(max.y, aabb.max.y, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(max.z, aabb.max.z, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn arc() {
+        let range = RangeArc {
+            center: Point3::new(1., 2., 3.),
+            radius: 10.,
+            theta: -90. * deg..=90. * deg,
+            phi: 0. * deg,
+            resolution: 45. * deg,
+        };
+        assert_eq!(5, range.thetas().len());
+        let s = 10. / 2f32.sqrt();
+        assert_points(
+            vec![
+                Point3::new(-9., 2., 3.),
+                Point3::new(1. - s, 2., 3. + s),
+                Point3::new(1., 2., 13.),
+                Point3::new(1. + s, 2., 3. + s),
+                Point3::new(11., 2., 3.),
+            ],
+            &range,
+        );
+        assert_aabb(Point3::new(-9., 2., 3.), Point3::new(11., 2., 13.), &range);
+    }
+
+    #[test]
+    fn sphere() {
+        let range = RangeSphere {
+            center: Point3::new(1., 2., 3.),
+            radius: 10.,
+            theta: 0. * deg..=90. * deg,
+            phi: 0. * deg..=270. * deg,
+            resolution: 90. * deg,
+        };
+        assert_eq!(vec![4, 2], range.shape());
+        assert_eq!(
+            vec!["phi[rad]", "theta[rad]"],
+            range.axes().iter().map(|a| a.name).collect::<Vec<_>>()
+        );
+        assert_points(
+            vec![
+                Point3::new(1., 2., 13.),
+                Point3::new(11., 2., 3.),
+                Point3::new(1., 2., 13.),
+                Point3::new(1., 12., 3.),
+                Point3::new(1., 2., 13.),
+                Point3::new(-9., 2., 3.),
+                Point3::new(1., 2., 13.),
+                Point3::new(1., -8., 3.),
+            ],
+            &range,
+        );
+        assert_aabb(
+            Point3::new(-9., -8., 3.),
+            Point3::new(11., 12., 13.),
+            &range,
+        );
+    }
+
+    #[test]
+    fn directivity_range() {
+        let range = DirectivityRange {
+            theta: -90. * deg..=90. * deg,
+            phi: 0. * deg..=90. * deg,
+            resolution: 1. * deg,
+        };
+        assert_eq!(181, range.thetas().len());
+        assert_eq!(91, range.phis().len());
+    }
+
+    #[test]
+    fn cylinder() {
+        let range = RangeCylinder {
+            center: Point3::new(1., 2., 3.),
+            radius: 10.,
+            phi: 0. * deg..=180. * deg,
+            height: -1.0..=1.0,
+            phi_resolution: 90. * deg,
+            height_resolution: 2.,
+        };
+        assert_eq!(vec![2, 3], range.shape());
+        assert_eq!(vec![-1., 1.], range.heights());
+        assert_points(
+            vec![
+                Point3::new(11., 2., 2.),
+                Point3::new(1., 12., 2.),
+                Point3::new(-9., 2., 2.),
+                Point3::new(11., 2., 4.),
+                Point3::new(1., 12., 4.),
+                Point3::new(-9., 2., 4.),
+            ],
+            &range,
+        );
+        assert_aabb(Point3::new(-9., 2., 2.), Point3::new(11., 12., 4.), &range);
+    }
+
+    #[test]
+    fn polar_plane() {
+        let range = RangePolarPlane {
+            center: Point3::new(1., 2., 3.),
+            r: 10.0..=20.0,
+            theta: 0. * deg..=90. * deg,
+            phi: 90. * deg,
+            r_resolution: 10.,
+            theta_resolution: 90. * deg,
+        };
+        assert_eq!(vec![2, 2], range.shape());
+        assert_eq!(vec![10., 20.], range.rs());
+        assert_points(
+            vec![
+                Point3::new(1., 2., 13.),
+                Point3::new(1., 2., 23.),
+                Point3::new(1., 12., 3.),
+                Point3::new(1., 22., 3.),
+            ],
+            &range,
+        );
+        assert_aabb(Point3::new(1., 2., 3.), Point3::new(1., 22., 23.), &range);
+    }
+
+    #[rstest::rstest]
+    #[case(1, 0., 0., 1.)]
+    #[case(11, 0., 1., 0.1)]
+    #[case(37, 0., 360., 10.)]
+    fn test_len(#[case] n: usize, #[case] start: f32, #[case] end: f32, #[case] resolution: f32) {
+        assert_eq!(
+            n,
+            angles(&(start * deg..=end * deg), resolution * deg).len()
+        );
+    }
+}
@@ -9,9 +9,21 @@ use autd3_core::firmware::ULTRASOUND_PERIOD_COUNT_BITS;
 #[cfg(feature = "polars")]
 use polars::{frame::DataFrame, prelude::Column};
 
+#[cfg(feature = "gpu")]
+pub use sound_field::gpu_context::{GpuContext, GpuOption};
 pub use sound_field::{
-    instant::{Instant, InstantRecordOption},
-    rms::{Rms, RmsRecordOption},
+    adaptive::{AdaptiveField, AdaptiveNode, AdaptiveRange},
+    directivity::{Directivity, Lobe},
+    field::Field,
+    gorkov::{Gorkov, GorkovRecordOption},
+    instant::{Instant, InstantRecordOption, Interpolation},
+    phasor::{Phasor, PhasorFormat, PhasorRecordOption},
+    precision::Precision,
+    reflector::Reflector,
+    rms::{EmissionModel, Propagation, Rms, RmsRecordOption},
+    scatterer::Scatterer,
+    statistics::Statistic,
+    unit::OutputUnit,
 };
 pub(crate) use transducer::TransducerRecord;
 
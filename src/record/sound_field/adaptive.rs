@@ -0,0 +1,292 @@
+use std::{collections::HashMap, io::Write, path::Path, time::Duration};
+
+use autd3::driver::{
+    common::ULTRASOUND_PERIOD,
+    geometry::{Point3, Vector3},
+};
+#[cfg(feature = "polars")]
+use polars::{df, frame::DataFrame};
+
+use super::{super::Record, rms::RmsRecordOption};
+use crate::{EmulatorError, Grid, OutputUnit};
+
+/// A range refined adaptively around features of the sound field.
+///
+/// Each cell of [`AdaptiveRange::grid`] is a box spanned by adjacent grid points, and it is recursively split into halves along each axis while the field at its corners satisfies the thresholds.
+/// Therefore, a 3D grid results in an octree and a 2D grid in a quadtree.
+#[derive(Clone, Debug)]
+pub struct AdaptiveRange {
+    /// The coarsest grid. Axes of length 1 are ignored.
+    pub grid: Grid,
+    /// The maximum depth of the refinement.
+    pub max_depth: usize,
+    /// A cell is refined if the difference between the maximum and minimum values at its corners divided by the length of its diagonal exceeds this value \[Pa/mm\].
+    pub gradient_threshold: Option<f32>,
+    /// A cell is refined if the maximum value at its corners exceeds this value \[Pa\].
+    pub amplitude_threshold: Option<f32>,
+}
+
+/// A node of [`AdaptiveField`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdaptiveNode {
+    /// The depth of the node, 0 for the cells of the coarsest grid.
+    pub depth: usize,
+    /// The position of the corner with the smallest index \[mm\].
+    pub origin: Point3,
+    /// The edges of the cell along each axis of the grid \[mm\].
+    pub edges: Vec<Vector3>,
+    /// The indices of the corners in [`AdaptiveField::points`], ordered so that the index along the last axis changes fastest as in [`Grid`].
+    pub corners: Vec<usize>,
+    /// The indices of the children in [`AdaptiveField::nodes`]. Empty for leaves.
+    pub children: Vec<usize>,
+}
+
+/// The RMS of the sound field on an [`AdaptiveRange`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdaptiveField {
+    /// The nodes of the tree.
+    pub nodes: Vec<AdaptiveNode>,
+    /// The indices of the nodes for the cells of the coarsest grid.
+    pub roots: Vec<usize>,
+    /// The evaluated points \[mm\].
+    pub points: Vec<Point3>,
+    /// The RMS of the sound pressure at each point \[Pa\].
+    pub values: Vec<f32>,
+}
+
+impl AdaptiveField {
+    /// Returns the leaves of the tree.
+    pub fn leaves(&self) -> impl Iterator<Item = &AdaptiveNode> {
+        self.nodes.iter().filter(|node| node.children.is_empty())
+    }
+
+    /// Returns the evaluated points and the values as a point cloud.
+    #[cfg(feature = "polars")]
+    pub fn point_cloud(&self) -> DataFrame {
+        df!(
+            "x[mm]" => self.points.iter().map(|p| p.x).collect::<Vec<_>>(),
+            "y[mm]" => self.points.iter().map(|p| p.y).collect::<Vec<_>>(),
+            "z[mm]" => self.points.iter().map(|p| p.z).collect::<Vec<_>>(),
+            "rms[Pa]" => &self.values,
+        )
+        .unwrap()
+    }
+
+    /// Writes the evaluated points and the values as a point cloud in ASCII PLY format.
+    pub fn write_ply(&self, mut writer: impl Write) -> Result<(), EmulatorError> {
+        writeln!(writer, "ply")?;
+        writeln!(writer, "format ascii 1.0")?;
+        writeln!(writer, "element vertex {}", self.points.len())?;
+        writeln!(writer, "property float x")?;
+        writeln!(writer, "property float y")?;
+        writeln!(writer, "property float z")?;
+        writeln!(writer, "property float rms")?;
+        writeln!(writer, "end_header")?;
+        self.points
+            .iter()
+            .zip(self.values.iter())
+            .try_for_each(|(p, v)| writeln!(writer, "{} {} {} {}", p.x, p.y, p.z, v))?;
+        Ok(())
+    }
+
+    /// Saves the point cloud in ASCII PLY format. See [`AdaptiveField::write_ply`].
+    pub fn save_ply(&self, path: impl AsRef<Path>) -> Result<(), EmulatorError> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        self.write_ply(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+struct Builder<'a> {
+    range: &'a AdaptiveRange,
+    // Indices of the axes of the grid with more than one point.
+    axes: Vec<usize>,
+    // The number of the finest cells in a cell of the coarsest grid.
+    scale: u64,
+    table: HashMap<Vec<u64>, usize>,
+    points: Vec<Point3>,
+}
+
+impl Builder<'_> {
+    fn point(&mut self, lattice: Vec<u64>) -> usize {
+        let len = self.points.len();
+        let idx = *self.table.entry(lattice.clone()).or_insert(len);
+        if idx == len {
+            let p = self
+                .axes
+                .iter()
+                .zip(lattice.iter())
+                .fold(self.range.grid.origin, |acc, (&a, &l)| {
+                    acc + self.range.grid.axes[a].step * (l as f32 / self.scale as f32)
+                });
+            self.points.push(p);
+        }
+        idx
+    }
+
+    fn node(&mut self, lattice: &[u64], depth: usize) -> AdaptiveNode {
+        let size = self.scale >> depth;
+        let corners: Vec<_> = (0..1usize << self.axes.len())
+            .map(|n| {
+                let corner = lattice
+                    .iter()
+                    .rev()
+                    .enumerate()
+                    .map(|(i, &l)| l + if n >> i & 1 == 1 { size } else { 0 })
+                    .rev()
+                    .collect();
+                self.point(corner)
+            })
+            .collect();
+        AdaptiveNode {
+            depth,
+            origin: self.points[corners[0]],
+            edges: self
+                .axes
+                .iter()
+                .map(|&a| self.range.grid.axes[a].step * (size as f32 / self.scale as f32))
+                .collect(),
+            corners,
+            children: Vec::new(),
+        }
+    }
+}
+
+impl AdaptiveRange {
+    fn should_refine(&self, node: &AdaptiveNode, values: &[f32]) -> bool {
+        if node.depth >= self.max_depth {
+            return false;
+        }
+        let (min, max) = node
+            .corners
+            .iter()
+            .map(|&i| values[i])
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), v| {
+                (min.min(v), max.max(v))
+            });
+        let diagonal = node.edges.iter().copied().sum::<Vector3>().norm();
+        self.amplitude_threshold.is_some_and(|th| max > th)
+            || self
+                .gradient_threshold
+                .is_some_and(|th| (max - min) / diagonal > th)
+    }
+}
+
+impl Record {
+    /// Calculates the RMS of the sound field during the period starting at the specified time on the adaptively refined range.
+    ///
+    /// [`RmsRecordOption::unit`] and [`RmsRecordOption::window`] are ignored, and the values are always the RMS of the period in \[Pa\].
+    pub fn sound_field_adaptive(
+        &self,
+        range: &AdaptiveRange,
+        option: RmsRecordOption,
+        time: Duration,
+    ) -> Result<AdaptiveField, EmulatorError> {
+        let option = RmsRecordOption {
+            unit: OutputUnit::Pascal,
+            window: ULTRASOUND_PERIOD,
+            window_step: None,
+            ..option
+        };
+        if range.grid.is_empty() {
+            return Err(EmulatorError::InvalidAdaptiveRange(
+                "grid has no points".to_owned(),
+            ));
+        }
+        let axes = (0..range.grid.axes.len())
+            .filter(|&a| range.grid.axes[a].len > 1)
+            .collect::<Vec<_>>();
+        // The lattice coordinates of the finest cells must fit in u64.
+        let scale = u32::try_from(range.max_depth)
+            .ok()
+            .and_then(|depth| 1u64.checked_shl(depth))
+            .filter(|&scale| {
+                axes.iter().all(|&a| {
+                    (range.grid.axes[a].len as u64 - 1)
+                        .checked_mul(scale)
+                        .is_some()
+                })
+            })
+            .ok_or_else(|| {
+                EmulatorError::InvalidAdaptiveRange(format!(
+                    "maximum depth ({}) is too large",
+                    range.max_depth
+                ))
+            })?;
+        let mut builder = Builder {
+            range,
+            scale,
+            table: HashMap::new(),
+            points: Vec::new(),
+            axes,
+        };
+
+        let num_cells = builder
+            .axes
+            .iter()
+            .map(|&a| range.grid.axes[a].len - 1)
+            .collect::<Vec<_>>();
+        let mut lattices = (0..num_cells.iter().product())
+            .map(|mut n| {
+                let mut lattice = num_cells
+                    .iter()
+                    .rev()
+                    .map(|&len| {
+                        let i = n % len;
+                        n /= len;
+                        i as u64 * builder.scale
+                    })
+                    .collect::<Vec<_>>();
+                lattice.reverse();
+                lattice
+            })
+            .collect::<Vec<_>>();
+        let mut nodes = lattices
+            .iter()
+            .map(|lattice| builder.node(lattice, 0))
+            .collect::<Vec<_>>();
+        let roots = (0..nodes.len()).collect::<Vec<_>>();
+
+        let mut values = Vec::new();
+        let mut pending = roots.clone();
+        while !pending.is_empty() {
+            let new_points = builder.points[values.len()..].to_vec();
+            if !new_points.is_empty() {
+                let mut rms = self.sound_field(new_points, option.clone())?;
+                values.extend(rms.skip(time)?.next_field(ULTRASOUND_PERIOD)?.values);
+            }
+
+            let mut next = Vec::new();
+            for i in pending {
+                if !range.should_refine(&nodes[i], &values) {
+                    continue;
+                }
+                let depth = nodes[i].depth + 1;
+                let size = builder.scale >> depth;
+                let first = nodes.len();
+                (0..1usize << builder.axes.len()).for_each(|n| {
+                    let lattice = lattices[i]
+                        .iter()
+                        .rev()
+                        .enumerate()
+                        .map(|(a, &l)| l + if n >> a & 1 == 1 { size } else { 0 })
+                        .rev()
+                        .collect::<Vec<_>>();
+                    nodes.push(builder.node(&lattice, depth));
+                    lattices.push(lattice);
+                });
+                nodes[i].children = (first..nodes.len()).collect();
+                next.extend(first..nodes.len());
+            }
+            pending = next;
+        }
+
+        Ok(AdaptiveField {
+            nodes,
+            roots,
+            points: builder.points,
+            values,
+        })
+    }
+}
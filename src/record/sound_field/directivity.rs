@@ -0,0 +1,270 @@
+use std::{f32::consts::PI, time::Duration};
+
+use autd3::{
+    driver::{
+        common::{Angle, ULTRASOUND_PERIOD},
+        geometry::{Complex, Vector3},
+    },
+    prelude::ULTRASOUND_FREQ,
+};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{super::Record, rms::RmsRecordOption};
+use crate::{DirectivityRange, EmulatorError};
+
+fn direction(theta: Angle, phi: Angle) -> Vector3 {
+    let (st, ct) = theta.radian().sin_cos();
+    let (sp, cp) = phi.radian().sin_cos();
+    Vector3::new(st * cp, st * sp, ct)
+}
+
+/// A lobe of [`Directivity`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lobe {
+    /// The polar angle of the peak.
+    pub theta: Angle,
+    /// The azimuthal angle of the peak.
+    pub phi: Angle,
+    /// The normalized level of the peak, 1 for the main lobe.
+    pub level: f32,
+}
+
+impl Lobe {
+    /// Returns the level in \[dB\] relative to the main lobe.
+    pub fn level_db(&self) -> f32 {
+        20. * self.level.log10()
+    }
+}
+
+/// The far-field directivity on a [`DirectivityRange`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directivity {
+    /// The polar angles along the first axis.
+    pub thetas: Vec<Angle>,
+    /// The azimuthal angles along the second axis.
+    pub phis: Vec<Angle>,
+    /// The angular resolution of the range.
+    pub resolution: Angle,
+    /// The magnitude normalized by its maximum in the range, iterating in the order of θ-φ.
+    pub values: Vec<f32>,
+    /// The maximum magnitude multiplied by the distance from the array \[Pa·mm\].
+    pub peak: f32,
+}
+
+impl Directivity {
+    /// Returns the normalized magnitude at the `i`-th θ and the `j`-th φ.
+    pub fn get(&self, i: usize, j: usize) -> f32 {
+        self.values[i + j * self.thetas.len()]
+    }
+
+    /// Returns the lobe with the maximum magnitude.
+    pub fn main_lobe(&self) -> Lobe {
+        self.lobe(self.argmax())
+    }
+
+    /// Returns the local maxima in descending order of the level, the first of which is the main lobe.
+    ///
+    /// Maxima closer than twice the resolution to a higher one, e.g., the same direction at θ = 0 with different φ, are merged into it.
+    pub fn lobes(&self) -> Vec<Lobe> {
+        let [n_theta, n_phi] = [self.thetas.len(), self.phis.len()];
+        let mut peaks = (0..self.values.len())
+            .filter(|&idx| {
+                let (i, j) = (idx % n_theta, idx / n_theta);
+                let v = self.values[idx];
+                (i == 0 || self.get(i - 1, j) <= v)
+                    && (i + 1 == n_theta || self.get(i + 1, j) <= v)
+                    && (j == 0 || self.get(i, j - 1) <= v)
+                    && (j + 1 == n_phi || self.get(i, j + 1) <= v)
+            })
+            .collect::<Vec<_>>();
+        peaks.sort_by(|&a, &b| self.values[b].total_cmp(&self.values[a]));
+
+        let max_cos = (2. * self.resolution.radian()).cos();
+        peaks
+            .into_iter()
+            .fold(Vec::new(), |mut lobes: Vec<Lobe>, idx| {
+                let lobe = self.lobe(idx);
+                let dir = direction(lobe.theta, lobe.phi);
+                if lobes
+                    .iter()
+                    .all(|l| direction(l.theta, l.phi).dot(&dir) <= max_cos)
+                {
+                    lobes.push(lobe);
+                }
+                lobes
+            })
+    }
+
+    /// Returns the lobes other than the main lobe whose levels relative to the main lobe are not less than `threshold` \[dB\].
+    ///
+    /// The grating lobes of an array with the pitch larger than half the wavelength are comparable to the main lobe, so that a threshold such as -3 dB separates them from the side lobes.
+    pub fn grating_lobes(&self, threshold: f32) -> Vec<Lobe> {
+        self.lobes()
+            .into_iter()
+            .skip(1)
+            .filter(|lobe| lobe.level_db() >= threshold)
+            .collect()
+    }
+
+    /// Returns the full width of the main lobe along θ at the φ of the main lobe, where the level drops to `level` \[dB\], e.g., -3 for the half-power beamwidth.
+    ///
+    /// Returns `None` if the level does not drop below `level` within the range on either side.
+    pub fn beamwidth(&self, level: f32) -> Option<Angle> {
+        let n_theta = self.thetas.len();
+        let main = self.argmax();
+        let (i0, j) = (main % n_theta, main / n_theta);
+        let threshold = 10f32.powf(level / 20.);
+
+        // The interpolated angle where the level crosses the threshold between the `i`-th and the `next`-th θ.
+        let crossing = |i: usize, next: usize| {
+            let (v, v_next) = (self.get(i, j), self.get(next, j));
+            let (t, t_next) = (self.thetas[i].radian(), self.thetas[next].radian());
+            t + (t_next - t) * (v - threshold) / (v - v_next)
+        };
+        let lower = (1..=i0)
+            .rev()
+            .find(|&i| self.get(i - 1, j) < threshold)
+            .map(|i| crossing(i, i - 1))?;
+        let upper = (i0..n_theta - 1)
+            .find(|&i| self.get(i + 1, j) < threshold)
+            .map(|i| crossing(i, i + 1))?;
+        Some(Angle::from_radian(upper - lower))
+    }
+
+    fn argmax(&self) -> usize {
+        self.values
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &v)| if v > self.values[acc] { i } else { acc })
+    }
+
+    fn lobe(&self, idx: usize) -> Lobe {
+        let n_theta = self.thetas.len();
+        Lobe {
+            theta: self.thetas[idx % n_theta],
+            phi: self.phis[idx / n_theta],
+            level: self.values[idx],
+        }
+    }
+}
+
+impl Record {
+    /// Calculates the far-field directivity of the period starting at the specified time.
+    ///
+    /// Under the far-field approximation, the sound pressure at the distance `R` in the direction `u` is proportional to `exp(ikR) / R` times `Σ a exp(i(φ - k u·x))`, where `a`, `φ` and `x` are the amplitude, phase and position of each source.
+    /// The magnitude of the sum is normalized by its maximum in the range.
+    ///
+    /// [`RmsRecordOption::sound_speed`], [`RmsRecordOption::emission`], [`RmsRecordOption::harmonic`] and the reflectors are taken into account, and the other options are ignored.
+    /// The image sources of the reflectors are treated as free-field sources, which is valid only on the side of the transducers.
+    pub fn directivity(
+        &self,
+        range: &DirectivityRange,
+        option: RmsRecordOption,
+        time: Duration,
+    ) -> Result<Directivity, EmulatorError> {
+        if !time.as_nanos().is_multiple_of(ULTRASOUND_PERIOD.as_nanos()) {
+            return Err(EmulatorError::InvalidDuration);
+        }
+        let idx = (time.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize;
+        if idx >= self.records[0].pulse_width.len() {
+            return Err(EmulatorError::NotRecorded);
+        }
+
+        let records = self.rms_transducer_records_of(&option)?;
+        let sources = self.sources(&option.reflectors, option.reflection_order);
+        let center = self.aabb.min + (self.aabb.max - self.aabb.min) / 2.;
+        let wavenumber =
+            2. * PI * option.harmonic as f32 * ULTRASOUND_FREQ.hz() as f32 / option.sound_speed;
+
+        let thetas = range.thetas();
+        let phis = range.phis();
+        let directions = phis
+            .iter()
+            .flat_map(|&phi| thetas.iter().map(move |&theta| direction(theta, phi)))
+            .collect::<Vec<_>>();
+        let magnitude = |u: &Vector3| {
+            sources
+                .iter()
+                .zip(records.iter().cycle())
+                .map(|(src, tr)| {
+                    let r = src.coef * tr.amp[idx];
+                    let theta = tr.phase[idx] - wavenumber * u.dot(&(src.pos - center));
+                    Complex::new(r * theta.cos(), r * theta.sin())
+                })
+                .sum::<Complex>()
+                .norm()
+        };
+        #[cfg(feature = "parallel")]
+        let mut values = directions.par_iter().map(magnitude).collect::<Vec<_>>();
+        #[cfg(not(feature = "parallel"))]
+        let mut values = directions.iter().map(magnitude).collect::<Vec<_>>();
+
+        let peak = values.iter().fold(0., |acc: f32, &v| acc.max(v));
+        if peak > 0. {
+            values.iter_mut().for_each(|v| *v /= peak);
+        }
+
+        Ok(Directivity {
+            thetas,
+            phis,
+            resolution: range.resolution,
+            values,
+            peak,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::prelude::deg;
+
+    use super::*;
+
+    fn directivity(values: Vec<f32>, n_theta: usize) -> Directivity {
+        let n_phi = values.len() / n_theta;
+        Directivity {
+            thetas: (0..n_theta).map(|i| i as f32 * deg).collect(),
+            phis: (0..n_phi).map(|j| (10. * j as f32) * deg).collect(),
+            resolution: 1. * deg,
+            values,
+            peak: 1.,
+        }
+    }
+
+    #[test]
+    fn lobes() {
+        let d = directivity(vec![0.1, 0.5, 1.0, 0.5, 0.2, 0.8, 0.3, 0.1, 0.4], 9);
+        let lobes = d.lobes();
+        assert_eq!(3, lobes.len());
+        assert_eq!(1.0, lobes[0].level);
+        approx::assert_abs_diff_eq!(2., lobes[0].theta.degree(), epsilon = 1e-4);
+        assert_eq!(0.8, lobes[1].level);
+        assert_eq!(0.4, lobes[2].level);
+        assert_eq!(lobes[0], d.main_lobe());
+        assert_eq!(vec![lobes[1]], d.grating_lobes(-3.));
+    }
+
+    #[test]
+    fn lobes_merged() {
+        let d = directivity(vec![1.0, 0.5, 0.2, 1.0, 0.5, 0.2], 3);
+        assert_eq!(1, d.lobes().len());
+    }
+
+    #[test]
+    fn beamwidth() {
+        let d = directivity(vec![0., 0., 0.5, 1.0, 0.5, 0., 0.], 7);
+        approx::assert_abs_diff_eq!(
+            4. * (1. - 10f32.powf(-3. / 20.)),
+            d.beamwidth(-3.).unwrap().degree(),
+            epsilon = 1e-4
+        );
+        approx::assert_abs_diff_eq!(2., d.beamwidth(-6.0206).unwrap().degree(), epsilon = 1e-3);
+    }
+
+    #[test]
+    fn beamwidth_out_of_range() {
+        let d = directivity(vec![1.0, 0.5, 0.], 3);
+        assert_eq!(None, d.beamwidth(-3.));
+    }
+}
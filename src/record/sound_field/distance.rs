@@ -0,0 +1,138 @@
+use autd3::prelude::Point3;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{precision::Float, reflector::Source};
+
+// The distances from each target to each source.
+// They are calculated once if they fit in the memory limits, and otherwise for each tile of targets every time they are used.
+#[derive(Debug)]
+pub(crate) struct Distances<T = f32> {
+    // The number of targets in a tile.
+    tile_size: usize,
+    // The distances of all the targets if they fit in the memory limits, i.e., a tile has all the targets, otherwise the buffer of a tile.
+    cache: Vec<Vec<T>>,
+}
+
+impl<T: Float> Distances<T> {
+    // `memory_limits` is the number of bytes available for the distances, and a tile has at least one target.
+    pub(crate) fn new(targets: &[Point3], sources: &[Source], memory_limits: usize) -> Self {
+        let tile_size = Self::tile_size(targets.len(), sources.len(), memory_limits);
+        Self {
+            tile_size,
+            cache: if tile_size >= targets.len() {
+                Self::calc(targets, sources)
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    pub(crate) fn tile_size(num_targets: usize, num_sources: usize, memory_limits: usize) -> usize {
+        let per_target = num_sources.max(1) * size_of::<T>();
+        (memory_limits / per_target).clamp(1, num_targets.max(1))
+    }
+
+    // Returns the number of bytes of the distances held at a time.
+    pub(crate) fn memory_usage(
+        num_targets: usize,
+        num_sources: usize,
+        memory_limits: usize,
+    ) -> usize {
+        Self::tile_size(num_targets, num_sources, memory_limits) * num_sources * size_of::<T>()
+    }
+
+    fn calc(targets: &[Point3], sources: &[Source]) -> Vec<Vec<T>> {
+        let dists = |p: &Point3| {
+            sources
+                .iter()
+                .map(|src| distance(p, &src.pos))
+                .collect::<Vec<_>>()
+        };
+        #[cfg(feature = "parallel")]
+        let dists = targets.par_iter().map(dists).collect();
+        #[cfg(not(feature = "parallel"))]
+        let dists = targets.iter().map(dists).collect();
+        dists
+    }
+
+    // Calls `f` with the index of the first target and the distances of the targets in each tile.
+    pub(crate) fn for_each_tile(
+        &mut self,
+        targets: &[Point3],
+        sources: &[Source],
+        mut f: impl FnMut(usize, &[Vec<T>]),
+    ) {
+        if self.tile_size >= targets.len() {
+            f(0, &self.cache);
+            return;
+        }
+        targets
+            .chunks(self.tile_size)
+            .enumerate()
+            .for_each(|(i, tile)| {
+                self.cache = Self::calc(tile, sources);
+                f(i * self.tile_size, &self.cache);
+            });
+    }
+}
+
+// The distance between `a` and `b` calculated in `T`.
+pub(crate) fn distance<T: Float>(a: &Point3, b: &Point3) -> T {
+    let [x, y, z] = difference::<T>(a, b);
+    (x * x + y * y + z * z).sqrt()
+}
+
+// `a - b` calculated in `T`.
+pub(crate) fn difference<T: Float>(a: &Point3, b: &Point3) -> [T; 3] {
+    [
+        T::from(a.x) - T::from(b.x),
+        T::from(a.y) - T::from(b.y),
+        T::from(a.z) - T::from(b.z),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(10, 2, 0, 1)]
+    #[case(10, 2, 24, 3)]
+    #[case(10, 2, 80, 10)]
+    #[case(10, 2, 1000, 10)]
+    fn tiles(
+        #[case] num_targets: usize,
+        #[case] num_sources: usize,
+        #[case] memory_limits: usize,
+        #[case] expect_tile_size: usize,
+    ) {
+        let targets = (0..num_targets)
+            .map(|i| Point3::new(i as f32, 0., 0.))
+            .collect::<Vec<_>>();
+        let sources = (0..num_sources)
+            .map(|i| Source {
+                pos: Point3::new(0., i as f32, 0.),
+                coef: 1.,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            expect_tile_size,
+            Distances::<f32>::tile_size(num_targets, num_sources, memory_limits)
+        );
+        let mut distances = Distances::<f32>::new(&targets, &sources, memory_limits);
+        let mut visited = Vec::new();
+        distances.for_each_tile(&targets, &sources, |start, tile| {
+            assert!(tile.len() <= expect_tile_size);
+            tile.iter().enumerate().for_each(|(i, d)| {
+                visited.push(start + i);
+                d.iter().zip(sources.iter()).for_each(|(&d, src)| {
+                    assert_eq!((targets[start + i] - src.pos).norm(), d);
+                });
+            });
+        });
+        assert_eq!((0..num_targets).collect::<Vec<_>>(), visited);
+    }
+}
@@ -0,0 +1,173 @@
+use autd3::driver::geometry::Complex;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+// Radix-2 FFT of a fixed power-of-two length.
+#[derive(Debug)]
+pub(crate) struct Fft {
+    // `exp(-2πij/n)` for `j < n/2`.
+    twiddles: Vec<Complex>,
+    bit_reversed: Vec<usize>,
+}
+
+impl Fft {
+    pub(crate) fn new(n: usize) -> Self {
+        assert!(n.is_power_of_two());
+        let bits = n.trailing_zeros();
+        Self {
+            twiddles: (0..n / 2)
+                .map(|j| {
+                    let (s, c) = (-2. * std::f64::consts::PI * j as f64 / n as f64).sin_cos();
+                    Complex::new(c as f32, s as f32)
+                })
+                .collect(),
+            bit_reversed: (0..n)
+                .map(|i| {
+                    i.reverse_bits()
+                        .checked_shr(usize::BITS - bits)
+                        .unwrap_or(0)
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.bit_reversed.len()
+    }
+
+    // Transforms `v` in place. The inverse transform is not normalized.
+    pub(crate) fn process(&self, v: &mut [Complex], inverse: bool) {
+        let n = self.len();
+        (0..n).for_each(|i| {
+            let j = self.bit_reversed[i];
+            if i < j {
+                v.swap(i, j);
+            }
+        });
+        let mut len = 2;
+        while len <= n {
+            let stride = n / len;
+            v.chunks_mut(len).for_each(|chunk| {
+                let (lo, hi) = chunk.split_at_mut(len / 2);
+                lo.iter_mut()
+                    .zip(hi.iter_mut())
+                    .enumerate()
+                    .for_each(|(j, (a, b))| {
+                        let w = self.twiddles[j * stride];
+                        let t = *b * if inverse { w.conj() } else { w };
+                        *b = Complex::new(a.re - t.re, a.im - t.im);
+                        *a += t;
+                    });
+            });
+            len *= 2;
+        }
+    }
+}
+
+// 2D FFT of a row-major array whose rows and columns have the lengths of `row` and `col`, respectively.
+#[derive(Debug)]
+pub(crate) struct Fft2 {
+    row: Fft,
+    col: Fft,
+    transposed: Vec<Complex>,
+}
+
+impl Fft2 {
+    pub(crate) fn new(nx: usize, ny: usize) -> Self {
+        Self {
+            row: Fft::new(nx),
+            col: Fft::new(ny),
+            transposed: vec![Complex::new(0., 0.); nx * ny],
+        }
+    }
+
+    // Transforms `v` in place. The inverse transform is normalized.
+    pub(crate) fn process(&mut self, v: &mut [Complex], inverse: bool) {
+        let (nx, ny) = (self.row.len(), self.col.len());
+        Self::rows(&self.row, v, inverse);
+        transpose(v, &mut self.transposed, nx, ny);
+        Self::rows(&self.col, &mut self.transposed, inverse);
+        transpose(&self.transposed, v, ny, nx);
+        if inverse {
+            let scale = 1. / (nx * ny) as f32;
+            v.iter_mut()
+                .for_each(|v| *v = Complex::new(v.re * scale, v.im * scale));
+        }
+    }
+
+    fn rows(fft: &Fft, v: &mut [Complex], inverse: bool) {
+        #[cfg(feature = "parallel")]
+        v.par_chunks_mut(fft.len())
+            .for_each(|row| fft.process(row, inverse));
+        #[cfg(not(feature = "parallel"))]
+        v.chunks_mut(fft.len())
+            .for_each(|row| fft.process(row, inverse));
+    }
+}
+
+// Transposes the row-major `src` with rows of length `nx` into `dst`.
+fn transpose(src: &[Complex], dst: &mut [Complex], nx: usize, ny: usize) {
+    (0..ny).for_each(|j| (0..nx).for_each(|i| dst[i * ny + j] = src[j * nx + i]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dft(v: &[Complex]) -> Vec<Complex> {
+        let n = v.len();
+        (0..n)
+            .map(|k| {
+                v.iter()
+                    .enumerate()
+                    .map(|(j, v)| {
+                        let theta = -2. * std::f32::consts::PI * ((j * k) % n) as f32 / n as f32;
+                        v * Complex::new(theta.cos(), theta.sin())
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    fn signal(n: usize) -> Vec<Complex> {
+        (0..n)
+            .map(|i| Complex::new((i as f32 * 0.7).sin(), (i as f32 * 1.3).cos() - 0.2))
+            .collect()
+    }
+
+    #[rstest::rstest]
+    #[case(1)]
+    #[case(2)]
+    #[case(8)]
+    #[case(64)]
+    #[test]
+    fn fft(#[case] n: usize) {
+        let v = signal(n);
+        let expect = dft(&v);
+        let mut actual = v.clone();
+        Fft::new(n).process(&mut actual, false);
+        expect.iter().zip(actual.iter()).for_each(|(e, a)| {
+            approx::assert_abs_diff_eq!(e.re, a.re, epsilon = 1e-4);
+            approx::assert_abs_diff_eq!(e.im, a.im, epsilon = 1e-4);
+        });
+    }
+
+    #[test]
+    fn fft2_inverse() {
+        let (nx, ny) = (16, 4);
+        let v = signal(nx * ny);
+        let mut actual = v.clone();
+        let mut fft = Fft2::new(nx, ny);
+        fft.process(&mut actual, false);
+        // the DC component is the sum of all
+        let sum = v.iter().copied().sum::<Complex>();
+        approx::assert_abs_diff_eq!(sum.re, actual[0].re, epsilon = 1e-4);
+        approx::assert_abs_diff_eq!(sum.im, actual[0].im, epsilon = 1e-4);
+        fft.process(&mut actual, true);
+        v.iter().zip(actual.iter()).for_each(|(e, a)| {
+            approx::assert_abs_diff_eq!(e.re, a.re, epsilon = 1e-5);
+            approx::assert_abs_diff_eq!(e.im, a.im, epsilon = 1e-5);
+        });
+    }
+}
@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use crate::RangeAxis;
+
+/// A sound field reshaped onto the axes of the range.
+///
+/// For axis-aligned ranges, the shape is `[nt, nz, ny, nx]` regardless of the iteration order of the range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    /// The time of each frame.
+    pub time: Vec<Duration>,
+    /// The axes of the range. See [`Range::axes`].
+    ///
+    /// [`Range::axes`]: crate::Range::axes
+    pub axes: Vec<RangeAxis>,
+    /// The values in row-major order with the shape of [`Field::shape`].
+    pub values: Vec<f32>,
+    // The index of the point for each element of a frame.
+    gather: Vec<usize>,
+}
+
+impl Field {
+    pub(crate) fn new(axes: Vec<RangeAxis>) -> Self {
+        let gather = axes.iter().fold(vec![0], |acc, axis| {
+            acc.iter()
+                .flat_map(|&base| (0..axis.len()).map(move |i| base + i * axis.stride))
+                .collect()
+        });
+        Self {
+            time: Vec::new(),
+            axes,
+            values: Vec::new(),
+            gather,
+        }
+    }
+
+    pub(crate) fn push(&mut self, time: Duration, v: &[f32]) {
+        self.time.push(time);
+        self.values.extend(self.gather.iter().map(|&i| v[i]));
+    }
+
+    /// Returns the shape of the field, i.e., the number of frames followed by the length of each axis.
+    pub fn shape(&self) -> Vec<usize> {
+        std::iter::once(self.time.len())
+            .chain(self.axes.iter().map(RangeAxis::len))
+            .collect()
+    }
+
+    /// Returns the value at the specified index, where the first element is the frame index.
+    pub fn get(&self, index: &[usize]) -> Option<f32> {
+        let shape = self.shape();
+        if index.len() != shape.len() || index.iter().zip(shape.iter()).any(|(i, n)| i >= n) {
+            return None;
+        }
+        let i = index
+            .iter()
+            .zip(shape.iter())
+            .fold(0, |acc, (&i, &n)| acc * n + i);
+        self.values.get(i).copied()
+    }
+
+    /// Converts the field into an [`ndarray::ArrayD`] with the shape of [`Field::shape`].
+    #[cfg(feature = "ndarray")]
+    pub fn to_ndarray(&self) -> ndarray::ArrayD<f32> {
+        ndarray::ArrayD::from_shape_vec(self.shape(), self.values.clone()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{Range, RangeXYZ, RangeZYX};
+
+    #[test]
+    fn reshape() {
+        let xyz = RangeXYZ {
+            x: 0.0..=2.,
+            y: 0.0..=1.,
+            z: 0.0..=3.,
+            resolution: 1.,
+        };
+        let zyx = RangeZYX {
+            x: 0.0..=2.,
+            y: 0.0..=1.,
+            z: 0.0..=3.,
+            resolution: 1.,
+        };
+        let value = |(x, y, z): (f32, f32, f32)| 100. * z + 10. * y + x;
+
+        let mut field_xyz = Field::new(xyz.axes());
+        field_xyz.push(Duration::ZERO, &xyz.points().map(value).collect::<Vec<_>>());
+        let mut field_zyx = Field::new(zyx.axes());
+        field_zyx.push(Duration::ZERO, &zyx.points().map(value).collect::<Vec<_>>());
+
+        assert_eq!(vec![1, 4, 2, 3], field_xyz.shape());
+        assert_eq!(field_xyz.values, field_zyx.values);
+        assert_eq!(Some(312.), field_xyz.get(&[0, 3, 1, 2]));
+        assert_eq!(None, field_xyz.get(&[0, 4, 1, 2]));
+        assert_eq!(None, field_xyz.get(&[0, 1, 2]));
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn to_ndarray() {
+        let range = RangeXYZ {
+            x: 0.0..=2.,
+            y: 0.0..=1.,
+            z: 0.0..=3.,
+            resolution: 1.,
+        };
+        let mut field = Field::new(range.axes());
+        let v = range
+            .points()
+            .map(|(x, y, z)| 100. * z + 10. * y + x)
+            .collect::<Vec<_>>();
+        field.push(Duration::ZERO, &v);
+        field.push(Duration::from_micros(25), &v);
+
+        let array = field.to_ndarray();
+        assert_eq!(&[2, 4, 2, 3], array.shape());
+        assert_eq!(312., array[[1, 3, 1, 2]]);
+    }
+}
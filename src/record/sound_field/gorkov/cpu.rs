@@ -0,0 +1,117 @@
+use autd3::{driver::geometry::Complex, prelude::Point3};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{super::rms::RmsTransducerRecord, GorkovCoef};
+
+#[derive(Debug)]
+pub(crate) struct Cpu {
+    records: Vec<RmsTransducerRecord>,
+    target_positions: Vec<Point3>,
+    transducer_positions: Vec<Point3>,
+    buffer: Vec<[f32; 4]>,
+}
+
+impl Cpu {
+    pub(crate) fn new(
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        transducer_positions: impl Iterator<Item = Point3>,
+        records: Vec<RmsTransducerRecord>,
+    ) -> Self {
+        let target_positions = x
+            .iter()
+            .zip(y.iter())
+            .zip(z.iter())
+            .map(|((&x, &y), &z)| Point3::new(x, y, z))
+            .collect::<Vec<_>>();
+        Self {
+            records,
+            buffer: vec![[0.; 4]; target_positions.len()],
+            target_positions,
+            transducer_positions: transducer_positions.collect(),
+        }
+    }
+
+    fn potential_and_force(
+        p: &Point3,
+        transducer_positions: &[Point3],
+        records: &[RmsTransducerRecord],
+        idx: usize,
+        coef: &GorkovCoef,
+    ) -> [f32; 4] {
+        let k = coef.wavenumber;
+        let mut pressure = Complex::new(0., 0.);
+        let mut grad = [Complex::new(0., 0.); 3];
+        let mut hessian = [[Complex::new(0., 0.); 3]; 3];
+        transducer_positions
+            .iter()
+            .zip(records.iter())
+            .for_each(|(tp, tr)| {
+                let d = *p - *tp;
+                let r = d.norm();
+                let n = [d.x / r, d.y / r, d.z / r];
+                let theta = k * r + tr.phase[idx];
+                let g = Complex::new(theta.cos(), theta.sin()) * (tr.amp[idx] / r);
+                let dg = g * Complex::new(-1. / r, k);
+                let nn = g * Complex::new(3. / (r * r) - k * k, -3. * k / r);
+                let delta = dg / r;
+                pressure += g;
+                (0..3).for_each(|i| {
+                    grad[i] += dg * n[i];
+                    (0..3).for_each(|j| {
+                        hessian[i][j] += nn * (n[i] * n[j]);
+                    });
+                    hessian[i][i] += delta;
+                });
+            });
+        let u = coef.kp * pressure.norm_sqr()
+            - coef.kv * grad.iter().map(|g| g.norm_sqr()).sum::<f32>();
+        let f = |i: usize| {
+            let grad_p2 = 2. * (pressure.conj() * grad[i]).re;
+            let grad_g2 = 2.
+                * (0..3)
+                    .map(|j| (grad[j].conj() * hessian[i][j]).re)
+                    .sum::<f32>();
+            -GorkovCoef::FORCE_SCALE * (coef.kp * grad_p2 - coef.kv * grad_g2)
+        };
+        [u, f(0), f(1), f(2)]
+    }
+
+    pub(crate) fn compute(&mut self, idx: usize, coef: GorkovCoef) -> &Vec<[f32; 4]> {
+        #[cfg(feature = "parallel")]
+        {
+            self.target_positions
+                .par_iter()
+                .map(|p| {
+                    Self::potential_and_force(
+                        p,
+                        &self.transducer_positions,
+                        &self.records,
+                        idx,
+                        &coef,
+                    )
+                })
+                .collect_into_vec(&mut self.buffer);
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            self.buffer = self
+                .target_positions
+                .iter()
+                .map(|p| {
+                    Self::potential_and_force(
+                        p,
+                        &self.transducer_positions,
+                        &self.records,
+                        idx,
+                        &coef,
+                    )
+                })
+                .collect();
+        }
+        &self.buffer
+    }
+}
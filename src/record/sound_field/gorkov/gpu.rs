@@ -0,0 +1,69 @@
+use crate::{EmulatorError, GpuContext, record::sound_field::period_gpu::PeriodGpu};
+
+use autd3::prelude::Point3;
+
+use bytemuck::NoUninit;
+
+use super::{super::rms::RmsTransducerRecord, GorkovCoef};
+
+// GRCOV_EXCL_START
+#[derive(NoUninit, Clone, Copy)]
+#[repr(C)]
+struct Pc {
+    idx: u32,
+    wavenumber: f32,
+    num_trans: u32,
+    stride: u32,
+    kp: f32,
+    kv: f32,
+    force_scale: f32,
+    _pad: u32,
+}
+// GRCOV_EXCL_STOP
+
+#[derive(Debug)]
+pub(crate) struct Gpu {
+    inner: PeriodGpu<[f32; 4]>,
+}
+
+impl Gpu {
+    pub(crate) fn new(
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        transducer_positions: impl Iterator<Item = Point3>,
+        records: Vec<RmsTransducerRecord>,
+        context: GpuContext,
+    ) -> Result<Self, EmulatorError> {
+        Ok(Self {
+            inner: PeriodGpu::new::<Pc>(
+                module_path!(),
+                include_str!("shader.wgsl"),
+                x,
+                y,
+                z,
+                transducer_positions,
+                records,
+                context,
+            )?,
+        })
+    }
+
+    pub(crate) fn compute(
+        &mut self,
+        idx: usize,
+        coef: GorkovCoef,
+    ) -> Result<&Vec<[f32; 4]>, EmulatorError> {
+        let pc = Pc {
+            idx: idx as _,
+            wavenumber: coef.wavenumber,
+            num_trans: self.inner.num_transducers(),
+            stride: self.inner.stride(),
+            kp: coef.kp,
+            kv: coef.kv,
+            force_scale: GorkovCoef::FORCE_SCALE,
+            _pad: 0,
+        };
+        self.inner.compute(&pc)
+    }
+}
@@ -0,0 +1,264 @@
+mod cpu;
+#[cfg(feature = "gpu")]
+mod gpu;
+mod option;
+
+use std::{f32::consts::PI, time::Duration};
+
+use autd3::{driver::common::ULTRASOUND_PERIOD, prelude::ULTRASOUND_FREQ};
+#[cfg(feature = "polars")]
+use polars::{df, frame::DataFrame, prelude::Column};
+
+use super::{super::Record, SoundFieldOption};
+use crate::{EmulatorError, Range};
+
+pub use option::GorkovRecordOption;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct GorkovCoef {
+    /// Wavenumber \[1/mm\].
+    pub(crate) wavenumber: f32,
+    /// Coefficient of the squared pressure \[J/Pa²\].
+    pub(crate) kp: f32,
+    /// Coefficient of the squared pressure gradient \[J/(Pa/mm)²\].
+    pub(crate) kv: f32,
+}
+
+impl GorkovCoef {
+    /// Scale factor from the gradient per millimeter to the gradient per meter.
+    pub(crate) const FORCE_SCALE: f32 = 1e3;
+
+    fn new(option: &GorkovRecordOption) -> Self {
+        let omega = 2. * PI * ULTRASOUND_FREQ.hz() as f32;
+        let c0 = option.sound_speed * 1e-3;
+        let cp = option.particle_sound_speed * 1e-3;
+        let rho0 = option.density;
+        let rhop = option.particle_density;
+        let volume = 4. / 3. * PI * (option.particle_radius * 1e-3).powi(3);
+        let f1 = 1. - (rho0 * c0 * c0) / (rhop * cp * cp);
+        let f2 = 2. * (rhop - rho0) / (2. * rhop + rho0);
+        Self {
+            wavenumber: omega / option.sound_speed,
+            kp: volume * f1 / (2. * rho0 * c0 * c0),
+            kv: 3. * volume * f2 / (4. * rho0 * omega * omega) * 1e6,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ComputeDevice {
+    Cpu(cpu::Cpu),
+    #[cfg(feature = "gpu")]
+    Gpu(gpu::Gpu),
+}
+
+impl ComputeDevice {
+    fn compute(&mut self, idx: usize, coef: GorkovCoef) -> Result<&Vec<[f32; 4]>, EmulatorError> {
+        match self {
+            Self::Cpu(cpu) => Ok(cpu.compute(idx, coef)),
+            #[cfg(feature = "gpu")]
+            Self::Gpu(gpu) => gpu.compute(idx, coef),
+        }
+    }
+}
+
+/// An interface to calculate the Gor'kov potential and the acoustic radiation force on a small sphere.
+///
+/// The potential and the force are computed from the pressure phasor and its spatial derivatives per ultrasound period, as in [`Rms`].
+///
+/// [`Rms`]: crate::Rms
+#[derive(Debug)]
+pub struct Gorkov {
+    coef: GorkovCoef,
+    cursor: usize,
+    max_frame: usize,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+    compute_device: ComputeDevice,
+}
+
+impl Gorkov {
+    #[cfg(feature = "polars")]
+    /// Returns the observed points.
+    pub fn observe_points(&self) -> DataFrame {
+        df!(
+            "x[mm]" => &self.x,
+            "y[mm]" => &self.y,
+            "z[mm]" => &self.z,
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "polars")]
+    /// Progresses by the specified time and calculates the Gor'kov potential and the radiation force during that time.
+    ///
+    /// For each period, four columns of the potential and the x, y and z components of the force are returned.
+    pub fn next(&mut self, duration: Duration) -> Result<DataFrame, EmulatorError> {
+        let n = self.next_time_len(duration);
+        let mut time = vec![0; n];
+        let mut v = vec![vec![0.0; self.next_points_len()]; 4 * n];
+        self.next_inplace(
+            duration,
+            false,
+            &mut time,
+            v.iter_mut().map(|v| v.as_mut_ptr()),
+        )?;
+
+        Ok(DataFrame::new(
+            self.next_points_len(),
+            time.iter()
+                .zip(v.chunks(4))
+                .flat_map(|(t, v)| {
+                    [
+                        Column::new(format!("U[J]@{t}[ns]").into(), &v[0]),
+                        Column::new(format!("Fx[N]@{t}[ns]").into(), &v[1]),
+                        Column::new(format!("Fy[N]@{t}[ns]").into(), &v[2]),
+                        Column::new(format!("Fz[N]@{t}[ns]").into(), &v[3]),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap())
+    }
+
+    /// Progresses by the specified time.
+    pub fn skip(&mut self, duration: Duration) -> Result<&mut Self, EmulatorError> {
+        self.next_inplace(duration, true, &mut [], std::iter::empty())?;
+        Ok(self)
+    }
+
+    // GRCOV_EXCL_START
+    #[doc(hidden)]
+    pub fn x_inplace(&self, x: &mut [f32]) {
+        x.copy_from_slice(&self.x);
+    }
+
+    #[doc(hidden)]
+    pub fn y_inplace(&self, y: &mut [f32]) {
+        y.copy_from_slice(&self.y);
+    }
+
+    #[doc(hidden)]
+    pub fn z_inplace(&self, z: &mut [f32]) {
+        z.copy_from_slice(&self.z);
+    }
+    // GRCOV_EXCL_STOP
+
+    #[doc(hidden)]
+    pub fn next_time_len(&self, duration: Duration) -> usize {
+        (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize
+    }
+
+    #[doc(hidden)]
+    pub fn next_points_len(&self) -> usize {
+        self.x.len()
+    }
+
+    // `v` must yield four destinations (potential, x, y and z component of the force) for each period.
+    #[doc(hidden)]
+    pub fn next_inplace(
+        &mut self,
+        duration: Duration,
+        skip: bool,
+        time: &mut [u64],
+        mut v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        if !duration
+            .as_nanos()
+            .is_multiple_of(ULTRASOUND_PERIOD.as_nanos())
+        {
+            return Err(EmulatorError::InvalidDuration);
+        }
+
+        let num_frames = (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize;
+
+        if self.cursor + num_frames > self.max_frame {
+            return Err(EmulatorError::NotRecorded);
+        }
+
+        if !skip {
+            let mut i = 0;
+            while i < num_frames {
+                let cur_frame = self.cursor + i;
+                let r = self.compute_device.compute(cur_frame, self.coef)?;
+                time[i] = (cur_frame as u32 * ULTRASOUND_PERIOD).as_nanos() as u64;
+                (0..4).for_each(|c| {
+                    let dst = v.next().unwrap();
+                    r.iter()
+                        .enumerate()
+                        .for_each(|(j, r)| unsafe { *dst.add(j) = r[c] });
+                });
+                i += 1;
+            }
+        }
+
+        self.cursor += num_frames;
+
+        Ok(())
+    }
+}
+
+impl Record {
+    fn sound_field_gorkov(
+        &self,
+        range: impl Range,
+        option: GorkovRecordOption,
+    ) -> Result<Gorkov, EmulatorError> {
+        let max_frame = self.records[0].pulse_width.len();
+
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+
+        let records = self.rms_transducer_records();
+
+        #[cfg(feature = "gpu")]
+        let compute_device = if option.gpu {
+            ComputeDevice::Gpu(gpu::Gpu::new(
+                &x,
+                &y,
+                &z,
+                self.records.iter().map(|tr| tr.tr.position()),
+                records,
+                crate::GpuContext::get_or_new(option.gpu_context.as_ref(), option.gpu_option)?,
+            )?)
+        } else {
+            ComputeDevice::Cpu(cpu::Cpu::new(
+                &x,
+                &y,
+                &z,
+                self.records.iter().map(|tr| tr.tr.position()),
+                records,
+            ))
+        };
+        #[cfg(not(feature = "gpu"))]
+        let compute_device = ComputeDevice::Cpu(cpu::Cpu::new(
+            &x,
+            &y,
+            &z,
+            self.records.iter().map(|tr| tr.tr.position()),
+            records,
+        ));
+
+        Ok(Gorkov {
+            coef: GorkovCoef::new(&option),
+            compute_device,
+            cursor: 0,
+            max_frame,
+            x,
+            y,
+            z,
+        })
+    }
+}
+
+impl<'a> SoundFieldOption<'a> for GorkovRecordOption {
+    type Output = Gorkov;
+
+    fn sound_field(
+        self,
+        record: &'a Record,
+        range: impl Range,
+    ) -> Result<Self::Output, EmulatorError> {
+        record.sound_field_gorkov(range, self)
+    }
+}
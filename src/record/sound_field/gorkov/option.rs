@@ -0,0 +1,47 @@
+#[cfg(feature = "gpu")]
+use crate::{GpuContext, GpuOption};
+use autd3::prelude::mm;
+
+/// Options for Gor'kov potential and acoustic radiation force recording.
+///
+/// The default particle is an expanded polystyrene bead commonly used in acoustic levitation.
+#[derive(Debug, Clone)]
+pub struct GorkovRecordOption {
+    /// Sound speed of the medium \[mm/s\].
+    pub sound_speed: f32,
+    /// Density of the medium \[kg/m³\].
+    pub density: f32,
+    /// Radius of the particle \[mm\].
+    pub particle_radius: f32,
+    /// Density of the particle \[kg/m³\].
+    pub particle_density: f32,
+    /// Sound speed of the particle \[mm/s\].
+    pub particle_sound_speed: f32,
+    #[cfg(feature = "gpu")]
+    /// If true, use GPU for computation.
+    pub gpu: bool,
+    #[cfg(feature = "gpu")]
+    /// Context of GPU shared across the computations. If `None`, a new context is created for each computation with `gpu`.
+    pub gpu_context: Option<GpuContext>,
+    #[cfg(feature = "gpu")]
+    /// Selection of the adapter for the new context. Ignored if `gpu_context` is given.
+    pub gpu_option: GpuOption,
+}
+
+impl std::default::Default for GorkovRecordOption {
+    fn default() -> Self {
+        Self {
+            sound_speed: 340e3 * mm,
+            density: 1.18,
+            particle_radius: 1. * mm,
+            particle_density: 29.,
+            particle_sound_speed: 900e3 * mm,
+            #[cfg(feature = "gpu")]
+            gpu: false,
+            #[cfg(feature = "gpu")]
+            gpu_context: None,
+            #[cfg(feature = "gpu")]
+            gpu_option: GpuOption::default(),
+        }
+    }
+}
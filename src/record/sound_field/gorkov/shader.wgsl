@@ -0,0 +1,83 @@
+@group(0)
+@binding(0)
+var<storage, read> v_amp: array<f32>;
+
+@group(0)
+@binding(1)
+var<storage, read> v_phase: array<f32>;
+
+@group(0)
+@binding(2)
+var<storage, read> v_tr_pos: array<vec3<f32>>;
+
+@group(0)
+@binding(3)
+var<storage, read> v_tar_pos: array<vec3<f32>>;
+
+@group(0)
+@binding(4)
+var<storage, read_write> v_dst: array<vec4<f32>>;
+
+struct Pc {
+    idx: u32,
+    wavenumber: f32,
+    num_trans: u32,
+    stride: u32,
+    kp: f32,
+    kv: f32,
+    force_scale: f32,
+    _pad: u32,
+}
+
+var<immediate> pc: Pc;
+
+fn cmul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+// Re(conj(a) * b)
+fn cdot(a: vec2<f32>, b: vec2<f32>) -> f32 {
+    return a.x * b.x + a.y * b.y;
+}
+
+@compute
+@workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    if global_id.x >= arrayLength(&v_dst) {
+        return;
+    }
+    let k = pc.wavenumber;
+    var p = vec2<f32>(0., 0.);
+    var g = array<vec2<f32>, 3>();
+    // xx, yy, zz, xy, yz, zx
+    var h = array<vec2<f32>, 6>();
+    for (var i: u32 = 0; i < pc.num_trans; i++) {
+        let d = v_tar_pos[global_id.x] - v_tr_pos[i];
+        let r = length(d);
+        let n = d / r;
+        let phase = k * r + v_phase[i * pc.stride + pc.idx];
+        let s = v_amp[i * pc.stride + pc.idx] / r * vec2<f32>(cos(phase), sin(phase));
+        let ds = cmul(s, vec2<f32>(-1. / r, k));
+        let nn = cmul(s, vec2<f32>(3. / (r * r) - k * k, -3. * k / r));
+        let delta = ds / r;
+        p += s;
+        g[0] += ds * n.x;
+        g[1] += ds * n.y;
+        g[2] += ds * n.z;
+        h[0] += nn * n.x * n.x + delta;
+        h[1] += nn * n.y * n.y + delta;
+        h[2] += nn * n.z * n.z + delta;
+        h[3] += nn * n.x * n.y;
+        h[4] += nn * n.y * n.z;
+        h[5] += nn * n.z * n.x;
+    }
+    let u = pc.kp * dot(p, p) - pc.kv * (dot(g[0], g[0]) + dot(g[1], g[1]) + dot(g[2], g[2]));
+    let grad_p2 = 2. * vec3<f32>(cdot(p, g[0]), cdot(p, g[1]), cdot(p, g[2]));
+    let grad_g2 = 2. * vec3<f32>(
+        cdot(g[0], h[0]) + cdot(g[1], h[3]) + cdot(g[2], h[5]),
+        cdot(g[0], h[3]) + cdot(g[1], h[1]) + cdot(g[2], h[4]),
+        cdot(g[0], h[5]) + cdot(g[1], h[4]) + cdot(g[2], h[2]),
+    );
+    let f = -pc.force_scale * (pc.kp * grad_p2 - pc.kv * grad_g2);
+    v_dst[global_id.x] = vec4<f32>(u, f);
+}
@@ -0,0 +1,415 @@
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+};
+
+use crate::EmulatorError;
+
+use super::reflector::Source;
+
+use autd3::prelude::Point3;
+use bytemuck::NoUninit;
+use wgpu::{Buffer, BufferAddress, util::DeviceExt};
+
+// GRCOV_EXCL_START
+#[derive(NoUninit, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct Vec3 {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) z: f32,
+    pub(crate) _pad: f32,
+}
+
+impl From<Point3> for Vec3 {
+    fn from(v: Point3) -> Self {
+        Self {
+            x: v.x,
+            y: v.y,
+            z: v.z,
+            _pad: 0.,
+        }
+    }
+}
+
+#[derive(NoUninit, Clone, Copy)]
+#[repr(C)]
+pub(crate) struct Vec4 {
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) z: f32,
+    pub(crate) w: f32,
+}
+// GRCOV_EXCL_STOP
+
+impl From<Source> for Vec4 {
+    fn from(src: Source) -> Self {
+        Self {
+            x: src.pos.x,
+            y: src.pos.y,
+            z: src.pos.z,
+            w: src.coef,
+        }
+    }
+}
+
+impl Vec3 {
+    // Packs the coordinates of the targets.
+    pub(crate) fn targets(x: &[f32], y: &[f32], z: &[f32]) -> Vec<Self> {
+        x.iter()
+            .zip(y.iter())
+            .zip(z.iter())
+            .map(|((&x, &y), &z)| Self { x, y, z, _pad: 0. })
+            .collect()
+    }
+}
+
+// A compiled pipeline of a shader and the layout of its bind group.
+#[derive(Debug, Clone)]
+pub(crate) struct Pipeline {
+    pub(crate) bind_group_layout: wgpu::BindGroupLayout,
+    pub(crate) pipeline: wgpu::ComputePipeline,
+}
+
+/// Options to select the adapter of GPU.
+#[cfg_attr(docsrs, doc(cfg(feature = "gpu")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GpuOption {
+    /// Backends from which the adapter is selected.
+    pub backends: wgpu::Backends,
+    /// Power preference of the adapter.
+    pub power_preference: wgpu::PowerPreference,
+    /// If true, only the software fallback adapter such as llvmpipe or WARP is used, so that the computation works on the machines without GPU.
+    pub force_fallback_adapter: bool,
+    /// Index of the adapter in [`GpuContext::adapters`]. If `Some`, `power_preference` and `force_fallback_adapter` are ignored.
+    pub adapter_index: Option<usize>,
+}
+
+impl std::default::Default for GpuOption {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            power_preference: wgpu::PowerPreference::default(),
+            force_fallback_adapter: false,
+            adapter_index: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    // The pipelines compiled so far, keyed by the module of the shader.
+    pipelines: Mutex<HashMap<&'static str, Pipeline>>,
+}
+
+/// A GPU device and the compiled shaders shared across the computations.
+///
+/// Creating a device and compiling the shaders take hundreds of milliseconds, which is paid for every [`Record::sound_field`] with `gpu` unless a context is given in its options.
+/// Cloning a context is cheap, and the clones share the same device.
+///
+/// [`Record::sound_field`]: crate::Record::sound_field
+#[cfg_attr(docsrs, doc(cfg(feature = "gpu")))]
+#[derive(Debug, Clone)]
+pub struct GpuContext {
+    inner: Arc<Inner>,
+}
+
+impl GpuContext {
+    // Only the push constants and the number of the storage buffers are required beyond the downlevel defaults, and the sizes of the buffers are up to the adapter.
+    const MAX_STORAGE_BUFFERS_PER_SHADER_STAGE: u32 = 8;
+
+    /// Creates a new context on the default adapter.
+    pub fn new() -> Result<Self, EmulatorError> {
+        Self::with_option(GpuOption::default())
+    }
+
+    /// Creates a new context on the adapter selected by `option`.
+    pub fn with_option(option: GpuOption) -> Result<Self, EmulatorError> {
+        let instance = Self::instance(option.backends);
+
+        let adapter = match option.adapter_index {
+            Some(index) => {
+                let mut adapters =
+                    crate::utils::executor::block_on(instance.enumerate_adapters(option.backends));
+                let count = adapters.len();
+                if index >= count {
+                    return Err(EmulatorError::GpuAdapterNotFound { index, count });
+                }
+                adapters.swap_remove(index)
+            }
+            None => crate::utils::executor::block_on(instance.request_adapter(
+                &wgpu::RequestAdapterOptions {
+                    power_preference: option.power_preference,
+                    force_fallback_adapter: option.force_fallback_adapter,
+                    compatible_surface: None,
+                },
+            ))?,
+        };
+
+        let limits = adapter.limits();
+        let (device, queue) =
+            crate::utils::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                label: None,
+                required_features: wgpu::Features::IMMEDIATES,
+                required_limits: wgpu::Limits {
+                    max_immediate_size: limits.max_immediate_size,
+                    max_storage_buffers_per_shader_stage:
+                        Self::MAX_STORAGE_BUFFERS_PER_SHADER_STAGE,
+                    max_storage_buffer_binding_size: limits.max_storage_buffer_binding_size,
+                    max_buffer_size: limits.max_buffer_size,
+                    ..wgpu::Limits::downlevel_defaults()
+                },
+                memory_hints: wgpu::MemoryHints::MemoryUsage,
+                trace: wgpu::Trace::Off,
+                experimental_features: wgpu::ExperimentalFeatures::disabled(),
+            }))?;
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                device,
+                queue,
+                pipelines: Mutex::new(HashMap::new()),
+            }),
+        })
+    }
+
+    /// Returns the information of the adapters available on `backends`, in the order of [`GpuOption::adapter_index`].
+    pub fn adapters(backends: wgpu::Backends) -> Vec<wgpu::AdapterInfo> {
+        crate::utils::executor::block_on(Self::instance(backends).enumerate_adapters(backends))
+            .iter()
+            .map(wgpu::Adapter::get_info)
+            .collect()
+    }
+
+    fn instance(backends: wgpu::Backends) -> wgpu::Instance {
+        wgpu::Instance::new(wgpu::InstanceDescriptor {
+            backends,
+            ..wgpu::InstanceDescriptor::new_without_display_handle()
+        })
+    }
+
+    // Returns the context in the options or a new one on the adapter selected by `option`.
+    pub(crate) fn get_or_new(
+        context: Option<&GpuContext>,
+        option: GpuOption,
+    ) -> Result<Self, EmulatorError> {
+        context
+            .cloned()
+            .map_or_else(|| Self::with_option(option), Ok)
+    }
+
+    pub(crate) fn device(&self) -> &wgpu::Device {
+        &self.inner.device
+    }
+
+    pub(crate) fn queue(&self) -> &wgpu::Queue {
+        &self.inner.queue
+    }
+
+    // Checks that a storage buffer of `size` bytes can be bound on the device.
+    pub(crate) fn check_binding_size(
+        &self,
+        size: wgpu::BufferAddress,
+    ) -> Result<(), EmulatorError> {
+        let limits = self.inner.device.limits();
+        let limit = limits
+            .max_storage_buffer_binding_size
+            .min(limits.max_buffer_size);
+        if size > limit {
+            return Err(EmulatorError::GpuBufferTooLarge { size, limit });
+        }
+        Ok(())
+    }
+
+    // Returns the pipeline of the shader of `key`, which is compiled by `create` only the first time.
+    pub(crate) fn pipeline(
+        &self,
+        key: &'static str,
+        create: impl FnOnce(&wgpu::Device) -> Pipeline,
+    ) -> Pipeline {
+        self.inner
+            .pipelines
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| create(&self.inner.device))
+            .clone()
+    }
+
+    // Returns the pipeline of the compute shader `source`, whose storage buffers are bound in order and are read-only if the corresponding element of `read_only` is true.
+    pub(crate) fn compute_pipeline(
+        &self,
+        key: &'static str,
+        source: &'static str,
+        read_only: &[bool],
+        immediate_size: u32,
+    ) -> Pipeline {
+        self.pipeline(key, |device| {
+            let cs_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: None,
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+            });
+
+            let entries = read_only
+                .iter()
+                .enumerate()
+                .map(|(binding, &read_only)| wgpu::BindGroupLayoutEntry {
+                    binding: binding as _,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only },
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                })
+                .collect::<Vec<_>>();
+            let bind_group_layout =
+                device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    label: None,
+                    entries: &entries,
+                });
+
+            let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: None,
+                bind_group_layouts: &[Some(&bind_group_layout)],
+                immediate_size,
+            });
+
+            let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: None,
+                layout: Some(&pipeline_layout),
+                module: &cs_module,
+                entry_point: None,
+                compilation_options: Default::default(),
+                cache: None,
+            });
+
+            Pipeline {
+                bind_group_layout,
+                pipeline,
+            }
+        })
+    }
+
+    // Binds `buffers` in order.
+    pub(crate) fn bind_group(
+        &self,
+        layout: &wgpu::BindGroupLayout,
+        buffers: &[&Buffer],
+    ) -> wgpu::BindGroup {
+        let entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as _,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>();
+        self.inner
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                label: None,
+                layout,
+                entries: &entries,
+            })
+    }
+
+    // Creates a read-only storage buffer initialized with `contents`.
+    pub(crate) fn storage_buffer<T: NoUninit>(&self, contents: &[T]) -> Buffer {
+        self.inner
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: None,
+                contents: bytemuck::cast_slice(contents),
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            })
+    }
+
+    // Creates a storage buffer written by the shader and a staging buffer to read it back.
+    pub(crate) fn output_buffers(&self, size: BufferAddress) -> (Buffer, Buffer) {
+        let storage = self.inner.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::STORAGE
+                | wgpu::BufferUsages::COPY_DST
+                | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = self.inner.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        (storage, staging)
+    }
+
+    // Runs `pipeline` on `num_targets` targets and copies each pair of the storage and the staging buffers in `outputs`.
+    pub(crate) fn dispatch(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        bind_group: &wgpu::BindGroup,
+        immediates: &[u8],
+        num_targets: usize,
+        outputs: &[(&Buffer, &Buffer)],
+    ) {
+        let mut encoder = self
+            .inner
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+
+        {
+            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: None,
+                timestamp_writes: None,
+            });
+            cpass.set_pipeline(pipeline);
+            cpass.set_bind_group(0, bind_group, &[]);
+            cpass.set_immediates(0, immediates);
+            cpass.dispatch_workgroups(((num_targets - 1) / 64 + 1) as _, 1, 1);
+        }
+        outputs.iter().for_each(|(storage, staging)| {
+            encoder.copy_buffer_to_buffer(storage, 0, staging, 0, staging.size());
+        });
+
+        self.inner.queue.submit(Some(encoder.finish()));
+    }
+
+    // Reads the staging buffer back into `dst` after the submitted work is done.
+    pub(crate) fn read_buffer<T: bytemuck::Pod>(
+        &self,
+        staging: &Buffer,
+        dst: &mut [T],
+    ) -> Result<(), EmulatorError> {
+        let buffer_slice = staging.slice(..);
+        let result = Arc::new((Mutex::new(None), Condvar::new()));
+        let result_clone = result.clone();
+        buffer_slice.map_async(wgpu::MapMode::Read, move |r| {
+            let (lock, cvar) = &*result_clone;
+            let mut pending = lock.lock().unwrap();
+            *pending = Some(r);
+            cvar.notify_one();
+        });
+        self.inner
+            .device
+            .poll(wgpu::PollType::wait_indefinitely())?;
+        let (lock, cvar) = &*result;
+        let mut pending = lock.lock().unwrap();
+        // GRCOV_EXCL_START
+        while pending.is_none() {
+            pending = cvar.wait(pending).unwrap();
+        }
+        // GRCOV_EXCL_STOP
+        pending.take().unwrap()?;
+        {
+            let data = buffer_slice.get_mapped_range();
+            dst.copy_from_slice(bytemuck::cast_slice(&data));
+        }
+        staging.unmap();
+        Ok(())
+    }
+}
@@ -1,97 +1,200 @@
-use std::{collections::VecDeque, time::Duration};
+use std::time::Duration;
 
 use autd3::prelude::Point3;
 
-use crate::record::{
-    TransducerRecord, ULTRASOUND_PERIOD_COUNT, transducer::output_ultrasound::OutputUltrasound,
-};
+use crate::record::{TransducerRecord, transducer::output_ultrasound::OutputUltrasound};
 
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
+use super::{
+    super::{
+        distance::{Distances, difference},
+        precision::Float,
+        reflector::Source,
+        simd::{self, Kernel, Retarded},
+    },
+    Frame, Interpolation, SampleCache, drain_frame, push_frame,
+};
+
+impl Interpolation {
+    // The number of samples used on each side of the interpolated point.
+    pub(crate) const fn half_width(&self) -> usize {
+        match self {
+            Interpolation::Nearest | Interpolation::Linear => 1,
+            Interpolation::Cubic => 2,
+            Interpolation::WindowedSinc => 4,
+        }
+    }
+
+    // The weight of the sample at `x` samples away from the interpolated point.
+    fn kernel<T: Float>(&self, x: T) -> T {
+        let pi = T::from_f64(std::f64::consts::PI);
+        let (zero, one) = (T::from(0.), T::from(1.));
+        let sinc = |x: T| {
+            if x == zero {
+                one
+            } else {
+                (pi * x).sin() / (pi * x)
+            }
+        };
+        let a = x.abs();
+        match self {
+            Interpolation::Nearest => {
+                if T::from(-0.5) <= x && x < T::from(0.5) {
+                    one
+                } else {
+                    zero
+                }
+            }
+            Interpolation::Linear => {
+                if a < one {
+                    one - a
+                } else {
+                    zero
+                }
+            }
+            Interpolation::Cubic => {
+                if a < one {
+                    (T::from(1.5) * a - T::from(2.5)) * a * a + one
+                } else if a < T::from(2.) {
+                    ((T::from(-0.5) * a + T::from(2.5)) * a - T::from(4.)) * a + T::from(2.)
+                } else {
+                    zero
+                }
+            }
+            Interpolation::WindowedSinc => {
+                let w = T::from(self.half_width() as f32);
+                if a < w {
+                    let r = pi * x / w;
+                    sinc(x)
+                        * (T::from_f64(0.42)
+                            + T::from(0.5) * r.cos()
+                            + T::from_f64(0.08) * (T::from(2.) * r).cos())
+                } else {
+                    zero
+                }
+            }
+        }
+    }
+
+    // Interpolates `v` at `idx + alpha`, where `0 <= alpha < 1`.
+    pub(crate) fn interpolate<T: Float>(&self, v: &[T], idx: usize, alpha: T) -> T {
+        if *self == Interpolation::Linear {
+            return v[idx] * (T::from(1.) - alpha) + v[idx + 1] * alpha;
+        }
+        let w = self.half_width() as isize;
+        let (sum, weight) = (1 - w..=w).fold((T::default(), T::default()), |(sum, weight), j| {
+            let k = self.kernel(alpha - T::from(j as f32));
+            (sum + k * v[(idx as isize + j) as usize], weight + k)
+        });
+        sum / weight
+    }
+}
+
 #[derive(Debug)]
-pub(crate) struct Cpu<'a> {
-    output_ultrasound: Vec<OutputUltrasound<'a>>,
-    output_ultrasound_cache: Vec<VecDeque<f32>>,
-    dists: Vec<Vec<f32>>,
+pub(crate) struct Cpu<'a, T = f32> {
+    output_ultrasound: Vec<OutputUltrasound<'a, T>>,
+    output_ultrasound_cache: Vec<SampleCache<T>>,
+    output_ultrasound_integral_cache: Vec<SampleCache<T>>,
+    dists: Distances<T>,
     cache: Vec<Vec<f32>>,
     frame_window_size: usize,
+    target_positions: Vec<Point3>,
+    sources: Vec<Source>,
+    velocity_scale: Option<f32>,
+    velocity_cache: Vec<Vec<[f32; 3]>>,
 }
 
-impl<'a> Cpu<'a> {
+impl Cpu<'_> {
     pub(crate) const P0: f32 = autd3::driver::common::T4010A1_AMPLITUDE * std::f32::consts::SQRT_2
         / (4. * std::f32::consts::PI);
+}
 
+impl<'a, T: Float> Cpu<'a, T> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         x: &[f32],
         y: &[f32],
         z: &[f32],
-        transducer_positions: impl Iterator<Item = Point3>,
-        output_ultrasound: Vec<OutputUltrasound<'a>>,
+        sources: Vec<Source>,
+        output_ultrasound: Vec<OutputUltrasound<'a, T>>,
         frame_window_size: usize,
         num_points_in_frame: usize,
+        velocity_scale: Option<f32>,
+        memory_limits: usize,
     ) -> Self {
-        let transducer_positions = transducer_positions.collect::<Vec<_>>();
-        let dists = x
+        let target_positions = x
             .iter()
             .zip(y.iter())
             .zip(z.iter())
             .map(|((&x, &y), &z)| Point3::new(x, y, z))
-            .map(|p| {
-                transducer_positions
-                    .iter()
-                    .map(|tp| (p - *tp).norm())
-                    .collect::<Vec<_>>()
-            })
             .collect::<Vec<_>>();
+        let dists = Distances::new(&target_positions, &sources, memory_limits);
         Self {
             output_ultrasound,
             output_ultrasound_cache: Vec::new(),
-            cache: vec![vec![0.0f32; dists.len()]; num_points_in_frame],
+            output_ultrasound_integral_cache: Vec::new(),
+            cache: vec![vec![0.0f32; target_positions.len()]; num_points_in_frame],
+            velocity_cache: if velocity_scale.is_some() {
+                vec![vec![[0.0f32; 3]; target_positions.len()]; num_points_in_frame]
+            } else {
+                Vec::new()
+            },
             dists,
             frame_window_size,
+            target_positions,
+            sources,
+            velocity_scale,
         }
     }
 
     pub(crate) fn init(&mut self, cache_size: isize, cursor: &mut isize, rem_frame: &mut usize) {
         if self.output_ultrasound_cache.is_empty() {
+            let velocity = self.velocity_scale.is_some();
             #[cfg(feature = "parallel")]
             {
-                self.output_ultrasound_cache = self
+                (
+                    self.output_ultrasound_cache,
+                    self.output_ultrasound_integral_cache,
+                ) = self
                     .output_ultrasound
                     .par_iter_mut()
                     .map(|ut| {
-                        (0..cache_size)
-                            .flat_map(|i| {
-                                if *cursor + i >= 0 {
-                                    ut._next(1)
-                                        .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT])
-                                } else {
-                                    vec![0.; ULTRASOUND_PERIOD_COUNT]
-                                }
-                            })
-                            .collect()
+                        let mut cache = SampleCache::default();
+                        let mut integral_cache = SampleCache::default();
+                        (0..cache_size).for_each(|i| {
+                            push_frame(
+                                (*cursor + i >= 0).then_some(&mut *ut),
+                                &mut cache,
+                                velocity.then_some(&mut integral_cache),
+                            )
+                        });
+                        (cache, integral_cache)
                     })
-                    .collect();
+                    .unzip();
             }
             #[cfg(not(feature = "parallel"))]
             {
-                self.output_ultrasound_cache = self
+                (
+                    self.output_ultrasound_cache,
+                    self.output_ultrasound_integral_cache,
+                ) = self
                     .output_ultrasound
                     .iter_mut()
                     .map(|ut| {
-                        (0..cache_size)
-                            .flat_map(|i| {
-                                if *cursor + i >= 0 {
-                                    ut._next(1)
-                                        .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT])
-                                } else {
-                                    vec![0.; ULTRASOUND_PERIOD_COUNT]
-                                }
-                            })
-                            .collect()
+                        let mut cache = SampleCache::default();
+                        let mut integral_cache = SampleCache::default();
+                        (0..cache_size).for_each(|i| {
+                            push_frame(
+                                (*cursor + i >= 0).then_some(&mut *ut),
+                                &mut cache,
+                                velocity.then_some(&mut integral_cache),
+                            )
+                        });
+                        (cache, integral_cache)
                     })
-                    .collect();
+                    .unzip();
             }
             *cursor += cache_size;
             *rem_frame = self.frame_window_size;
@@ -104,19 +207,21 @@ impl<'a> Cpu<'a> {
             c if c >= 0 => self.frame_window_size,
             c => (c + self.frame_window_size as isize) as usize,
         };
+        let velocity = self.velocity_scale.is_some();
         #[cfg(feature = "parallel")]
         {
             self.output_ultrasound_cache
                 .iter_mut()
+                .zip(self.output_ultrasound_integral_cache.iter_mut())
                 .zip(self.output_ultrasound.iter_mut())
                 .par_bridge()
-                .for_each(|(cache, output_ultrasound)| {
-                    drop(cache.drain(0..ULTRASOUND_PERIOD_COUNT * n));
+                .for_each(|((cache, integral_cache), output_ultrasound)| {
+                    drain_frame(n, cache, velocity.then_some(&mut *integral_cache));
                     (0..n).for_each(|_| {
-                        cache.extend(
-                            output_ultrasound
-                                ._next(1)
-                                .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT]),
+                        push_frame(
+                            Some(output_ultrasound),
+                            cache,
+                            velocity.then_some(&mut *integral_cache),
                         );
                     })
                 });
@@ -125,14 +230,15 @@ impl<'a> Cpu<'a> {
         {
             self.output_ultrasound_cache
                 .iter_mut()
+                .zip(self.output_ultrasound_integral_cache.iter_mut())
                 .zip(self.output_ultrasound.iter_mut())
-                .for_each(|(cache, output_ultrasound)| {
-                    drop(cache.drain(0..ULTRASOUND_PERIOD_COUNT * n));
+                .for_each(|((cache, integral_cache), output_ultrasound)| {
+                    drain_frame(n, cache, velocity.then_some(&mut *integral_cache));
                     (0..n).for_each(|_| {
-                        cache.extend(
-                            output_ultrasound
-                                ._next(1)
-                                .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT]),
+                        push_frame(
+                            Some(output_ultrasound),
+                            cache,
+                            velocity.then_some(&mut *integral_cache),
                         );
                     })
                 });
@@ -140,6 +246,47 @@ impl<'a> Cpu<'a> {
         *cursor += self.frame_window_size as isize;
     }
 
+    #[allow(clippy::too_many_arguments)]
+    fn velocity(
+        t: T,
+        p: &Point3,
+        sources: &[Source],
+        output_ultrasound_cache: &[SampleCache<T>],
+        output_ultrasound_integral_cache: &[SampleCache<T>],
+        sound_speed: T,
+        offset: isize,
+        scale: f32,
+        interpolation: Interpolation,
+    ) -> [f32; 3] {
+        let v = sources
+            .iter()
+            .zip(
+                output_ultrasound_cache
+                    .iter()
+                    .zip(output_ultrasound_integral_cache.iter())
+                    .cycle(),
+            )
+            .fold(
+                [T::default(); 3],
+                |mut acc, (src, (output_ultrasound, integral))| {
+                    let d = difference::<T>(p, &src.pos);
+                    let dist = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                    let t_out = t - dist / sound_speed;
+                    let a = t_out / TransducerRecord::ts();
+                    let idx = a.floor();
+                    let alpha = a - idx;
+                    let idx = (idx.to_isize() - offset) as usize;
+                    let s = interpolation.interpolate(output_ultrasound, idx, alpha);
+                    let s_int = interpolation.interpolate(integral, idx, alpha);
+                    let c = T::from(src.coef) * (s / (sound_speed * dist) + s_int / (dist * dist))
+                        / dist;
+                    (0..3).for_each(|i| acc[i] += d[i] * c);
+                    acc
+                },
+            );
+        v.map(|v| v.to_f32() * scale)
+    }
+
     pub(crate) fn compute(
         &mut self,
         start_time: Duration,
@@ -147,62 +294,189 @@ impl<'a> Cpu<'a> {
         num_points_in_frame: usize,
         sound_speed: f32,
         offset: isize,
-    ) -> &Vec<Vec<f32>> {
+        interpolation: Interpolation,
+    ) -> Frame<'_> {
+        let sound_speed = T::from(sound_speed);
+        // Only the computation in single precision has the lane-parallel kernels.
+        let kernel = T::as_f32_slice(&[]).is_some().then(Kernel::detect);
+        let samples = self
+            .output_ultrasound_cache
+            .iter()
+            .filter_map(|cache| T::as_f32_slice(cache))
+            .collect::<Vec<_>>();
+        let coef = self.sources.iter().map(|src| src.coef).collect::<Vec<_>>();
+        // The sum is taken over the transducers and each set of their images separately, so that the image sources do not change the rounding of the direct ones.
+        let retarded = coef
+            .chunks(samples.len().max(1))
+            .map(|coef| Retarded {
+                samples: &samples,
+                coef,
+                sound_speed: sound_speed.to_f32(),
+                sampling_period: TransducerRecord::TS,
+                offset,
+                interpolation,
+            })
+            .collect::<Vec<_>>();
+        let pressure = |t: T, d: &[T]| match (kernel, T::as_f32_slice(d)) {
+            (Some(kernel), Some(d)) => d
+                .chunks(samples.len())
+                .zip(retarded.iter())
+                .map(|(d, retarded)| simd::instant(kernel, d, retarded, t.to_f32()))
+                .sum::<f32>(),
+            _ => d
+                .iter()
+                .zip(self.output_ultrasound_cache.iter().cycle())
+                .zip(self.sources.iter())
+                .map(|((&dist, output_ultrasound), src)| {
+                    let t_out = t - dist / sound_speed;
+                    let a = t_out / TransducerRecord::ts();
+                    let idx = a.floor();
+                    let alpha = a - idx;
+                    let idx = (idx.to_isize() - offset) as usize;
+                    T::from(src.coef) * interpolation.interpolate(output_ultrasound, idx, alpha)
+                        / dist
+                })
+                .sum::<T>()
+                .to_f32(),
+        };
+
         #[cfg(feature = "parallel")]
         {
-            (0..num_points_in_frame)
-                .into_par_iter()
-                .map(|i| (start_time + i as u32 * time_step).as_secs_f32())
-                .map(|t| {
-                    self.dists
-                        .iter()
-                        .map(|d| {
-                            Self::P0
-                                * d.iter()
-                                    .zip(self.output_ultrasound_cache.iter())
-                                    .map(|(dist, output_ultrasound)| {
-                                        let t_out = t - dist / sound_speed;
-                                        let a = t_out / TransducerRecord::TS;
-                                        let idx = a.floor() as isize;
-                                        let alpha = a - idx as f32;
-                                        let idx = (idx - offset) as usize;
-                                        (output_ultrasound[idx] * (1. - alpha)
-                                            + output_ultrasound[idx + 1] * alpha)
-                                            / dist
-                                    })
-                                    .sum::<f32>()
-                        })
-                        .collect()
-                })
-                .collect_into_vec(&mut self.cache);
+            self.cache.resize_with(num_points_in_frame, || {
+                vec![0.; self.target_positions.len()]
+            });
+            self.dists
+                .for_each_tile(&self.target_positions, &self.sources, |start, tile| {
+                    self.cache
+                        .par_iter_mut()
+                        .enumerate()
+                        .for_each(|(i, cache)| {
+                            let t = T::from_duration(start_time + i as u32 * time_step);
+                            cache[start..start + tile.len()]
+                                .iter_mut()
+                                .zip(tile.iter())
+                                .for_each(|(p, d)| *p = Cpu::P0 * pressure(t, d));
+                        });
+                });
+            if let Some(scale) = self.velocity_scale {
+                (0..num_points_in_frame)
+                    .into_par_iter()
+                    .map(|i| T::from_duration(start_time + i as u32 * time_step))
+                    .map(|t| {
+                        self.target_positions
+                            .iter()
+                            .map(|p| {
+                                Self::velocity(
+                                    t,
+                                    p,
+                                    &self.sources,
+                                    &self.output_ultrasound_cache,
+                                    &self.output_ultrasound_integral_cache,
+                                    sound_speed,
+                                    offset,
+                                    scale,
+                                    interpolation,
+                                )
+                            })
+                            .collect()
+                    })
+                    .collect_into_vec(&mut self.velocity_cache);
+            }
         }
         #[cfg(not(feature = "parallel"))]
         {
-            self.cache = (0..num_points_in_frame)
-                .map(|i| (start_time + i as u32 * time_step).as_secs_f32())
-                .map(|t| {
-                    self.dists
-                        .iter()
-                        .map(|d| {
-                            Self::P0
-                                * d.iter()
-                                    .zip(self.output_ultrasound_cache.iter())
-                                    .map(|(dist, output_ultrasound)| {
-                                        let t_out = t - dist / sound_speed;
-                                        let a = t_out / TransducerRecord::TS;
-                                        let idx = a.floor() as isize;
-                                        let alpha = a - idx as f32;
-                                        let idx = (idx - offset) as usize;
-                                        (output_ultrasound[idx] * (1. - alpha)
-                                            + output_ultrasound[idx + 1] * alpha)
-                                            / dist
-                                    })
-                                    .sum::<f32>()
-                        })
-                        .collect()
-                })
-                .collect();
+            self.cache.resize_with(num_points_in_frame, || {
+                vec![0.; self.target_positions.len()]
+            });
+            self.dists
+                .for_each_tile(&self.target_positions, &self.sources, |start, tile| {
+                    self.cache.iter_mut().enumerate().for_each(|(i, cache)| {
+                        let t = T::from_duration(start_time + i as u32 * time_step);
+                        cache[start..start + tile.len()]
+                            .iter_mut()
+                            .zip(tile.iter())
+                            .for_each(|(p, d)| *p = Cpu::P0 * pressure(t, d));
+                    });
+                });
+            if let Some(scale) = self.velocity_scale {
+                self.velocity_cache = (0..num_points_in_frame)
+                    .map(|i| T::from_duration(start_time + i as u32 * time_step))
+                    .map(|t| {
+                        self.target_positions
+                            .iter()
+                            .map(|p| {
+                                Self::velocity(
+                                    t,
+                                    p,
+                                    &self.sources,
+                                    &self.output_ultrasound_cache,
+                                    &self.output_ultrasound_integral_cache,
+                                    sound_speed,
+                                    offset,
+                                    scale,
+                                    interpolation,
+                                )
+                            })
+                            .collect()
+                    })
+                    .collect();
+            }
         }
-        &self.cache
+        (&self.cache, &self.velocity_cache)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(Interpolation::Nearest, 0.2)]
+    #[case(Interpolation::Linear, 2e-2)]
+    #[case(Interpolation::Cubic, 3e-3)]
+    #[case(Interpolation::WindowedSinc, 8e-4)]
+    fn interpolate_sinusoid(#[case] interpolation: Interpolation, #[case] tolerance: f32) {
+        const N: usize = 16;
+        let omega = 2. * std::f32::consts::PI / N as f32;
+        let v = (0..4 * N)
+            .map(|k| (omega * k as f32).sin())
+            .collect::<Vec<_>>();
+
+        let err = (N..3 * N)
+            .flat_map(|idx| (0..10).map(move |i| (idx, i as f32 / 10.)))
+            .map(|(idx, alpha)| {
+                let expect = (omega * (idx as f32 + alpha)).sin();
+                (interpolation.interpolate(&v, idx, alpha) - expect).abs()
+            })
+            .fold(0., f32::max);
+        assert!(err < tolerance);
+    }
+
+    #[rstest::rstest]
+    #[case(Interpolation::Nearest)]
+    #[case(Interpolation::Linear)]
+    #[case(Interpolation::Cubic)]
+    #[case(Interpolation::WindowedSinc)]
+    fn interpolate_at_sample(#[case] interpolation: Interpolation) {
+        let v = (0..16).map(|k| (k * k) as f32).collect::<Vec<_>>();
+        (4..12).for_each(|idx| {
+            approx::assert_relative_eq!(
+                v[idx],
+                interpolation.interpolate(&v, idx, 0.),
+                max_relative = 1e-6
+            );
+        });
+    }
+
+    #[test]
+    fn sample_cache() {
+        let mut cache = SampleCache::default();
+        cache.extend(0..8);
+        (0..10).for_each(|i| {
+            cache.drain(4);
+            cache.extend(8 + 4 * i..12 + 4 * i);
+            assert_eq!(&*cache, (4 + 4 * i..12 + 4 * i).collect::<Vec<_>>());
+            assert!(cache.buf.len() <= 16);
+        });
     }
 }
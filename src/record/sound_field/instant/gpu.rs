@@ -1,43 +1,29 @@
 use std::{
-    borrow::Cow,
-    collections::VecDeque,
     sync::{Arc, Condvar, Mutex},
     time::Duration,
 };
 
 use crate::{
-    EmulatorError,
-    record::{ULTRASOUND_PERIOD_COUNT, transducer::output_ultrasound::OutputUltrasound},
+    EmulatorError, GpuContext,
+    record::{
+        ULTRASOUND_PERIOD_COUNT,
+        sound_field::{
+            gpu_context::{Pipeline, Vec3, Vec4},
+            statistics::Aggregator,
+            statistics_gpu::StatisticsGpu,
+        },
+        transducer::output_ultrasound::OutputUltrasound,
+    },
 };
 
-use autd3::prelude::Point3;
-
 use bytemuck::NoUninit;
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
-use wgpu::{Buffer, BufferAddress, util::DeviceExt};
+use wgpu::{Buffer, BufferAddress};
 
-// GRCOV_EXCL_START
-#[derive(NoUninit, Clone, Copy)]
-#[repr(C)]
-struct Vec3 {
-    x: f32,
-    y: f32,
-    z: f32,
-    _pad: f32,
-}
-
-impl From<Point3> for Vec3 {
-    fn from(v: Point3) -> Self {
-        Self {
-            x: v.x,
-            y: v.y,
-            z: v.z,
-            _pad: 0.,
-        }
-    }
-}
+use super::{super::reflector::Source, Frame, Interpolation, SampleCache, drain_frame, push_frame};
 
+// GRCOV_EXCL_START
 #[derive(NoUninit, Clone, Copy)]
 #[repr(C)]
 struct Pc {
@@ -46,27 +32,38 @@ struct Pc {
     num_trans: u32,
     offset: i32,
     output_ultrasound_stride: u32,
-    _pad: [u32; 3],
+    velocity: u32,
+    velocity_scale: f32,
+    interpolation: u32,
 }
 // GRCOV_EXCL_STOP
 
 #[derive(Debug)]
 pub(crate) struct Gpu<'a> {
     output_ultrasound: Vec<OutputUltrasound<'a>>,
-    output_ultrasound_cache: Vec<VecDeque<f32>>,
+    output_ultrasound_cache: Vec<SampleCache<f32>>,
+    output_ultrasound_integral_cache: Vec<SampleCache<f32>>,
     frame_window_size: usize,
     num_transducers: u32,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+    context: GpuContext,
     pipeline: wgpu::ComputePipeline,
     bind_group: wgpu::BindGroup,
     buf_staging_output_ultrasound: Buffer,
     buf_storage_output_ultrasound: Buffer,
+    buf_staging_output_ultrasound_integral: Buffer,
+    buf_storage_output_ultrasound_integral: Buffer,
     buf_output_ultrasound_size: BufferAddress,
     buf_storage_dst: Buffer,
     buf_staging_dst: Buffer,
+    buf_storage_velocity: Buffer,
+    buf_staging_velocity: Buffer,
     update_buf_output_ultrasound: bool,
     cache: Vec<Vec<f32>>,
+    velocity_scale: Option<f32>,
+    velocity_buffer: Vec<[f32; 4]>,
+    velocity_cache: Vec<Vec<[f32; 3]>>,
+    // If `Some`, the pressure is reduced on GPU instead of being read back.
+    statistics: Option<StatisticsGpu>,
 }
 
 impl<'a> Gpu<'a> {
@@ -75,19 +72,17 @@ impl<'a> Gpu<'a> {
         x: &[f32],
         y: &[f32],
         z: &[f32],
-        transducer_positions: impl Iterator<Item = Point3>,
+        sources: Vec<Source>,
         output_ultrasound: Vec<OutputUltrasound<'a>>,
         frame_window_size: usize,
         num_points_in_frame: usize,
         cache_size: isize,
+        velocity_scale: Option<f32>,
+        context: GpuContext,
     ) -> Result<Self, EmulatorError> {
-        let target_pos = x
-            .iter()
-            .zip(y.iter())
-            .zip(z.iter())
-            .map(|((&x, &y), &z)| Vec3 { x, y, z, _pad: 0. })
-            .collect::<Vec<_>>();
-        let transducer_pos = transducer_positions.map(Vec3::from).collect::<Vec<_>>();
+        let target_pos = Vec3::targets(x, y, z);
+        let transducer_pos = sources.into_iter().map(Vec4::from).collect::<Vec<_>>();
+        let num_transducers = output_ultrasound.len() as _;
 
         let buf_output_ultrasound_size = (output_ultrasound.len()
             * cache_size as usize
@@ -95,48 +90,29 @@ impl<'a> Gpu<'a> {
             * size_of::<f32>()) as BufferAddress;
         let buf_dst_size = (target_pos.len() * size_of::<f32>()) as BufferAddress;
         let buf_target_pos_size = (target_pos.len() * size_of::<Vec3>()) as BufferAddress;
-        let buf_tr_pos_size = (transducer_pos.len() * size_of::<Vec3>()) as BufferAddress;
-
-        let instance = wgpu::Instance::default();
-
-        let adapter = crate::utils::executor::block_on(
-            instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
-        )?;
+        let buf_tr_pos_size = (transducer_pos.len() * size_of::<Vec4>()) as BufferAddress;
+        let (buf_output_ultrasound_integral_size, buf_velocity_size) = if velocity_scale.is_some() {
+            (
+                buf_output_ultrasound_size,
+                (target_pos.len() * size_of::<[f32; 4]>()) as BufferAddress,
+            )
+        } else {
+            (
+                size_of::<f32>() as BufferAddress,
+                size_of::<[f32; 4]>() as BufferAddress,
+            )
+        };
 
-        let (device, queue) = crate::utils::executor::block_on(
-            adapter.request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::IMMEDIATES,
-                required_limits: wgpu::Limits {
-                    max_immediate_size: std::mem::size_of::<Pc>() as u32,
-                    max_storage_buffers_per_shader_stage: 5,
-                    max_storage_buffer_binding_size: buf_output_ultrasound_size
-                        .max(buf_target_pos_size)
-                        .max(buf_tr_pos_size)
-                        as _,
-                    ..wgpu::Limits::downlevel_defaults()
-                },
-                memory_hints: wgpu::MemoryHints::MemoryUsage,
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            }),
+        context.check_binding_size(
+            buf_output_ultrasound_size
+                .max(buf_target_pos_size)
+                .max(buf_tr_pos_size)
+                .max(buf_velocity_size),
         )?;
+        let device = context.device();
 
-        let cs_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
-        });
-
-        let buf_storage_target_pos = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&target_pos),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-        });
-        let buf_storage_trans_pos = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&transducer_pos),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-        });
+        let buf_storage_target_pos = context.storage_buffer(&target_pos);
+        let buf_storage_trans_pos = context.storage_buffer(&transducer_pos);
 
         let buf_staging_output_ultrasound = device.create_buffer(&wgpu::BufferDescriptor {
             label: None,
@@ -153,163 +129,150 @@ impl<'a> Gpu<'a> {
             mapped_at_creation: false,
         });
 
-        let buf_staging_dst = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: buf_dst_size,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let buf_storage_dst = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: buf_dst_size,
-            usage: wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST
-                | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
-
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
+        let buf_staging_output_ultrasound_integral =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                size: buf_output_ultrasound_integral_size,
+                usage: wgpu::BufferUsages::MAP_WRITE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            });
+        let buf_storage_output_ultrasound_integral =
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: None,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_SRC
+                    | wgpu::BufferUsages::COPY_DST,
+                size: buf_output_ultrasound_integral_size,
+                mapped_at_creation: false,
+            });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: buf_storage_output_ultrasound.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: buf_storage_trans_pos.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: buf_storage_target_pos.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: buf_storage_dst.as_entire_binding(),
-                },
-            ],
-        });
+        let (buf_storage_velocity, buf_staging_velocity) =
+            context.output_buffers(buf_velocity_size);
+        let (buf_storage_dst, buf_staging_dst) = context.output_buffers(buf_dst_size);
 
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[Some(&bind_group_layout)],
-            immediate_size: std::mem::size_of::<Pc>() as u32,
-        });
+        let Pipeline {
+            bind_group_layout,
+            pipeline,
+        } = context.compute_pipeline(
+            module_path!(),
+            include_str!("shader.wgsl"),
+            &[true, true, true, false, true, false],
+            size_of::<Pc>() as _,
+        );
 
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            module: &cs_module,
-            entry_point: None,
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        let bind_group = context.bind_group(
+            &bind_group_layout,
+            &[
+                &buf_storage_output_ultrasound,
+                &buf_storage_trans_pos,
+                &buf_storage_target_pos,
+                &buf_storage_dst,
+                &buf_storage_output_ultrasound_integral,
+                &buf_storage_velocity,
+            ],
+        );
 
         Ok(Self {
             output_ultrasound,
             output_ultrasound_cache: Vec::new(),
+            output_ultrasound_integral_cache: Vec::new(),
             frame_window_size,
-            num_transducers: transducer_pos.len() as _,
-            device,
-            queue,
+            num_transducers,
+            context,
             pipeline,
             bind_group,
             buf_staging_output_ultrasound,
             buf_storage_output_ultrasound,
+            buf_staging_output_ultrasound_integral,
+            buf_storage_output_ultrasound_integral,
             buf_output_ultrasound_size,
             update_buf_output_ultrasound: false,
             buf_storage_dst,
             buf_staging_dst,
+            buf_storage_velocity,
+            buf_staging_velocity,
             cache: vec![vec![0.0f32; target_pos.len()]; num_points_in_frame],
+            velocity_scale,
+            velocity_buffer: if velocity_scale.is_some() {
+                vec![[0.0f32; 4]; target_pos.len()]
+            } else {
+                Vec::new()
+            },
+            velocity_cache: if velocity_scale.is_some() {
+                vec![vec![[0.0f32; 3]; target_pos.len()]; num_points_in_frame]
+            } else {
+                Vec::new()
+            },
+            statistics: None,
         })
     }
 
+    // Reduces the pressure of each time on GPU instead of returning it until `finish_statistics` is called.
+    pub(crate) fn start_statistics(&mut self) -> Result<(), EmulatorError> {
+        self.statistics = Some(StatisticsGpu::new(
+            &self.context,
+            &self.buf_storage_dst,
+            self.cache[0].len(),
+            false,
+        )?);
+        Ok(())
+    }
+
+    pub(crate) fn finish_statistics(
+        &mut self,
+        aggregator: &mut Aggregator,
+    ) -> Result<(), EmulatorError> {
+        self.statistics
+            .take()
+            .map_or(Ok(()), |statistics| statistics.finish(aggregator))
+    }
+
     pub(crate) fn init(&mut self, cache_size: isize, cursor: &mut isize, rem_frame: &mut usize) {
         if self.output_ultrasound_cache.is_empty() {
+            let velocity = self.velocity_scale.is_some();
             #[cfg(feature = "parallel")]
             {
-                self.output_ultrasound_cache = self
+                (
+                    self.output_ultrasound_cache,
+                    self.output_ultrasound_integral_cache,
+                ) = self
                     .output_ultrasound
                     .par_iter_mut()
                     .map(|ut| {
-                        (0..cache_size)
-                            .flat_map(|i| {
-                                if *cursor + i >= 0 {
-                                    ut._next(1)
-                                        .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT])
-                                } else {
-                                    vec![0.; ULTRASOUND_PERIOD_COUNT]
-                                }
-                            })
-                            .collect()
+                        let mut cache = SampleCache::default();
+                        let mut integral_cache = SampleCache::default();
+                        (0..cache_size).for_each(|i| {
+                            push_frame(
+                                (*cursor + i >= 0).then_some(&mut *ut),
+                                &mut cache,
+                                velocity.then_some(&mut integral_cache),
+                            )
+                        });
+                        (cache, integral_cache)
                     })
-                    .collect();
+                    .unzip();
             }
             #[cfg(not(feature = "parallel"))]
             {
-                self.output_ultrasound_cache = self
+                (
+                    self.output_ultrasound_cache,
+                    self.output_ultrasound_integral_cache,
+                ) = self
                     .output_ultrasound
                     .iter_mut()
                     .map(|ut| {
-                        (0..cache_size)
-                            .flat_map(|i| {
-                                if *cursor + i >= 0 {
-                                    ut._next(1)
-                                        .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT])
-                                } else {
-                                    vec![0.; ULTRASOUND_PERIOD_COUNT]
-                                }
-                            })
-                            .collect()
+                        let mut cache = SampleCache::default();
+                        let mut integral_cache = SampleCache::default();
+                        (0..cache_size).for_each(|i| {
+                            push_frame(
+                                (*cursor + i >= 0).then_some(&mut *ut),
+                                &mut cache,
+                                velocity.then_some(&mut integral_cache),
+                            )
+                        });
+                        (cache, integral_cache)
                     })
-                    .collect();
+                    .unzip();
             }
             *cursor += cache_size;
             *rem_frame = self.frame_window_size;
@@ -335,19 +298,21 @@ impl<'a> Gpu<'a> {
                 (c + self.frame_window_size as isize) as usize
             }
         };
+        let velocity = self.velocity_scale.is_some();
         #[cfg(feature = "parallel")]
         {
             self.output_ultrasound_cache
                 .iter_mut()
+                .zip(self.output_ultrasound_integral_cache.iter_mut())
                 .zip(self.output_ultrasound.iter_mut())
                 .par_bridge()
-                .for_each(|(cache, output_ultrasound)| {
-                    drop(cache.drain(0..ULTRASOUND_PERIOD_COUNT * n));
+                .for_each(|((cache, integral_cache), output_ultrasound)| {
+                    drain_frame(n, cache, velocity.then_some(&mut *integral_cache));
                     (0..n).for_each(|_| {
-                        cache.extend(
-                            output_ultrasound
-                                ._next(1)
-                                .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT]),
+                        push_frame(
+                            Some(output_ultrasound),
+                            cache,
+                            velocity.then_some(&mut *integral_cache),
                         );
                     })
                 });
@@ -356,14 +321,15 @@ impl<'a> Gpu<'a> {
         {
             self.output_ultrasound_cache
                 .iter_mut()
+                .zip(self.output_ultrasound_integral_cache.iter_mut())
                 .zip(self.output_ultrasound.iter_mut())
-                .for_each(|(cache, output_ultrasound)| {
-                    drop(cache.drain(0..ULTRASOUND_PERIOD_COUNT * n));
+                .for_each(|((cache, integral_cache), output_ultrasound)| {
+                    drain_frame(n, cache, velocity.then_some(&mut *integral_cache));
                     (0..n).for_each(|_| {
-                        cache.extend(
-                            output_ultrasound
-                                ._next(1)
-                                .unwrap_or_else(|| vec![0.; ULTRASOUND_PERIOD_COUNT]),
+                        push_frame(
+                            Some(output_ultrasound),
+                            cache,
+                            velocity.then_some(&mut *integral_cache),
                         );
                     })
                 });
@@ -372,7 +338,23 @@ impl<'a> Gpu<'a> {
     }
 
     fn copy_output_ultrasound(&self) -> Result<(), EmulatorError> {
-        let buffer_slice = self.buf_staging_output_ultrasound.slice(..);
+        Self::write_staging(
+            self.context.device(),
+            &self.buf_staging_output_ultrasound,
+            &self.output_ultrasound_cache,
+        );
+        if self.velocity_scale.is_some() {
+            Self::write_staging(
+                self.context.device(),
+                &self.buf_staging_output_ultrasound_integral,
+                &self.output_ultrasound_integral_cache,
+            );
+        }
+        Ok(())
+    }
+
+    fn write_staging(device: &wgpu::Device, staging: &Buffer, cache: &[SampleCache<f32>]) {
+        let buffer_slice = staging.slice(..);
         let pair = Arc::new((Mutex::new(false), Condvar::new()));
         let pair2 = Arc::clone(&pair);
         buffer_slice.map_async(wgpu::MapMode::Write, move |_| {
@@ -381,7 +363,7 @@ impl<'a> Gpu<'a> {
             *started = true;
             cvar.notify_one();
         });
-        self.device
+        device
             .poll(wgpu::PollType::wait_indefinitely())
             .expect("failed to poll device");
         let (lock, cvar) = &*pair;
@@ -391,17 +373,15 @@ impl<'a> Gpu<'a> {
             started = cvar.wait(started).unwrap();
         }
         // GRCOV_EXCL_STOP
-        let src = self
-            .output_ultrasound_cache
+        let src = cache
             .iter()
-            .flatten()
-            .cloned()
+            .flat_map(|c| c.iter())
+            .copied()
             .collect::<Vec<_>>();
         buffer_slice
             .get_mapped_range_mut()
             .copy_from_slice(bytemuck::cast_slice(&src));
-        self.buf_staging_output_ultrasound.unmap();
-        Ok(())
+        staging.unmap();
     }
 
     pub(crate) fn compute(
@@ -411,7 +391,8 @@ impl<'a> Gpu<'a> {
         num_points_in_frame: usize,
         sound_speed: f32,
         offset: isize,
-    ) -> Result<&Vec<Vec<f32>>, EmulatorError> {
+        interpolation: Interpolation,
+    ) -> Result<Frame<'_>, EmulatorError> {
         for i in 0..num_points_in_frame {
             let t = (start_time + i as u32 * time_step).as_secs_f32();
             let pc = Pc {
@@ -420,11 +401,15 @@ impl<'a> Gpu<'a> {
                 num_trans: self.num_transducers,
                 offset: offset as _,
                 output_ultrasound_stride: self.output_ultrasound_cache[0].len() as _,
-                _pad: [0; 3],
+                velocity: self.velocity_scale.is_some() as _,
+                velocity_scale: self.velocity_scale.unwrap_or(0.),
+                // The discriminant matches the constants in the shader.
+                interpolation: interpolation as _,
             };
 
             let mut encoder = self
-                .device
+                .context
+                .device()
                 .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
 
             if self.update_buf_output_ultrasound {
@@ -436,6 +421,15 @@ impl<'a> Gpu<'a> {
                     0,
                     self.buf_output_ultrasound_size,
                 );
+                if self.velocity_scale.is_some() {
+                    encoder.copy_buffer_to_buffer(
+                        &self.buf_staging_output_ultrasound_integral,
+                        0,
+                        &self.buf_storage_output_ultrasound_integral,
+                        0,
+                        self.buf_output_ultrasound_size,
+                    );
+                }
                 self.update_buf_output_ultrasound = false;
             }
 
@@ -449,6 +443,11 @@ impl<'a> Gpu<'a> {
                 cpass.set_immediates(0, bytemuck::bytes_of(&pc));
                 cpass.dispatch_workgroups(((self.cache[0].len() - 1) / 64 + 1) as _, 1, 1);
             }
+            if let Some(statistics) = &mut self.statistics {
+                self.context.queue().submit(Some(encoder.finish()));
+                statistics.accumulate();
+                continue;
+            }
             encoder.copy_buffer_to_buffer(
                 &self.buf_storage_dst,
                 0,
@@ -456,37 +455,33 @@ impl<'a> Gpu<'a> {
                 0,
                 (self.cache[0].len() * size_of::<f32>()) as u64,
             );
+            if self.velocity_scale.is_some() {
+                encoder.copy_buffer_to_buffer(
+                    &self.buf_storage_velocity,
+                    0,
+                    &self.buf_staging_velocity,
+                    0,
+                    (self.velocity_buffer.len() * size_of::<[f32; 4]>()) as u64,
+                );
+            }
 
-            self.queue.submit(Some(encoder.finish()));
+            self.context.queue().submit(Some(encoder.finish()));
 
-            let buffer_slice = self.buf_staging_dst.slice(..);
-            let result =
-                std::sync::Arc::new((std::sync::Mutex::new(None), std::sync::Condvar::new()));
-            let result_clone = result.clone();
-            buffer_slice.map_async(wgpu::MapMode::Read, move |r| {
-                let (lock, cvar) = &*result_clone;
-                let mut pending = lock.lock().unwrap();
-                *pending = Some(r);
-                cvar.notify_one();
-            });
-            self.device
-                .poll(wgpu::PollType::wait_indefinitely())
-                .expect("failed to poll device");
-            let (lock, cvar) = &*result;
-            let mut pending = lock.lock().unwrap();
-            // GRCOV_EXCL_START
-            while pending.is_none() {
-                pending = cvar.wait(pending).unwrap();
-            }
-            // GRCOV_EXCL_STOP
-            pending.take().unwrap()?;
-            {
-                let data = buffer_slice.get_mapped_range();
-                self.cache[i].copy_from_slice(bytemuck::cast_slice(&data));
+            self.context
+                .read_buffer(&self.buf_staging_dst, &mut self.cache[i])?;
+            if self.velocity_scale.is_some() {
+                self.context
+                    .read_buffer(&self.buf_staging_velocity, &mut self.velocity_buffer)?;
+                self.velocity_cache[i]
+                    .iter_mut()
+                    .zip(self.velocity_buffer.iter())
+                    .for_each(|(dst, src)| dst.copy_from_slice(&src[..3]));
             }
-            self.buf_staging_dst.unmap();
         }
 
-        Ok(&self.cache)
+        if self.statistics.is_some() {
+            return Ok((&[], &[]));
+        }
+        Ok((&self.cache, &self.velocity_cache))
     }
 }
@@ -3,28 +3,113 @@ mod cpu;
 mod gpu;
 mod option;
 
-use std::time::Duration;
+use std::{ops::Deref, time::Duration};
 
 use autd3::driver::common::ULTRASOUND_PERIOD;
 #[cfg(feature = "polars")]
 use polars::{df, frame::DataFrame, prelude::Column};
 
-use super::{super::Record, SoundFieldOption};
-use crate::{EmulatorError, Range, record::ULTRASOUND_PERIOD_COUNT};
+use super::{
+    super::Record,
+    SoundFieldOption,
+    distance::Distances,
+    field::Field,
+    precision::{Float, Precision},
+    statistics::{Aggregator, Statistic},
+};
+use crate::{
+    EmulatorError, OutputUnit, Range, RangeAxis,
+    record::{ULTRASOUND_PERIOD_COUNT, transducer::output_ultrasound::OutputUltrasound},
+};
+
+pub use option::{InstantRecordOption, Interpolation};
+
+// The samples of the emitted ultrasound of a transducer, which are kept contiguous so that they are read as a slice.
+// The drained samples are removed when the rest are not more than them, so that each sample is moved once on average.
+#[derive(Debug, Default)]
+struct SampleCache<T> {
+    buf: Vec<T>,
+    start: usize,
+}
+
+impl<T> SampleCache<T> {
+    fn extend(&mut self, v: impl IntoIterator<Item = T>) {
+        if self.start > 0 && self.start >= self.buf.len() - self.start {
+            drop(self.buf.drain(..self.start));
+            self.start = 0;
+        }
+        self.buf.extend(v);
+    }
+
+    fn drain(&mut self, n: usize) {
+        assert!(self.start + n <= self.buf.len());
+        self.start += n;
+    }
+}
+
+impl<T> Deref for SampleCache<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.buf[self.start..]
+    }
+}
+
+// Pushes the emitted ultrasound (and its time integral if `integral_cache` is given) of the next frame.
+// If `output_ultrasound` is `None` or it reaches the end of the record, zeros are pushed instead.
+fn push_frame<T: Float>(
+    output_ultrasound: Option<&mut OutputUltrasound<T>>,
+    cache: &mut SampleCache<T>,
+    integral_cache: Option<&mut SampleCache<T>>,
+) {
+    match integral_cache {
+        Some(integral_cache) => {
+            let (v, integral) = output_ultrasound
+                .and_then(|ut| ut._next_with_integral(1))
+                .unwrap_or_else(|| {
+                    (
+                        vec![T::default(); ULTRASOUND_PERIOD_COUNT],
+                        vec![T::default(); ULTRASOUND_PERIOD_COUNT],
+                    )
+                });
+            cache.extend(v);
+            integral_cache.extend(integral);
+        }
+        None => cache.extend(
+            output_ultrasound
+                .and_then(|ut| ut._next(1))
+                .unwrap_or_else(|| vec![T::default(); ULTRASOUND_PERIOD_COUNT]),
+        ),
+    }
+}
 
-pub use option::InstantRecordOption;
+// Pressure and particle velocity of each time in a frame.
+type Frame<'a> = (&'a [Vec<f32>], &'a [Vec<[f32; 3]>]);
+
+fn drain_frame<T>(
+    n: usize,
+    cache: &mut SampleCache<T>,
+    integral_cache: Option<&mut SampleCache<T>>,
+) {
+    cache.drain(ULTRASOUND_PERIOD_COUNT * n);
+    if let Some(integral_cache) = integral_cache {
+        integral_cache.drain(ULTRASOUND_PERIOD_COUNT * n);
+    }
+}
 
 #[derive(Debug)]
 enum ComputeDevice<'a> {
-    Cpu(cpu::Cpu<'a>),
+    Cpu(Box<cpu::Cpu<'a>>),
+    CpuDouble(Box<cpu::Cpu<'a, f64>>),
     #[cfg(feature = "gpu")]
-    Gpu(gpu::Gpu<'a>),
+    Gpu(Box<gpu::Gpu<'a>>),
 }
 
 impl ComputeDevice<'_> {
     fn init(&mut self, cache_size: isize, cursor: &mut isize, rem_frame: &mut usize) {
         match self {
             Self::Cpu(cpu) => cpu.init(cache_size, cursor, rem_frame),
+            Self::CpuDouble(cpu) => cpu.init(cache_size, cursor, rem_frame),
             #[cfg(feature = "gpu")]
             Self::Gpu(gpu) => gpu.init(cache_size, cursor, rem_frame),
         }
@@ -33,11 +118,29 @@ impl ComputeDevice<'_> {
     fn progress(&mut self, cursor: &mut isize) {
         match self {
             Self::Cpu(cpu) => cpu.progress(cursor),
+            Self::CpuDouble(cpu) => cpu.progress(cursor),
             #[cfg(feature = "gpu")]
             Self::Gpu(gpu) => gpu.progress(cursor),
         }
     }
 
+    // Starts reducing the pressure into the statistics on the device, and returns false if the device does not support it.
+    fn start_statistics(&mut self) -> Result<bool, EmulatorError> {
+        match self {
+            #[cfg(feature = "gpu")]
+            Self::Gpu(gpu) => gpu.start_statistics().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    fn finish_statistics(&mut self, _aggregator: &mut Aggregator) -> Result<(), EmulatorError> {
+        match self {
+            #[cfg(feature = "gpu")]
+            Self::Gpu(gpu) => gpu.finish_statistics(_aggregator),
+            _ => Ok(()),
+        }
+    }
+
     fn compute(
         &mut self,
         start_time: Duration,
@@ -45,7 +148,8 @@ impl ComputeDevice<'_> {
         num_points_in_frame: usize,
         sound_speed: f32,
         offset: isize,
-    ) -> Result<&Vec<Vec<f32>>, EmulatorError> {
+        interpolation: Interpolation,
+    ) -> Result<Frame<'_>, EmulatorError> {
         match self {
             Self::Cpu(cpu) => Ok(cpu.compute(
                 start_time,
@@ -53,6 +157,15 @@ impl ComputeDevice<'_> {
                 num_points_in_frame,
                 sound_speed,
                 offset,
+                interpolation,
+            )),
+            Self::CpuDouble(cpu) => Ok(cpu.compute(
+                start_time,
+                time_step,
+                num_points_in_frame,
+                sound_speed,
+                offset,
+                interpolation,
             )),
             #[cfg(feature = "gpu")]
             Self::Gpu(gpu) => gpu.compute(
@@ -61,6 +174,7 @@ impl ComputeDevice<'_> {
                 num_points_in_frame,
                 sound_speed,
                 offset,
+                interpolation,
             ),
         }
     }
@@ -77,6 +191,7 @@ pub struct Instant<'a> {
     x: Vec<f32>,
     y: Vec<f32>,
     z: Vec<f32>,
+    axes: Vec<RangeAxis>,
     frame_window_size: usize,
     cache_size: isize,
     num_points_in_frame: usize,
@@ -96,11 +211,14 @@ impl Instant<'_> {
     }
 
     /// Progresses by the specified time and calculates the instant sound field during that time.
+    ///
+    /// If [`InstantRecordOption::particle_velocity`] is true, x, y and z components of the particle velocity follow the pressure for each time.
     #[cfg(feature = "polars")]
     pub fn next(&mut self, duration: Duration) -> Result<DataFrame, EmulatorError> {
         let n = self.next_time_len(duration);
+        let cols = self.next_cols_per_time();
         let mut time = vec![0; n];
-        let mut v = vec![vec![0.0; self.next_points_len()]; n];
+        let mut v = vec![vec![0.0; self.next_points_len()]; cols * n];
         self.next_inplace(
             duration,
             false,
@@ -111,13 +229,55 @@ impl Instant<'_> {
         Ok(DataFrame::new(
             self.next_points_len(),
             time.iter()
-                .zip(v.iter())
-                .map(|(t, v)| Column::new(format!("p[Pa]@{t}[ns]").into(), v))
+                .zip(v.chunks(cols))
+                .flat_map(|(t, v)| {
+                    [
+                        format!("{}@{t}[ns]", self.option.unit.column_name("p")),
+                        format!("vx[m/s]@{t}[ns]"),
+                        format!("vy[m/s]@{t}[ns]"),
+                        format!("vz[m/s]@{t}[ns]"),
+                    ]
+                    .into_iter()
+                    .zip(v.iter())
+                    .map(|(name, v)| Column::new(name.into(), v))
+                })
                 .collect::<Vec<_>>(),
         )
         .unwrap())
     }
 
+    /// Progresses by the specified time and calculates the instant sound pressure during that time reshaped onto the axes of the range.
+    pub fn next_field(&mut self, duration: Duration) -> Result<Field, EmulatorError> {
+        let mut field = Field::new(self.axes.clone());
+        self.for_each_frame(duration, |t, p| field.push(Duration::from_nanos(t), p))?;
+        Ok(field)
+    }
+
+    /// Progresses by the specified time and calls `f` with the time \[ns\] and the instant sound pressure of each time during that time.
+    ///
+    /// Unlike [`Instant::next`], the sound pressure is passed by reference to the internal buffer, so that the memory usage does not depend on the duration.
+    pub fn for_each_frame(
+        &mut self,
+        duration: Duration,
+        mut f: impl FnMut(u64, &[f32]),
+    ) -> Result<(), EmulatorError> {
+        let (unit, density, sound_speed) = (
+            self.option.unit,
+            self.option.density,
+            self.option.sound_speed,
+        );
+        let mut buf = Vec::new();
+        self.process(duration, false, |t, p, _| {
+            if unit == OutputUnit::Pascal {
+                return f(t, p);
+            }
+            buf.clear();
+            buf.extend_from_slice(p);
+            unit.convert(&mut buf, density, sound_speed);
+            f(t, &buf)
+        })
+    }
+
     /// Progresses by the specified time.
     pub fn skip(&mut self, duration: Duration) -> Result<&mut Self, EmulatorError> {
         self.next_inplace(duration, true, &mut [], std::iter::empty())?;
@@ -152,6 +312,11 @@ impl Instant<'_> {
         self.x.len()
     }
 
+    #[doc(hidden)]
+    pub fn next_cols_per_time(&self) -> usize {
+        if self.option.particle_velocity { 4 } else { 1 }
+    }
+
     #[doc(hidden)]
     pub fn next_inplace(
         &mut self,
@@ -159,6 +324,92 @@ impl Instant<'_> {
         skip: bool,
         time: &mut [u64],
         mut v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        let particle_velocity = self.option.particle_velocity;
+        let (unit, density, sound_speed) = (
+            self.option.unit,
+            self.option.density,
+            self.option.sound_speed,
+        );
+        let mut idx = 0;
+        self.process(duration, skip, |t, p, vel| {
+            time[idx] = t;
+            unsafe {
+                let dst = v.next().unwrap();
+                std::ptr::copy_nonoverlapping(p.as_ptr(), dst, p.len());
+                unit.convert(
+                    std::slice::from_raw_parts_mut(dst, p.len()),
+                    density,
+                    sound_speed,
+                );
+            }
+            if particle_velocity {
+                (0..3).for_each(|c| {
+                    let dst = v.next().unwrap();
+                    vel.iter()
+                        .enumerate()
+                        .for_each(|(j, vel)| unsafe { *dst.add(j) = vel[c] });
+                });
+            }
+            idx += 1;
+        })
+    }
+
+    /// Progresses by the specified time and calculates the statistics of the instant sound pressure for each point during that time.
+    ///
+    /// Unlike [`Instant::next`], the sound field at each time is not returned, so that the memory usage does not depend on the duration.
+    /// On GPU, the statistics except for [`Statistic::Percentile`] are reduced on the device without reading back the sound field at each time.
+    #[cfg(feature = "polars")]
+    pub fn aggregate(
+        &mut self,
+        duration: Duration,
+        statistics: &[Statistic],
+    ) -> Result<DataFrame, EmulatorError> {
+        let mut v = vec![vec![0.0; self.next_points_len()]; statistics.len()];
+        self.aggregate_inplace(duration, statistics, v.iter_mut().map(|v| v.as_mut_ptr()))?;
+
+        Ok(DataFrame::new(
+            self.next_points_len(),
+            statistics
+                .iter()
+                .zip(v.iter())
+                .map(|(s, v)| Column::new(format!("p_{s}[Pa]").into(), v))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap())
+    }
+
+    #[doc(hidden)]
+    pub fn aggregate_inplace(
+        &mut self,
+        duration: Duration,
+        statistics: &[Statistic],
+        v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        let mut aggregator = Aggregator::new(
+            statistics,
+            self.next_points_len(),
+            self.option
+                .memory_limits_hint_mb
+                .saturating_mul(1024 * 1024),
+        )?;
+        let reduce =
+            !Aggregator::needs_samples(statistics) && self.compute_device.start_statistics()?;
+        let result = self.process(duration, false, |_, p, _| aggregator.push(p));
+        if reduce {
+            self.compute_device.finish_statistics(&mut aggregator)?;
+        }
+        result?;
+        aggregator.finish(v);
+        Ok(())
+    }
+
+    // Calls `f` with the time, the pressure and the particle velocity (empty if disabled) of each time.
+    fn process(
+        &mut self,
+        duration: Duration,
+        skip: bool,
+        mut f: impl FnMut(u64, &[f32], &[[f32; 3]]),
     ) -> Result<(), EmulatorError> {
         if !duration
             .as_nanos()
@@ -177,10 +428,10 @@ impl Instant<'_> {
 
         let time_step = self.option.time_step;
         let sound_speed = self.option.sound_speed;
+        let interpolation = self.option.interpolation;
 
         let mut cur_frame = self.last_frame;
 
-        let mut idx = 0;
         loop {
             if cur_frame == self.last_frame + num_frames {
                 break;
@@ -205,23 +456,20 @@ impl Instant<'_> {
                 let offset = (self.cursor - self.cache_size) * ULTRASOUND_PERIOD_COUNT as isize;
                 for i in 0..num_frames {
                     let start_time = (cur_frame + i) as u32 * ULTRASOUND_PERIOD;
-                    let r = self.compute_device.compute(
+                    let (r, vel) = self.compute_device.compute(
                         start_time,
                         time_step,
                         self.num_points_in_frame,
                         sound_speed,
                         offset,
+                        interpolation,
                     )?;
-                    (0..r.len()).for_each(|i| {
-                        time[idx] = (start_time + (i as u32 * time_step)).as_nanos() as u64;
-                        unsafe {
-                            std::ptr::copy_nonoverlapping(
-                                r[i].as_ptr(),
-                                v.next().unwrap(),
-                                r[i].len(),
-                            );
-                        }
-                        idx += 1;
+                    r.iter().enumerate().for_each(|(i, r)| {
+                        f(
+                            (start_time + (i as u32 * time_step)).as_nanos() as u64,
+                            r,
+                            vel.get(i).map_or(&[], |v| v.as_slice()),
+                        )
                     });
                 }
             }
@@ -252,88 +500,133 @@ impl Record {
             (ULTRASOUND_PERIOD.as_nanos() / option.time_step.as_nanos()) as usize;
 
         let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+        let axes = range.axes();
 
-        let min_dist = crate::utils::aabb::aabb_min_dist(&self.aabb, &range.aabb());
-        let max_dist = crate::utils::aabb::aabb_max_dist(&self.aabb, &range.aabb());
+        // Reflected waves arrive later than direct ones, so the images are also taken into account.
+        let sources = self.sources(&option.reflectors, option.reflection_order);
+        let sources_aabb = self.sources_aabb(&sources);
+        let min_dist = crate::utils::aabb::aabb_min_dist(&sources_aabb, &range.aabb());
+        let max_dist = crate::utils::aabb::aabb_max_dist(&sources_aabb, &range.aabb());
 
+        // Interpolation with more than two samples may refer to the adjacent frames.
+        let margin = (option.interpolation.half_width() > 1) as usize;
         let required_frame_size = (max_dist / option.sound_speed / ULTRASOUND_PERIOD.as_secs_f32())
             .ceil() as usize
-            - (min_dist / option.sound_speed / ULTRASOUND_PERIOD.as_secs_f32()).floor() as usize;
+            - (min_dist / option.sound_speed / ULTRASOUND_PERIOD.as_secs_f32()).floor() as usize
+            + 2 * margin;
 
-        let frame_window_size = {
+        let (frame_window_size, dists_memory_limits) = {
             let num_transducers = self.records.len();
 
             let mem_usage = x.len() * size_of::<f32>()
                 + y.len() * size_of::<f32>()
                 + z.len() * size_of::<f32>();
 
+            let memory_limits = option.memory_limits_hint_mb.saturating_mul(1024 * 1024);
+
+            let cache_per_frame = if option.particle_velocity { 2 } else { 1 }
+                * ULTRASOUND_PERIOD_COUNT
+                * num_transducers
+                * size_of::<f32>();
+
+            // The distances on CPU take the memory left after the cache of the minimum frames, and are calculated for each tile of points if they do not fit in it.
+            let dists_memory_limits = memory_limits
+                .saturating_sub(mem_usage + (required_frame_size + 1) * cache_per_frame);
+            let dists_mem_usage = match option.precision {
+                Precision::Single => {
+                    Distances::<f32>::memory_usage(x.len(), sources.len(), dists_memory_limits)
+                }
+                Precision::Double => {
+                    Distances::<f64>::memory_usage(x.len(), sources.len(), dists_memory_limits)
+                }
+            };
             #[cfg(feature = "gpu")]
             let mem_usage = if option.gpu {
                 mem_usage
             } else {
-                mem_usage + x.len() * num_transducers * size_of::<f32>()
+                mem_usage + dists_mem_usage
             };
             #[cfg(not(feature = "gpu"))]
-            let mem_usage = mem_usage + x.len() * num_transducers * size_of::<f32>();
-
-            let memory_limits = option.memory_limits_hint_mb.saturating_mul(1024 * 1024);
+            let mem_usage = mem_usage + dists_mem_usage;
 
             let frame_window_size_mem = ((memory_limits.saturating_sub(mem_usage))
-                / (ULTRASOUND_PERIOD_COUNT * num_transducers * size_of::<f32>()))
-            .saturating_sub(required_frame_size)
-            .max(1);
+                / cache_per_frame)
+                .saturating_sub(required_frame_size)
+                .max(1);
 
             let frame_window_size_time =
                 ((Duration::from_nanos(self.end.sys_time() - self.start.sys_time()).as_nanos()
                     / ULTRASOUND_PERIOD.as_nanos()) as usize)
                     .max(1);
 
-            frame_window_size_mem.min(frame_window_size_time)
+            (
+                frame_window_size_mem.min(frame_window_size_time),
+                dists_memory_limits,
+            )
         };
 
-        let cursor =
-            -((max_dist / option.sound_speed / ULTRASOUND_PERIOD.as_secs_f32()).ceil() as isize);
+        let cursor = -((max_dist / option.sound_speed / ULTRASOUND_PERIOD.as_secs_f32()).ceil()
+            as isize)
+            - margin as isize;
 
-        let output_ultrasound = self
-            .records
-            .iter()
-            .map(|tr| tr.output_ultrasound())
-            .collect::<Vec<_>>();
         let cache_size = (required_frame_size + frame_window_size) as isize;
 
-        #[cfg(feature = "gpu")]
-        let compute_device = if option.gpu {
-            ComputeDevice::Gpu(gpu::Gpu::new(
+        let velocity_scale = option
+            .particle_velocity
+            .then(|| crate::record::sound_field::VELOCITY_SCALE * cpu::Cpu::P0 / option.density);
+
+        let cpu = |sources| match option.precision {
+            Precision::Single => ComputeDevice::Cpu(Box::new(cpu::Cpu::new(
                 &x,
                 &y,
                 &z,
-                self.records.iter().map(|tr| tr.tr.position()),
-                output_ultrasound,
+                sources,
+                self.records
+                    .iter()
+                    .map(|tr| tr.output_ultrasound())
+                    .collect(),
                 frame_window_size,
                 num_points_in_frame,
-                cache_size,
-            )?)
-        } else {
-            ComputeDevice::Cpu(cpu::Cpu::new(
+                velocity_scale,
+                dists_memory_limits,
+            ))),
+            Precision::Double => ComputeDevice::CpuDouble(Box::new(cpu::Cpu::new(
                 &x,
                 &y,
                 &z,
-                self.records.iter().map(|tr| tr.tr.position()),
-                output_ultrasound,
+                sources,
+                self.records
+                    .iter()
+                    .map(|tr| tr.output_ultrasound_with_precision())
+                    .collect(),
                 frame_window_size,
                 num_points_in_frame,
-            ))
+                velocity_scale,
+                dists_memory_limits,
+            ))),
+        };
+        #[cfg(feature = "gpu")]
+        let compute_device = if option.gpu {
+            ComputeDevice::Gpu(Box::new(gpu::Gpu::new(
+                &x,
+                &y,
+                &z,
+                sources,
+                self.records
+                    .iter()
+                    .map(|tr| tr.output_ultrasound())
+                    .collect(),
+                frame_window_size,
+                num_points_in_frame,
+                cache_size,
+                velocity_scale,
+                crate::GpuContext::get_or_new(option.gpu_context.as_ref(), option.gpu_option)?,
+            )?))
+        } else {
+            cpu(sources)
         };
         #[cfg(not(feature = "gpu"))]
-        let compute_device = ComputeDevice::Cpu(cpu::Cpu::new(
-            &x,
-            &y,
-            &z,
-            self.records.iter().map(|tr| tr.tr.position()),
-            output_ultrasound,
-            frame_window_size,
-            num_points_in_frame,
-        ));
+        let compute_device = cpu(sources);
 
         Ok(Instant {
             compute_device,
@@ -344,6 +637,7 @@ impl Record {
             x,
             y,
             z,
+            axes,
             frame_window_size,
             cache_size,
             num_points_in_frame,
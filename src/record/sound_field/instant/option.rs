@@ -2,28 +2,77 @@ use std::time::Duration;
 
 use autd3::prelude::mm;
 
+#[cfg(feature = "gpu")]
+use crate::{GpuContext, GpuOption};
+use crate::{OutputUnit, Precision, Reflector};
+
+/// Interpolation of the emitted ultrasound between its samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// The nearest sample.
+    Nearest,
+    /// Linear interpolation between the two adjacent samples.
+    #[default]
+    Linear,
+    /// Cubic convolution (Catmull-Rom spline) with 4 samples.
+    Cubic,
+    /// Blackman-windowed sinc with 8 samples. The weights are normalized so that their sum is 1.
+    WindowedSinc,
+}
+
 /// Options for instant recording.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct InstantRecordOption {
     /// Sound speed \[mm/s\].
     pub sound_speed: f32,
+    /// Density of the medium \[kg/m³\]. This is used to calculate the particle velocity and the intensity.
+    pub density: f32,
+    /// If true, the particle velocity is also calculated.
+    pub particle_velocity: bool,
     /// Time step.
     pub time_step: Duration,
+    /// Interpolation of the emitted ultrasound at the retarded time.
+    pub interpolation: Interpolation,
     /// Memory limits hint \[MB\].
     pub memory_limits_hint_mb: usize,
+    /// Planar reflectors.
+    pub reflectors: Vec<Reflector>,
+    /// Maximum number of reflections of each path.
+    pub reflection_order: usize,
+    /// Unit of the sound pressure in the results. The statistics of `aggregate` are always in \[Pa\].
+    pub unit: OutputUnit,
+    /// Floating-point precision of the computation on CPU. The computation on GPU is always in single precision.
+    pub precision: Precision,
     #[cfg(feature = "gpu")]
     /// If true, use GPU for computation.
     pub gpu: bool,
+    #[cfg(feature = "gpu")]
+    /// Context of GPU shared across the computations. If `None`, a new context is created for each computation with `gpu`.
+    pub gpu_context: Option<GpuContext>,
+    #[cfg(feature = "gpu")]
+    /// Selection of the adapter for the new context. Ignored if `gpu_context` is given.
+    pub gpu_option: GpuOption,
 }
 
 impl std::default::Default for InstantRecordOption {
     fn default() -> Self {
         Self {
             sound_speed: 340e3 * mm,
+            density: 1.18,
+            particle_velocity: false,
             time_step: Duration::from_micros(1),
+            interpolation: Interpolation::Linear,
             memory_limits_hint_mb: 128,
+            reflectors: Vec::new(),
+            reflection_order: 1,
+            unit: OutputUnit::Pascal,
+            precision: Precision::Single,
             #[cfg(feature = "gpu")]
             gpu: false,
+            #[cfg(feature = "gpu")]
+            gpu_context: None,
+            #[cfg(feature = "gpu")]
+            gpu_option: GpuOption::default(),
         }
     }
 }
@@ -4,7 +4,7 @@ var<storage, read> v_ult: array<f32>;
 
 @group(0)
 @binding(1)
-var<storage, read> v_tr_pos: array<vec3<f32>>;
+var<storage, read> v_src: array<vec4<f32>>;
 
 @group(0)
 @binding(2)
@@ -14,15 +14,23 @@ var<storage, read> v_tar_pos: array<vec3<f32>>;
 @binding(3)
 var<storage, read_write> v_dst: array<f32>;
 
+@group(0)
+@binding(4)
+var<storage, read> v_ult_int: array<f32>;
+
+@group(0)
+@binding(5)
+var<storage, read_write> v_vel: array<vec4<f32>>;
+
 struct Pc {
     t: f32,
     sound_speed: f32,
     num_trans: u32,
     offset: i32,
     output_ultrasound_stride: u32,
-    _pad0: u32,
-    _pad1: u32,
-    _pad2: u32,
+    velocity: u32,
+    velocity_scale: f32,
+    interpolation: u32,
 }
 
 var<immediate> pc: Pc;
@@ -35,6 +43,58 @@ const PI: f32 = radians(180.0);
 const T4010A1_AMPLITUDE: f32 = 55114.85; // [Pa*mm]
 const P0: f32 = T4010A1_AMPLITUDE * 1.41421356237309504880168872420969808 / (4. * PI);
 
+const INTERPOLATION_NEAREST: u32 = 0;
+const INTERPOLATION_LINEAR: u32 = 1;
+const INTERPOLATION_CUBIC: u32 = 2;
+const INTERPOLATION_WINDOWED_SINC: u32 = 3;
+
+fn half_width() -> i32 {
+    switch pc.interpolation {
+        case INTERPOLATION_CUBIC: {
+            return 2;
+        }
+        case INTERPOLATION_WINDOWED_SINC: {
+            return 4;
+        }
+        default: {
+            return 1;
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x == 0. {
+        return 1.;
+    }
+    return sin(PI * x) / (PI * x);
+}
+
+// The weight of the sample at `x` samples away from the interpolated point.
+fn kernel(x: f32) -> f32 {
+    let a = abs(x);
+    switch pc.interpolation {
+        case INTERPOLATION_NEAREST: {
+            return select(0., 1., -0.5 <= x && x < 0.5);
+        }
+        case INTERPOLATION_CUBIC: {
+            if a < 1. {
+                return (1.5 * a - 2.5) * a * a + 1.;
+            } else if a < 2. {
+                return ((-0.5 * a + 2.5) * a - 4.) * a + 2.;
+            }
+            return 0.;
+        }
+        case INTERPOLATION_WINDOWED_SINC: {
+            let w = f32(half_width());
+            let r = PI * x / w;
+            return select(0., sinc(x) * (0.42 + 0.5 * cos(r) + 0.08 * cos(2. * r)), a < w);
+        }
+        default: {
+            return max(1. - a, 0.);
+        }
+    }
+}
+
 @compute
 @workgroup_size(64)
 fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
@@ -42,14 +102,46 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         return;
     }
     var res: f32 = 0.;
-    for (var i: u32 = 0; i < pc.num_trans; i++) {
-        let dist = distance(v_tar_pos[global_id.x], v_tr_pos[i]);
+    var vel = vec3<f32>(0., 0., 0.);
+    for (var i: u32 = 0; i < arrayLength(&v_src); i++) {
+        let d = v_tar_pos[global_id.x] - v_src[i].xyz;
+        let dist = length(d);
         let t_out = pc.t - dist / pc.sound_speed;
         let a = t_out / TS;
         let idx = i32(floor(a));
         let alpha = a - f32(idx);
-        let idx_ = i * pc.output_ultrasound_stride + u32(idx - pc.offset);
-        res += mix(v_ult[idx_], v_ult[idx_ + 1], alpha) / dist;
+        let idx_ = (i % pc.num_trans) * pc.output_ultrasound_stride + u32(idx - pc.offset);
+        var u = 0.;
+        var u_int = 0.;
+        if pc.interpolation == INTERPOLATION_LINEAR {
+            u = mix(v_ult[idx_], v_ult[idx_ + 1], alpha);
+            if pc.velocity != 0u {
+                u_int = mix(v_ult_int[idx_], v_ult_int[idx_ + 1], alpha);
+            }
+        } else {
+            let w = half_width();
+            var weight = 0.;
+            for (var j: i32 = 1 - w; j <= w; j++) {
+                let k = kernel(alpha - f32(j));
+                let idx_j = u32(i32(idx_) + j);
+                u += k * v_ult[idx_j];
+                if pc.velocity != 0u {
+                    u_int += k * v_ult_int[idx_j];
+                }
+                weight += k;
+            }
+            u /= weight;
+            u_int /= weight;
+        }
+        let s = v_src[i].w * u;
+        res += s / dist;
+        if pc.velocity != 0u {
+            let s_int = v_src[i].w * u_int;
+            vel += d * ((s / (pc.sound_speed * dist) + s_int / (dist * dist)) / dist);
+        }
     }
     v_dst[global_id.x] = P0 * res;
+    if pc.velocity != 0u {
+        v_vel[global_id.x] = vec4<f32>(pc.velocity_scale * vel, 0.);
+    }
 }
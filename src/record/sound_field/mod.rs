@@ -2,8 +2,30 @@ use crate::{EmulatorError, Range};
 
 use super::Record;
 
+/// Scale factor to convert the particle velocity from \[Pa/mm / (kg/m³) · s\] to \[m/s\].
+pub(crate) const VELOCITY_SCALE: f32 = 1e3;
+
+pub(crate) mod adaptive;
+pub(crate) mod directivity;
+pub(crate) mod distance;
+pub(crate) mod fft;
+pub(crate) mod field;
+pub(crate) mod gorkov;
+#[cfg(feature = "gpu")]
+pub(crate) mod gpu_context;
 pub(crate) mod instant;
+#[cfg(feature = "gpu")]
+pub(crate) mod period_gpu;
+pub(crate) mod phasor;
+pub(crate) mod precision;
+pub(crate) mod reflector;
 pub(crate) mod rms;
+pub(crate) mod scatterer;
+pub(crate) mod simd;
+pub(crate) mod statistics;
+#[cfg(feature = "gpu")]
+pub(crate) mod statistics_gpu;
+pub(crate) mod unit;
 
 pub trait SoundFieldOption<'a> {
     type Output;
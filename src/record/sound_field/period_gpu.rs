@@ -0,0 +1,124 @@
+use autd3::prelude::Point3;
+use bytemuck::{NoUninit, Pod};
+use wgpu::{Buffer, BufferAddress};
+
+use crate::{
+    EmulatorError, GpuContext,
+    record::sound_field::gpu_context::{Pipeline, Vec3},
+};
+
+use super::rms::RmsTransducerRecord;
+
+// A shader which computes `T` at each target from the amplitudes and the phases of the transducers at a time in the ultrasound period.
+//
+// The shader binds the amplitudes, the phases, the positions of the transducers, the positions of the targets and the output in order.
+#[derive(Debug)]
+pub(crate) struct PeriodGpu<T> {
+    context: GpuContext,
+    num_transducers: u32,
+    stride: u32,
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    buf_storage_dst: Buffer,
+    buf_staging_dst: Buffer,
+    buffer: Vec<T>,
+}
+
+impl<T: Pod> PeriodGpu<T> {
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<Pc>(
+        key: &'static str,
+        shader: &'static str,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        transducer_positions: impl Iterator<Item = Point3>,
+        records: Vec<RmsTransducerRecord>,
+        context: GpuContext,
+    ) -> Result<Self, EmulatorError> {
+        let stride = records[0].amp.len();
+
+        let target_pos = Vec3::targets(x, y, z);
+        let transducer_pos = transducer_positions.map(Vec3::from).collect::<Vec<_>>();
+
+        let buf_amp_size =
+            (records.len() * records[0].amp.len() * size_of::<f32>()) as BufferAddress;
+        let buf_dst_size = (target_pos.len() * size_of::<T>()) as BufferAddress;
+        let buf_target_pos_size = (target_pos.len() * size_of::<Vec3>()) as BufferAddress;
+        let buf_tr_pos_size = (transducer_pos.len() * size_of::<Vec3>()) as BufferAddress;
+
+        context.check_binding_size(
+            buf_amp_size
+                .max(buf_target_pos_size)
+                .max(buf_tr_pos_size)
+                .max(buf_dst_size),
+        )?;
+
+        let amp = records
+            .iter()
+            .flat_map(|r| r.amp.iter().cloned())
+            .collect::<Vec<_>>();
+        let phase = records
+            .iter()
+            .flat_map(|r| r.phase.iter().cloned())
+            .collect::<Vec<_>>();
+        let buf_storage_amp = context.storage_buffer(&amp);
+        let buf_storage_phase = context.storage_buffer(&phase);
+        let buf_storage_trans_pos = context.storage_buffer(&transducer_pos);
+        let buf_storage_target_pos = context.storage_buffer(&target_pos);
+        let (buf_storage_dst, buf_staging_dst) = context.output_buffers(buf_dst_size);
+
+        let Pipeline {
+            bind_group_layout,
+            pipeline,
+        } = context.compute_pipeline(
+            key,
+            shader,
+            &[true, true, true, true, false],
+            size_of::<Pc>() as _,
+        );
+
+        let bind_group = context.bind_group(
+            &bind_group_layout,
+            &[
+                &buf_storage_amp,
+                &buf_storage_phase,
+                &buf_storage_trans_pos,
+                &buf_storage_target_pos,
+                &buf_storage_dst,
+            ],
+        );
+
+        Ok(Self {
+            context,
+            num_transducers: transducer_pos.len() as _,
+            stride: stride as _,
+            pipeline,
+            bind_group,
+            buf_storage_dst,
+            buf_staging_dst,
+            buffer: vec![T::zeroed(); target_pos.len()],
+        })
+    }
+
+    pub(crate) const fn num_transducers(&self) -> u32 {
+        self.num_transducers
+    }
+
+    pub(crate) const fn stride(&self) -> u32 {
+        self.stride
+    }
+
+    pub(crate) fn compute(&mut self, pc: &impl NoUninit) -> Result<&Vec<T>, EmulatorError> {
+        self.context.dispatch(
+            &self.pipeline,
+            &self.bind_group,
+            bytemuck::bytes_of(pc),
+            self.buffer.len(),
+            &[(&self.buf_storage_dst, &self.buf_staging_dst)],
+        );
+        self.context
+            .read_buffer(&self.buf_staging_dst, &mut self.buffer)?;
+        Ok(&self.buffer)
+    }
+}
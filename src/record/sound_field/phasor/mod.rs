@@ -0,0 +1,201 @@
+mod option;
+
+use std::time::Duration;
+
+use autd3::driver::common::ULTRASOUND_PERIOD;
+#[cfg(feature = "polars")]
+use polars::{df, frame::DataFrame, prelude::Column};
+
+use super::{super::Record, SoundFieldOption, rms::ComputeDevice};
+use crate::{EmulatorError, Range};
+
+pub use option::{PhasorFormat, PhasorRecordOption};
+
+/// An interface to calculate the complex pressure of the sound field.
+///
+/// The complex pressure `P` of each ultrasound period is calculated in the same way as [`Rms`], and `|P|` is the RMS amplitude, i.e., the value of [`Rms`].
+/// This is the same convention as the phasors of [`RmsRecordOption::particle_velocity`].
+///
+/// [`Rms`]: crate::Rms
+/// [`RmsRecordOption::particle_velocity`]: crate::RmsRecordOption::particle_velocity
+#[derive(Debug)]
+pub struct Phasor {
+    option: PhasorRecordOption,
+    cursor: usize,
+    max_frame: usize,
+    x: Vec<f32>,
+    y: Vec<f32>,
+    z: Vec<f32>,
+    compute_device: ComputeDevice,
+}
+
+impl Phasor {
+    #[cfg(feature = "polars")]
+    /// Returns the observed points.
+    pub fn observe_points(&self) -> DataFrame {
+        df!(
+            "x[mm]" => &self.x,
+            "y[mm]" => &self.y,
+            "z[mm]" => &self.z,
+        )
+        .unwrap()
+    }
+
+    #[cfg(feature = "polars")]
+    /// Progresses by the specified time and calculates the complex pressure for each period during that time.
+    ///
+    /// For each period, two columns are returned according to [`PhasorRecordOption::format`].
+    pub fn next(&mut self, duration: Duration) -> Result<DataFrame, EmulatorError> {
+        let n = self.next_time_len(duration);
+        let mut time = vec![0; n];
+        let mut v = vec![vec![0.0; self.next_points_len()]; 2 * n];
+        self.next_inplace(
+            duration,
+            false,
+            &mut time,
+            v.iter_mut().map(|v| v.as_mut_ptr()),
+        )?;
+
+        let (c0, c1) = match self.option.format {
+            PhasorFormat::Rectangular => ("re[Pa]", "im[Pa]"),
+            PhasorFormat::Polar => ("amp[Pa]", "phase[rad]"),
+        };
+        Ok(DataFrame::new(
+            self.next_points_len(),
+            time.iter()
+                .zip(v.chunks(2))
+                .flat_map(|(t, v)| {
+                    [
+                        Column::new(format!("{c0}@{t}[ns]").into(), &v[0]),
+                        Column::new(format!("{c1}@{t}[ns]").into(), &v[1]),
+                    ]
+                })
+                .collect::<Vec<_>>(),
+        )
+        .unwrap())
+    }
+
+    /// Progresses by the specified time.
+    pub fn skip(&mut self, duration: Duration) -> Result<&mut Self, EmulatorError> {
+        self.next_inplace(duration, true, &mut [], std::iter::empty())?;
+        Ok(self)
+    }
+
+    // GRCOV_EXCL_START
+    #[doc(hidden)]
+    pub fn x_inplace(&self, x: &mut [f32]) {
+        x.copy_from_slice(&self.x);
+    }
+
+    #[doc(hidden)]
+    pub fn y_inplace(&self, y: &mut [f32]) {
+        y.copy_from_slice(&self.y);
+    }
+
+    #[doc(hidden)]
+    pub fn z_inplace(&self, z: &mut [f32]) {
+        z.copy_from_slice(&self.z);
+    }
+    // GRCOV_EXCL_STOP
+
+    #[doc(hidden)]
+    pub fn next_time_len(&self, duration: Duration) -> usize {
+        (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize
+    }
+
+    #[doc(hidden)]
+    pub fn next_points_len(&self) -> usize {
+        self.x.len()
+    }
+
+    // `v` must yield two destinations (real and imaginary parts, or amplitude and phase) for each period.
+    #[doc(hidden)]
+    pub fn next_inplace(
+        &mut self,
+        duration: Duration,
+        skip: bool,
+        time: &mut [u64],
+        mut v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        if !duration
+            .as_nanos()
+            .is_multiple_of(ULTRASOUND_PERIOD.as_nanos())
+        {
+            return Err(EmulatorError::InvalidDuration);
+        }
+
+        let num_frames = (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize;
+
+        if self.cursor + num_frames > self.max_frame {
+            return Err(EmulatorError::NotRecorded);
+        }
+
+        if !skip {
+            let format = self.option.format;
+            let mut i = 0;
+            while i < num_frames {
+                let cur_frame = self.cursor + i;
+                let (r, _) = self.compute_device.compute(
+                    cur_frame,
+                    self.option.harmonic,
+                    self.option.sound_speed,
+                )?;
+                time[i] = (cur_frame as u32 * ULTRASOUND_PERIOD).as_nanos() as u64;
+                let dst0 = v.next().unwrap();
+                let dst1 = v.next().unwrap();
+                r.iter().enumerate().for_each(|(j, p)| {
+                    let (a, b) = match format {
+                        PhasorFormat::Rectangular => (p.re, p.im),
+                        PhasorFormat::Polar => (p.norm(), p.arg()),
+                    };
+                    unsafe {
+                        *dst0.add(j) = a;
+                        *dst1.add(j) = b;
+                    }
+                });
+                i += 1;
+            }
+        }
+
+        self.cursor += num_frames;
+
+        Ok(())
+    }
+}
+
+impl Record {
+    fn sound_field_phasor(
+        &self,
+        range: impl Range,
+        option: PhasorRecordOption,
+    ) -> Result<Phasor, EmulatorError> {
+        let max_frame = self.records[0].pulse_width.len();
+
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+
+        let compute_device =
+            self.rms_compute_device(&x, &y, &z, &range.axes(), &option.rms_option())?;
+
+        Ok(Phasor {
+            option,
+            compute_device,
+            cursor: 0,
+            max_frame,
+            x,
+            y,
+            z,
+        })
+    }
+}
+
+impl<'a> SoundFieldOption<'a> for PhasorRecordOption {
+    type Output = Phasor;
+
+    fn sound_field(
+        self,
+        record: &'a Record,
+        range: impl Range,
+    ) -> Result<Self::Output, EmulatorError> {
+        record.sound_field_phasor(range, self)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::{EmissionModel, Precision, Propagation, Reflector, RmsRecordOption, Scatterer};
+#[cfg(feature = "gpu")]
+use crate::{GpuContext, GpuOption};
+use autd3::prelude::mm;
+
+/// Representation of the complex pressure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PhasorFormat {
+    /// Real and imaginary parts.
+    #[default]
+    Rectangular,
+    /// Amplitude and phase.
+    Polar,
+}
+
+/// Options for complex pressure recording.
+///
+/// The options except for [`PhasorRecordOption::format`] are the same as those of [`RmsRecordOption`].
+#[derive(Debug, Clone)]
+pub struct PhasorRecordOption {
+    /// Sound speed [mm/s].
+    pub sound_speed: f32,
+    /// Representation of the output.
+    pub format: PhasorFormat,
+    /// Planar reflectors.
+    pub reflectors: Vec<Reflector>,
+    /// Maximum number of reflections of each path.
+    pub reflection_order: usize,
+    /// Rigid spherical scatterers.
+    pub scatterers: Vec<Scatterer>,
+    /// Model of the emitted ultrasound.
+    pub emission: EmissionModel,
+    /// Order of the harmonic. 1 is the fundamental component.
+    pub harmonic: usize,
+    /// Memory limits hint \[MB\].
+    ///
+    /// If the distances between the points and the sources do not fit in it, they are calculated for each tile of points on CPU every period.
+    pub memory_limits_hint_mb: usize,
+    /// Floating-point precision of the computation on CPU.
+    pub precision: Precision,
+    /// Method to propagate the ultrasound.
+    pub propagation: Propagation,
+    #[cfg(feature = "gpu")]
+    /// If true, use GPU for computation.
+    pub gpu: bool,
+    #[cfg(feature = "gpu")]
+    /// Context of GPU shared across the computations. If `None`, a new context is created for each computation with `gpu`.
+    pub gpu_context: Option<GpuContext>,
+    #[cfg(feature = "gpu")]
+    /// Selection of the adapter for the new context. Ignored if `gpu_context` is given.
+    pub gpu_option: GpuOption,
+}
+
+impl PhasorRecordOption {
+    // The options of `Rms` whose pressure is the same as the phasors.
+    pub(crate) fn rms_option(&self) -> RmsRecordOption {
+        RmsRecordOption {
+            sound_speed: self.sound_speed,
+            reflectors: self.reflectors.clone(),
+            reflection_order: self.reflection_order,
+            scatterers: self.scatterers.clone(),
+            emission: self.emission,
+            harmonic: self.harmonic,
+            memory_limits_hint_mb: self.memory_limits_hint_mb,
+            precision: self.precision,
+            propagation: self.propagation,
+            #[cfg(feature = "gpu")]
+            gpu: self.gpu,
+            #[cfg(feature = "gpu")]
+            gpu_context: self.gpu_context.clone(),
+            #[cfg(feature = "gpu")]
+            gpu_option: self.gpu_option,
+            ..Default::default()
+        }
+    }
+}
+
+impl std::default::Default for PhasorRecordOption {
+    fn default() -> Self {
+        Self {
+            sound_speed: 340e3 * mm,
+            format: PhasorFormat::default(),
+            reflectors: Vec::new(),
+            reflection_order: 1,
+            scatterers: Vec::new(),
+            emission: EmissionModel::Ideal,
+            harmonic: 1,
+            memory_limits_hint_mb: 128,
+            precision: Precision::Single,
+            propagation: Propagation::Direct,
+            #[cfg(feature = "gpu")]
+            gpu: false,
+            #[cfg(feature = "gpu")]
+            gpu_context: None,
+            #[cfg(feature = "gpu")]
+            gpu_option: GpuOption::default(),
+        }
+    }
+}
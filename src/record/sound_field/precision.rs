@@ -0,0 +1,115 @@
+use std::{
+    fmt::Debug,
+    iter::Sum,
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub},
+    time::Duration,
+};
+
+/// Floating-point precision of the computation on CPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Precision {
+    /// Single precision (`f32`).
+    #[default]
+    Single,
+    /// Double precision (`f64`) of the distances, times, phases, sums and the BVD model of the transducers.
+    ///
+    /// This is slower than [`Precision::Single`], but free from its phase noise at large distances or long times, so that it serves as the reference to quantify the error of `f32`.
+    /// The inputs such as the positions and the results are still in `f32`.
+    Double,
+}
+
+// The floating-point type of the computation on CPU.
+pub(crate) trait Float:
+    Copy
+    + Debug
+    + Default
+    + PartialOrd
+    + Send
+    + Sync
+    + From<f32>
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+    + AddAssign
+    + Sum
+    + 'static
+{
+    fn from_f64(v: f64) -> Self;
+    fn from_duration(d: Duration) -> Self;
+    fn to_f32(self) -> f32;
+    // Truncates toward zero.
+    fn to_isize(self) -> isize;
+    fn floor(self) -> Self;
+    fn abs(self) -> Self;
+    fn sqrt(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn hypot(self, other: Self) -> Self;
+    fn atan2(self, other: Self) -> Self;
+    // Returns `v` itself if `Self` is `f32`, which is for the lane-parallel kernels only in single precision.
+    fn as_f32_slice(v: &[Self]) -> Option<&[f32]>;
+}
+
+macro_rules! impl_float {
+    ($t:ty, $as_secs:ident, $as_f32_slice:expr) => {
+        impl Float for $t {
+            fn from_f64(v: f64) -> Self {
+                v as _
+            }
+
+            fn from_duration(d: Duration) -> Self {
+                d.$as_secs()
+            }
+
+            fn to_f32(self) -> f32 {
+                self as _
+            }
+
+            fn to_isize(self) -> isize {
+                self as _
+            }
+
+            fn floor(self) -> Self {
+                <$t>::floor(self)
+            }
+
+            fn abs(self) -> Self {
+                <$t>::abs(self)
+            }
+
+            fn sqrt(self) -> Self {
+                <$t>::sqrt(self)
+            }
+
+            fn sin(self) -> Self {
+                <$t>::sin(self)
+            }
+
+            fn cos(self) -> Self {
+                <$t>::cos(self)
+            }
+
+            fn sin_cos(self) -> (Self, Self) {
+                <$t>::sin_cos(self)
+            }
+
+            fn hypot(self, other: Self) -> Self {
+                <$t>::hypot(self, other)
+            }
+
+            fn atan2(self, other: Self) -> Self {
+                <$t>::atan2(self, other)
+            }
+
+            fn as_f32_slice(v: &[Self]) -> Option<&[f32]> {
+                $as_f32_slice(v)
+            }
+        }
+    };
+}
+
+impl_float!(f32, as_secs_f32, Some);
+impl_float!(f64, as_secs_f64, |_| None);
@@ -0,0 +1,118 @@
+use autd3::driver::geometry::{Point3, UnitVector3};
+
+use super::super::Record;
+use crate::utils::aabb::Aabb;
+
+/// A planar reflector.
+///
+/// The reflected waves are calculated by the image source method, so the sound field is valid only on the same side of the plane as the transducers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reflector {
+    /// A point on the plane.
+    pub pos: Point3,
+    /// Normal of the plane.
+    pub normal: UnitVector3,
+    /// Pressure reflection coefficient. 1 for a rigid plane.
+    pub reflection_coefficient: f32,
+}
+
+impl Reflector {
+    fn mirror(&self, p: Point3) -> Point3 {
+        p - self.normal.into_inner() * (2. * self.normal.dot(&(p - self.pos)))
+    }
+}
+
+// A transducer or its image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Source {
+    pub(crate) pos: Point3,
+    pub(crate) coef: f32,
+}
+
+// Sequences of reflectors of at most `order` reflections without reflecting on the same plane twice in a row.
+// The empty sequence corresponds to the direct path.
+fn reflection_paths(num_reflectors: usize, order: usize) -> Vec<Vec<usize>> {
+    let mut paths = vec![vec![]];
+    let mut last = vec![vec![]];
+    (0..order).for_each(|_| {
+        last = last
+            .iter()
+            .flat_map(|path: &Vec<usize>| {
+                (0..num_reflectors)
+                    .filter(|i| path.last() != Some(i))
+                    .map(|i| {
+                        let mut path = path.clone();
+                        path.push(i);
+                        path
+                    })
+            })
+            .collect();
+        paths.extend(last.iter().cloned());
+    });
+    paths
+}
+
+impl Record {
+    // Returns the transducers and their images. The `i`-th source is the image of the `i % num_transducers`-th transducer.
+    pub(crate) fn sources(&self, reflectors: &[Reflector], order: usize) -> Vec<Source> {
+        reflection_paths(reflectors.len(), order)
+            .into_iter()
+            .flat_map(|path| {
+                self.records.iter().map(move |tr| {
+                    path.iter().fold(
+                        Source {
+                            pos: tr.tr.position(),
+                            coef: 1.,
+                        },
+                        |src, &i| Source {
+                            pos: reflectors[i].mirror(src.pos),
+                            coef: src.coef * reflectors[i].reflection_coefficient,
+                        },
+                    )
+                })
+            })
+            .collect()
+    }
+
+    pub(crate) fn sources_aabb(&self, sources: &[Source]) -> Aabb {
+        sources
+            .iter()
+            .skip(self.records.len())
+            .fold(self.aabb, |aabb, src| aabb.grow(src.pos))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::driver::geometry::Vector3;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(vec![vec![]], 1, 0)]
+    #[case(vec![vec![]], 0, 2)]
+    #[case(vec![vec![], vec![0]], 1, 3)]
+    #[case(vec![vec![], vec![0], vec![1], vec![0, 1], vec![1, 0]], 2, 2)]
+    #[case(vec![vec![], vec![0], vec![1], vec![0, 1], vec![1, 0], vec![0, 1, 0], vec![1, 0, 1]], 2, 3)]
+    #[test]
+    fn test_reflection_paths(
+        #[case] expect: Vec<Vec<usize>>,
+        #[case] num_reflectors: usize,
+        #[case] order: usize,
+    ) {
+        assert_eq!(expect, reflection_paths(num_reflectors, order));
+    }
+
+    #[test]
+    fn test_mirror() {
+        let reflector = Reflector {
+            pos: Point3::new(0., 0., 10.),
+            normal: Vector3::z_axis(),
+            reflection_coefficient: 1.,
+        };
+        assert_eq!(
+            Point3::new(1., 2., 17.),
+            reflector.mirror(Point3::new(1., 2., 3.))
+        );
+    }
+}
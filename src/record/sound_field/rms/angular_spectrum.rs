@@ -0,0 +1,270 @@
+use autd3::{driver::geometry::Complex, prelude::Point3};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{
+    super::{
+        distance::Distances,
+        fft::Fft2,
+        reflector::Source,
+        simd::{self, Kernel},
+    },
+    Phasors, RmsTransducerRecord,
+};
+use crate::{EmulatorError, RangeAxis};
+
+// An observed plane at `dz` from the calculated plane.
+#[derive(Debug)]
+struct Plane {
+    dz: f32,
+    // The index on the padded plane and the index of the result of each observed point.
+    points: Vec<(usize, usize)>,
+}
+
+#[derive(Debug)]
+pub(crate) struct AngularSpectrum {
+    records: Vec<RmsTransducerRecord>,
+    sources: Vec<Source>,
+    // The points on the calculated plane, which is extended to the margin and arranged in row-major order with rows along x.
+    plane_positions: Vec<Point3>,
+    plane_nx: usize,
+    dists: Distances,
+    kernel: Kernel,
+    // The number of points of the plane zero-padded to a power of two along x and y.
+    nx: usize,
+    fft: Fft2,
+    // The spatial angular frequencies along x and y of the spectrum.
+    kx: Vec<f32>,
+    ky: Vec<f32>,
+    planes: Vec<Plane>,
+    spectrum: Vec<Complex>,
+    field: Vec<Complex>,
+    buffer: Vec<Complex>,
+    velocity_scale: Option<f32>,
+    phasor_buffer: Vec<Phasors>,
+    amp: Vec<f32>,
+    phase: Vec<f32>,
+}
+
+impl AngularSpectrum {
+    pub(crate) fn new(
+        axes: &[RangeAxis],
+        sources: Vec<Source>,
+        records: Vec<RmsTransducerRecord>,
+        velocity_scale: Option<f32>,
+        wavenumber: f32,
+        margin: f32,
+        memory_limits: usize,
+    ) -> Result<Self, EmulatorError> {
+        let invalid = |msg: &str| EmulatorError::InvalidAngularSpectrum(msg.into());
+        let [z, y, x] = axes else {
+            return Err(invalid("range must be axis-aligned"));
+        };
+        if (x.name, y.name, z.name) != ("x[mm]", "y[mm]", "z[mm]") {
+            return Err(invalid("range must be axis-aligned"));
+        }
+        if x.len() < 2 || y.len() < 2 {
+            return Err(invalid("range must have at least 2 points along x and y"));
+        }
+        let (dx, dy) = (x.coords[1] - x.coords[0], y.coords[1] - y.coords[0]);
+        // The propagating components up to the wavenumber must be sampled without aliasing.
+        if dx.max(dy) >= std::f32::consts::PI / wavenumber {
+            return Err(invalid(
+                "resolution must be smaller than half the wavelength",
+            ));
+        }
+        let z0 = z.coords.iter().fold(f32::INFINITY, |acc, &z| acc.min(z));
+        let (min, max) = sources.iter().fold(
+            (
+                Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+                Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            ),
+            |(min, max), src| (min.inf(&src.pos), max.sup(&src.pos)),
+        );
+        if max.z >= z0 {
+            return Err(invalid("transducers must be below the range"));
+        }
+
+        // The calculated plane is aligned to the grid of the range and covers both the range and the transducers with the margin.
+        let extent = |axis: &RangeAxis, step: f32, min: f32, max: f32| {
+            let start = axis.coords[0];
+            let end = axis.coords[axis.len() - 1];
+            let lo = ((start.min(min - margin) - start) / step).floor() as isize;
+            let hi = ((end.max(max + margin) - start) / step).ceil() as isize;
+            (lo, (hi - lo + 1) as usize)
+        };
+        let (x_lo, plane_nx) = extent(x, dx, min.x, max.x);
+        let (y_lo, plane_ny) = extent(y, dy, min.y, max.y);
+        // The zero padding of at least the size of the plane prevents the wrap-around of the circular convolution.
+        let nx = (2 * plane_nx).next_power_of_two();
+        let ny = (2 * plane_ny).next_power_of_two();
+
+        let plane_positions = (0..plane_ny)
+            .flat_map(|j| {
+                (0..plane_nx).map(move |i| {
+                    Point3::new(
+                        x.coords[0] + (x_lo + i as isize) as f32 * dx,
+                        y.coords[0] + (y_lo + j as isize) as f32 * dy,
+                        z0,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+        let dists = Distances::new(&plane_positions, &sources, memory_limits);
+
+        let planes = z
+            .coords
+            .iter()
+            .enumerate()
+            .map(|(iz, &zc)| Plane {
+                dz: zc - z0,
+                points: (0..y.len())
+                    .flat_map(|iy| {
+                        (0..x.len()).map(move |ix| {
+                            (
+                                (iy as isize - y_lo) as usize * nx + (ix as isize - x_lo) as usize,
+                                iz * z.stride + iy * y.stride + ix * x.stride,
+                            )
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let freq = |n: usize, step: f32| {
+            (0..n)
+                .map(|i| {
+                    let i = if i < n / 2 {
+                        i as f32
+                    } else {
+                        i as f32 - n as f32
+                    };
+                    2. * std::f32::consts::PI * i / (n as f32 * step)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let num_points = x.len() * y.len() * z.len();
+        Ok(Self {
+            records,
+            sources,
+            plane_positions,
+            plane_nx,
+            dists,
+            kernel: Kernel::detect(),
+            nx,
+            fft: Fft2::new(nx, ny),
+            kx: freq(nx, dx),
+            ky: freq(ny, dy),
+            planes,
+            spectrum: vec![Complex::new(0., 0.); nx * ny],
+            field: vec![Complex::new(0., 0.); nx * ny],
+            buffer: vec![Complex::new(0., 0.); num_points],
+            phasor_buffer: if velocity_scale.is_some() {
+                vec![[Complex::new(0., 0.); 4]; num_points]
+            } else {
+                Vec::new()
+            },
+            velocity_scale,
+            amp: Vec::new(),
+            phase: Vec::new(),
+        })
+    }
+
+    // Calculates the field on the plane by the direct summation and transforms it into the spectrum.
+    fn spectrum(&mut self, wavenumber: f32) {
+        self.spectrum.fill(Complex::new(0., 0.));
+        let (plane_nx, nx) = (self.plane_nx, self.nx);
+        let (kernel, amp, phase) = (self.kernel, &self.amp, &self.phase);
+        let spectrum = &mut self.spectrum;
+        self.dists
+            .for_each_tile(&self.plane_positions, &self.sources, |start, tile| {
+                #[cfg(feature = "parallel")]
+                let p = tile
+                    .par_iter()
+                    .map(|d| simd::rms(kernel, d, amp, phase, wavenumber))
+                    .collect::<Vec<_>>();
+                #[cfg(not(feature = "parallel"))]
+                let p = tile
+                    .iter()
+                    .map(|d| simd::rms(kernel, d, amp, phase, wavenumber))
+                    .collect::<Vec<_>>();
+                p.into_iter().enumerate().for_each(|(i, p)| {
+                    let i = start + i;
+                    spectrum[i / plane_nx * nx + i % plane_nx] = p;
+                });
+            });
+        self.fft.process(&mut self.spectrum, false);
+    }
+
+    // Propagates the spectrum by `dz` multiplied by `factor` of the wave vector, and transforms it back into `field`.
+    fn propagate(
+        &mut self,
+        dz: f32,
+        wavenumber: f32,
+        factor: impl Fn(f32, f32, Complex) -> Complex,
+    ) {
+        let k2 = wavenumber * wavenumber;
+        let nx = self.nx;
+        self.field
+            .iter_mut()
+            .zip(self.spectrum.iter())
+            .enumerate()
+            .for_each(|(i, (f, s))| {
+                let (kx, ky) = (self.kx[i % nx], self.ky[i / nx]);
+                let q = k2 - kx * kx - ky * ky;
+                // The evanescent components decay as `exp(-|kz| dz)`.
+                let kz = if q >= 0. {
+                    Complex::new(q.sqrt(), 0.)
+                } else {
+                    Complex::new(0., (-q).sqrt())
+                };
+                *f = *s * Complex::new(-kz.im * dz, kz.re * dz).exp() * factor(kx, ky, kz);
+            });
+        self.fft.process(&mut self.field, true);
+    }
+
+    pub(crate) fn compute(&mut self, idx: usize, wavenumber: f32) -> (&[Complex], &[Phasors]) {
+        self.amp = self
+            .sources
+            .iter()
+            .zip(self.records.iter().cycle())
+            .map(|(src, tr)| src.coef * tr.amp[idx])
+            .collect();
+        self.phase = self
+            .records
+            .iter()
+            .cycle()
+            .take(self.sources.len())
+            .map(|tr| tr.phase[idx])
+            .collect();
+        self.spectrum(wavenumber);
+
+        (0..self.planes.len()).for_each(|p| {
+            let dz = self.planes[p].dz;
+            self.propagate(dz, wavenumber, |_, _, _| Complex::new(1., 0.));
+            self.planes[p].points.iter().for_each(|&(i, j)| {
+                self.buffer[j] = self.field[i];
+            });
+            if let Some(scale) = self.velocity_scale {
+                self.planes[p].points.iter().for_each(|&(i, j)| {
+                    self.phasor_buffer[j][0] = self.field[i];
+                });
+                // The gradient multiplies each component of the spectrum by `i k`, and the particle velocity is `-i ∇p / (ωρ)`.
+                (0..3).for_each(|c| {
+                    self.propagate(dz, wavenumber, |kx, ky, kz| match c {
+                        0 => Complex::new(0., kx),
+                        1 => Complex::new(0., ky),
+                        _ => Complex::new(-kz.im, kz.re),
+                    });
+                    self.planes[p].points.iter().for_each(|&(i, j)| {
+                        let g = self.field[i];
+                        self.phasor_buffer[j][c + 1] = Complex::new(g.im * scale, -g.re * scale);
+                    });
+                });
+            }
+        });
+        (&self.buffer, &self.phasor_buffer)
+    }
+}
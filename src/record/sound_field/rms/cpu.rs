@@ -3,79 +3,238 @@ use autd3::{driver::geometry::Complex, prelude::Point3};
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
-use super::RmsTransducerRecord;
+use super::{
+    super::{
+        distance::{Distances, difference},
+        precision::Float,
+        reflector::Source,
+        scatterer::Scattered,
+        simd::{self, Kernel},
+    },
+    Phasors, RmsTransducerRecord,
+};
 
 #[derive(Debug)]
-pub(crate) struct Cpu {
+pub(crate) struct Cpu<T = f32> {
     records: Vec<RmsTransducerRecord>,
-    dists: Vec<Vec<f32>>,
-    buffer: Vec<f32>,
+    dists: Distances<T>,
+    buffer: Vec<Complex>,
+    target_positions: Vec<Point3>,
+    sources: Vec<Source>,
+    velocity_scale: Option<f32>,
+    phasor_buffer: Vec<Phasors>,
+    // The scattered pressure and its gradient of each source with the unit amplitude at each target. Empty if there are no scatterers.
+    scattered: Vec<Vec<Complex>>,
+    scattered_gradient: Vec<Vec<[Complex; 3]>>,
+    kernel: Kernel,
+    // The amplitude and phase of each source in the current period.
+    amp: Vec<f32>,
+    phase: Vec<f32>,
 }
 
-impl Cpu {
+impl<T: Float> Cpu<T> {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         x: &[f32],
         y: &[f32],
         z: &[f32],
-        transducer_positions: impl Iterator<Item = Point3>,
+        sources: Vec<Source>,
         records: Vec<RmsTransducerRecord>,
+        velocity_scale: Option<f32>,
+        scattered: Scattered,
+        memory_limits: usize,
     ) -> Self {
-        let transducer_positions = transducer_positions.collect::<Vec<_>>();
-        let dists = x
+        let target_positions = x
             .iter()
             .zip(y.iter())
             .zip(z.iter())
             .map(|((&x, &y), &z)| Point3::new(x, y, z))
-            .map(|p| {
-                transducer_positions
-                    .iter()
-                    .map(|tp| (p - *tp).norm())
-                    .collect::<Vec<_>>()
-            })
             .collect::<Vec<_>>();
+        let dists = Distances::new(&target_positions, &sources, memory_limits);
         Self {
             records,
             dists,
-            buffer: vec![0.; x.len()],
+            buffer: vec![Complex::new(0., 0.); x.len()],
+            phasor_buffer: if velocity_scale.is_some() {
+                vec![[Complex::new(0., 0.); 4]; x.len()]
+            } else {
+                Vec::new()
+            },
+            target_positions,
+            sources,
+            velocity_scale,
+            scattered: scattered.0,
+            scattered_gradient: scattered.1,
+            kernel: Kernel::detect(),
+            amp: Vec::new(),
+            phase: Vec::new(),
         }
     }
 
-    pub(crate) fn compute(&mut self, idx: usize, wavenumber: f32) -> &Vec<f32> {
+    // The complex amplitude of each source in the period `idx`.
+    fn emission(
+        sources: &[Source],
+        records: &[RmsTransducerRecord],
+        idx: usize,
+    ) -> impl Iterator<Item = Complex> {
+        sources
+            .iter()
+            .zip(records.iter().cycle())
+            .map(move |(src, tr)| {
+                let r = src.coef * tr.amp[idx];
+                let (s, c) = tr.phase[idx].sin_cos();
+                Complex::new(r * c, r * s)
+            })
+    }
+
+    fn scattered_pressure(
+        scattered: &[Complex],
+        sources: &[Source],
+        records: &[RmsTransducerRecord],
+        idx: usize,
+    ) -> Complex {
+        Self::emission(sources, records, idx)
+            .zip(scattered.iter())
+            .map(|(e, s)| e * *s)
+            .sum()
+    }
+
+    // The phasors of the pressure and the particle velocity, which is `-i ∇p / (ωρ)`.
+    #[allow(clippy::too_many_arguments)]
+    fn phasors(
+        p: &Point3,
+        scattered: &[Complex],
+        scattered_gradient: &[[Complex; 3]],
+        sources: &[Source],
+        records: &[RmsTransducerRecord],
+        idx: usize,
+        wavenumber: T,
+        scale: f32,
+    ) -> Phasors {
+        let pressure = Self::scattered_pressure(scattered, sources, records, idx);
+        let grad = Self::emission(sources, records, idx)
+            .zip(scattered_gradient.iter())
+            .fold([Complex::new(0., 0.); 3], |mut acc, (e, s)| {
+                (0..3).for_each(|c| acc[c] += e * s[c]);
+                acc
+            });
+        let init = (
+            (T::from(pressure.re), T::from(pressure.im)),
+            grad.map(|g| (T::from(g.re), T::from(g.im))),
+        );
+        // The gradient of `a exp(i(kr + φ)) / r` is `a exp(i(kr + φ)) (ik - 1/r) / r² (x - x_s)`.
+        let (pressure, grad) = sources.iter().zip(records.iter().cycle()).fold(
+            init,
+            |(mut pressure, mut grad), (src, tr)| {
+                let d = difference::<T>(p, &src.pos);
+                let r = (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt();
+                let (s, c) = (wavenumber * r + T::from(tr.phase[idx])).sin_cos();
+                let a = T::from(src.coef * tr.amp[idx]) / r;
+                pressure.0 += a * c;
+                pressure.1 += a * s;
+                let a = a / r;
+                let (re, im) = (-c / r - s * wavenumber, c * wavenumber - s / r);
+                (0..3).for_each(|i| {
+                    grad[i].0 += a * re * d[i];
+                    grad[i].1 += a * im * d[i];
+                });
+                (pressure, grad)
+            },
+        );
+        let scale = T::from(scale);
+        [
+            Complex::new(pressure.0.to_f32(), pressure.1.to_f32()),
+            Complex::new((grad[0].1 * scale).to_f32(), (-grad[0].0 * scale).to_f32()),
+            Complex::new((grad[1].1 * scale).to_f32(), (-grad[1].0 * scale).to_f32()),
+            Complex::new((grad[2].1 * scale).to_f32(), (-grad[2].0 * scale).to_f32()),
+        ]
+    }
+
+    pub(crate) fn compute(&mut self, idx: usize, wavenumber: T) -> (&[Complex], &[Phasors]) {
+        self.amp = self
+            .sources
+            .iter()
+            .zip(self.records.iter().cycle())
+            .map(|(src, tr)| src.coef * tr.amp[idx])
+            .collect();
+        self.phase = self
+            .records
+            .iter()
+            .cycle()
+            .take(self.sources.len())
+            .map(|tr| tr.phase[idx])
+            .collect();
+        let pressure = |i: usize, d: &[T]| {
+            let (mut re, mut im) = match T::as_f32_slice(d) {
+                Some(d) => {
+                    let v = simd::rms(self.kernel, d, &self.amp, &self.phase, wavenumber.to_f32());
+                    (T::from(v.re), T::from(v.im))
+                }
+                None => d.iter().zip(self.amp.iter()).zip(self.phase.iter()).fold(
+                    (T::default(), T::default()),
+                    |(re, im), ((&d, &a), &p)| {
+                        let r = T::from(a) / d;
+                        let (s, c) = (wavenumber * d + T::from(p)).sin_cos();
+                        (re + r * c, im + r * s)
+                    },
+                ),
+            };
+            if let Some(scattered) = self.scattered.get(i) {
+                let v = Self::scattered_pressure(scattered, &self.sources, &self.records, idx);
+                re += T::from(v.re);
+                im += T::from(v.im);
+            }
+            Complex::new(re.to_f32(), im.to_f32())
+        };
+        let phasors = |(i, p): (usize, &Point3)| {
+            Self::phasors(
+                p,
+                self.scattered.get(i).map_or(&[], Vec::as_slice),
+                self.scattered_gradient.get(i).map_or(&[], Vec::as_slice),
+                &self.sources,
+                &self.records,
+                idx,
+                wavenumber,
+                self.velocity_scale.unwrap_or(0.),
+            )
+        };
         #[cfg(feature = "parallel")]
         {
             self.dists
-                .par_iter()
-                .map(|d| {
-                    d.iter()
-                        .zip(self.records.iter())
-                        .map(|(dist, tr)| {
-                            let r = tr.amp[idx] / dist;
-                            let theta = wavenumber * dist + tr.phase[idx];
-                            Complex::new(r * theta.cos(), r * theta.sin())
-                        })
-                        .sum::<Complex>()
-                        .norm()
-                })
-                .collect_into_vec(&mut self.buffer);
+                .for_each_tile(&self.target_positions, &self.sources, |start, tile| {
+                    self.buffer[start..start + tile.len()]
+                        .par_iter_mut()
+                        .zip(tile.par_iter())
+                        .enumerate()
+                        .for_each(|(i, (p, d))| *p = pressure(start + i, d));
+                });
+            if self.velocity_scale.is_some() {
+                self.target_positions
+                    .par_iter()
+                    .enumerate()
+                    .map(phasors)
+                    .collect_into_vec(&mut self.phasor_buffer);
+            }
         }
         #[cfg(not(feature = "parallel"))]
         {
-            self.buffer = self
-                .dists
-                .iter()
-                .map(|d| {
-                    d.iter()
-                        .zip(self.records.iter())
-                        .map(|(dist, tr)| {
-                            let r = tr.amp[idx] / dist;
-                            let theta = wavenumber * dist + tr.phase[idx];
-                            Complex::new(r * theta.cos(), r * theta.sin())
-                        })
-                        .sum::<Complex>()
-                        .norm()
-                })
-                .collect();
+            self.dists
+                .for_each_tile(&self.target_positions, &self.sources, |start, tile| {
+                    self.buffer[start..start + tile.len()]
+                        .iter_mut()
+                        .zip(tile.iter())
+                        .enumerate()
+                        .for_each(|(i, (p, d))| *p = pressure(start + i, d));
+                });
+            if self.velocity_scale.is_some() {
+                self.phasor_buffer = self
+                    .target_positions
+                    .iter()
+                    .enumerate()
+                    .map(phasors)
+                    .collect();
+            }
         }
-        &self.buffer
+        (&self.buffer, &self.phasor_buffer)
     }
 }
@@ -0,0 +1,132 @@
+use std::{collections::HashMap, f64::consts::PI};
+
+use autd3::prelude::{Phase, Point3};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::{super::precision::Float, RmsTransducerRecord};
+use crate::record::{Record, TransducerRecord, ULTRASOUND_PERIOD_COUNT};
+
+// The amplitude of the fundamental component of the emitted ultrasound is about 1 in the steady state with the maximum pulse width,
+// which corresponds to `sin(π * pulse_width / ULTRASOUND_PERIOD_COUNT) = 1` in `rms_transducer_records`.
+const SCALE: f32 = Record::P0 * 2. / ULTRASOUND_PERIOD_COUNT as f32;
+
+// The number of periods until the emitted ultrasound of a constant drive is regarded as the steady state.
+const STEADY_STATE_PERIODS: usize = 100;
+
+// Basis of the Fourier coefficient of the `harmonic`-th component.
+// The coefficient is taken for `exp(-iωt)` to match the sign of the phase.
+fn fourier_basis<T: Float>(harmonic: usize) -> Vec<(T, T)> {
+    (0..ULTRASOUND_PERIOD_COUNT)
+        .map(|k| {
+            let theta = T::from_f64(
+                2. * PI * ((harmonic * k) % ULTRASOUND_PERIOD_COUNT) as f64
+                    / ULTRASOUND_PERIOD_COUNT as f64,
+            );
+            (theta.cos(), theta.sin())
+        })
+        .collect()
+}
+
+// Returns the amplitude and phase of the Fourier coefficient of the waveform of a period.
+fn fourier<T: Float>(u: &[T], basis: &[(T, T)]) -> (f32, f32) {
+    let (re, im) = u
+        .iter()
+        .zip(basis.iter())
+        .fold((T::default(), T::default()), |(re, im), (&u, &(c, s))| {
+            (re + u * c, im + u * s)
+        });
+    (SCALE * re.hypot(im).to_f32(), im.atan2(re).to_f32())
+}
+
+impl Record {
+    // Amplitude and phase of the `harmonic`-th component of the emitted ultrasound of each period.
+    pub(crate) fn rms_transducer_records_bvd<T: Float>(
+        &self,
+        harmonic: usize,
+    ) -> Vec<RmsTransducerRecord> {
+        let basis = fourier_basis::<T>(harmonic);
+        let component = |tr: &TransducerRecord| {
+            let mut output_ultrasound = tr.output_ultrasound_with_precision();
+            let mut u = vec![T::default(); ULTRASOUND_PERIOD_COUNT];
+            (0..tr.pulse_width.len())
+                .map(|_| {
+                    output_ultrasound._next_inplace(1, &mut u).unwrap();
+                    fourier(&u, &basis)
+                })
+                .unzip()
+        };
+        #[cfg(feature = "parallel")]
+        let records = self.records.par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let records = self.records.iter();
+        records
+            .map(|tr| {
+                let (amp, phase) = component(tr);
+                RmsTransducerRecord { amp, phase }
+            })
+            .collect()
+    }
+
+    // Amplitude and phase of the `harmonic`-th component of the emitted ultrasound in the steady state of the pulse width of each period.
+    // The phase is relative to the fundamental component, so that it is consistent with `rms_transducer_records`.
+    pub(crate) fn rms_transducer_records_ideal_harmonic<T: Float>(
+        &self,
+        harmonic: usize,
+    ) -> Vec<RmsTransducerRecord> {
+        let fundamental_basis = fourier_basis::<T>(1);
+        let basis = fourier_basis::<T>(harmonic);
+        let steady_state = |pulse_width: u16| {
+            let tr = TransducerRecord {
+                pulse_width: vec![pulse_width; STEADY_STATE_PERIODS],
+                phase: vec![0; STEADY_STATE_PERIODS],
+                tr: autd3::driver::geometry::Transducer::new(Point3::origin()),
+            };
+            let u = tr
+                .output_ultrasound_with_precision::<T>()
+                ._next(STEADY_STATE_PERIODS)
+                .unwrap()
+                .split_off((STEADY_STATE_PERIODS - 1) * ULTRASOUND_PERIOD_COUNT);
+            let (_, fundamental_phase) = fourier(&u, &fundamental_basis);
+            let (amp, phase) = fourier(&u, &basis);
+            (
+                pulse_width,
+                (amp, phase - harmonic as f32 * fundamental_phase),
+            )
+        };
+
+        let mut pulse_widths = self
+            .records
+            .iter()
+            .flat_map(|tr| tr.pulse_width.iter().copied())
+            .collect::<Vec<_>>();
+        pulse_widths.sort_unstable();
+        pulse_widths.dedup();
+        #[cfg(feature = "parallel")]
+        let table = pulse_widths
+            .into_par_iter()
+            .map(steady_state)
+            .collect::<HashMap<_, _>>();
+        #[cfg(not(feature = "parallel"))]
+        let table = pulse_widths
+            .into_iter()
+            .map(steady_state)
+            .collect::<HashMap<_, _>>();
+
+        self.records
+            .iter()
+            .map(|tr| {
+                let (amp, phase) = tr
+                    .pulse_width
+                    .iter()
+                    .zip(tr.phase.iter())
+                    .map(|(w, &p)| {
+                        let (amp, phase) = table[w];
+                        (amp, phase + harmonic as f32 * Phase(p).radian())
+                    })
+                    .unzip();
+                RmsTransducerRecord { amp, phase }
+            })
+            .collect()
+    }
+}
@@ -1,38 +1,21 @@
-use std::{
-    borrow::Cow,
-    sync::{Arc, Condvar, Mutex},
+use crate::{
+    EmulatorError, GpuContext,
+    record::sound_field::gpu_context::{Pipeline, Vec3, Vec4},
 };
 
-use crate::EmulatorError;
-
-use autd3::prelude::Point3;
-
+use autd3::driver::geometry::Complex;
 use bytemuck::NoUninit;
-use wgpu::{Buffer, BufferAddress, util::DeviceExt};
+use wgpu::{Buffer, BufferAddress};
 
-use super::RmsTransducerRecord;
+use super::{
+    super::{
+        reflector::Source, scatterer::Scattered, statistics::Aggregator,
+        statistics_gpu::StatisticsGpu,
+    },
+    Phasors, RmsTransducerRecord,
+};
 
 // GRCOV_EXCL_START
-#[derive(NoUninit, Clone, Copy)]
-#[repr(C)]
-struct Vec3 {
-    x: f32,
-    y: f32,
-    z: f32,
-    _pad: f32,
-}
-
-impl From<Point3> for Vec3 {
-    fn from(v: Point3) -> Self {
-        Self {
-            x: v.x,
-            y: v.y,
-            z: v.z,
-            _pad: 0.,
-        }
-    }
-}
-
 #[derive(NoUninit, Clone, Copy)]
 #[repr(C)]
 struct Pc {
@@ -40,292 +23,247 @@ struct Pc {
     wavenumber: f32,
     num_trans: u32,
     stride: u32,
+    velocity: u32,
+    velocity_scale: f32,
+    scatter: u32,
+    _pad: u32,
 }
 // GRCOV_EXCL_STOP
 
 #[derive(Debug)]
 pub(crate) struct Gpu {
     num_transducers: u32,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+    context: GpuContext,
     pipeline: wgpu::ComputePipeline,
     bind_group: wgpu::BindGroup,
     buf_storage_dst: Buffer,
     buf_staging_dst: Buffer,
-    buffer: Vec<f32>,
+    buf_storage_velocity: Buffer,
+    buf_staging_velocity: Buffer,
+    buffer: Vec<[f32; 2]>,
+    pressure_output: Vec<Complex>,
+    velocity_scale: Option<f32>,
+    velocity_buffer: Vec<[f32; 4]>,
+    phasor_output: Vec<Phasors>,
     stride: u32,
+    scatter: bool,
+    // If `Some`, the RMS is reduced on GPU instead of being read back.
+    statistics: Option<StatisticsGpu>,
 }
 
 impl Gpu {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         x: &[f32],
         y: &[f32],
         z: &[f32],
-        transducer_positions: impl Iterator<Item = Point3>,
+        sources: Vec<Source>,
         records: Vec<RmsTransducerRecord>,
+        velocity_scale: Option<f32>,
+        scattered: Scattered,
+        context: GpuContext,
     ) -> Result<Self, EmulatorError> {
         let stride = records[0].amp.len();
 
-        let target_pos = x
-            .iter()
-            .zip(y.iter())
-            .zip(z.iter())
-            .map(|((&x, &y), &z)| Vec3 { x, y, z, _pad: 0. })
-            .collect::<Vec<_>>();
-        let transducer_pos = transducer_positions.map(Vec3::from).collect::<Vec<_>>();
+        let target_pos = Vec3::targets(x, y, z);
+        let transducer_pos = sources.into_iter().map(Vec4::from).collect::<Vec<_>>();
 
         let buf_amp_size =
             (records.len() * records[0].amp.len() * size_of::<f32>()) as BufferAddress;
-        let buf_dst_size = (target_pos.len() * size_of::<f32>()) as BufferAddress;
+        let buf_dst_size = (target_pos.len() * size_of::<[f32; 2]>()) as BufferAddress;
         let buf_target_pos_size = (target_pos.len() * size_of::<Vec3>()) as BufferAddress;
-        let buf_tr_pos_size = (transducer_pos.len() * size_of::<Vec3>()) as BufferAddress;
+        // The phasors of the pressure and the particle velocity of each target are packed in two `vec4`s.
+        let buf_velocity_size = (if velocity_scale.is_some() {
+            2 * target_pos.len()
+        } else {
+            1
+        } * size_of::<[f32; 4]>()) as BufferAddress;
+        let buf_tr_pos_size = (transducer_pos.len() * size_of::<Vec4>()) as BufferAddress;
 
-        let instance = wgpu::Instance::default();
+        let scatter = !scattered.0.is_empty();
+        // The buffers must not be empty even if there are no scatterers.
+        let mut scattered_pressure = scattered
+            .0
+            .iter()
+            .flatten()
+            .map(|c| [c.re, c.im])
+            .collect::<Vec<_>>();
+        if scattered_pressure.is_empty() {
+            scattered_pressure.push([0.; 2]);
+        }
+        let mut scattered_gradient = scattered
+            .1
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|c| [c.re, c.im])
+            .collect::<Vec<_>>();
+        if scattered_gradient.is_empty() {
+            scattered_gradient.push([0.; 2]);
+        }
+        let buf_scattered_size = (scattered_gradient.len().max(scattered_pressure.len())
+            * size_of::<[f32; 2]>()) as BufferAddress;
 
-        let adapter = crate::utils::executor::block_on(
-            instance.request_adapter(&wgpu::RequestAdapterOptions::default()),
+        context.check_binding_size(
+            buf_amp_size
+                .max(buf_target_pos_size)
+                .max(buf_tr_pos_size)
+                .max(buf_velocity_size)
+                .max(buf_scattered_size),
         )?;
 
-        let (device, queue) =
-            crate::utils::executor::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-                label: None,
-                required_features: wgpu::Features::IMMEDIATES,
-                required_limits: wgpu::Limits {
-                    max_immediate_size: std::mem::size_of::<Pc>() as u32,
-                    max_storage_buffers_per_shader_stage: 6,
-                    max_storage_buffer_binding_size:
-                        buf_amp_size.max(buf_target_pos_size).max(buf_tr_pos_size) as _,
-                    ..wgpu::Limits::downlevel_defaults()
-                },
-                memory_hints: wgpu::MemoryHints::MemoryUsage,
-                trace: wgpu::Trace::Off,
-                experimental_features: wgpu::ExperimentalFeatures::disabled(),
-            }))?;
-
-        let cs_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: None,
-            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("shader.wgsl"))),
-        });
-
-        let buf_storage_amp = {
-            let amp = records
-                .iter()
-                .flat_map(|r| r.amp.iter().cloned())
-                .collect::<Vec<_>>();
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&amp),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            })
-        };
-        let buf_storage_phase = {
-            let phase = records
-                .iter()
-                .flat_map(|r| r.phase.iter().cloned())
-                .collect::<Vec<_>>();
-            device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: None,
-                contents: bytemuck::cast_slice(&phase),
-                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-            })
-        };
-        let buf_storage_trans_pos = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&transducer_pos),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-        });
-        let buf_storage_target_pos = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: None,
-            contents: bytemuck::cast_slice(&target_pos),
-            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
-        });
-        let buf_staging_dst = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: buf_dst_size,
-            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
-        let buf_storage_dst = device.create_buffer(&wgpu::BufferDescriptor {
-            label: None,
-            size: buf_dst_size,
-            usage: wgpu::BufferUsages::STORAGE
-                | wgpu::BufferUsages::COPY_DST
-                | wgpu::BufferUsages::COPY_SRC,
-            mapped_at_creation: false,
-        });
+        let amp = records
+            .iter()
+            .flat_map(|r| r.amp.iter().cloned())
+            .collect::<Vec<_>>();
+        let phase = records
+            .iter()
+            .flat_map(|r| r.phase.iter().cloned())
+            .collect::<Vec<_>>();
+        let buf_storage_amp = context.storage_buffer(&amp);
+        let buf_storage_phase = context.storage_buffer(&phase);
+        let buf_storage_trans_pos = context.storage_buffer(&transducer_pos);
+        let buf_storage_target_pos = context.storage_buffer(&target_pos);
+        let buf_storage_scattered = context.storage_buffer(&scattered_pressure);
+        let buf_storage_scattered_gradient = context.storage_buffer(&scattered_gradient);
+        let (buf_storage_dst, buf_staging_dst) = context.output_buffers(buf_dst_size);
+        let (buf_storage_velocity, buf_staging_velocity) =
+            context.output_buffers(buf_velocity_size);
 
-        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: None,
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 1,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 2,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 3,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: true },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-                wgpu::BindGroupLayoutEntry {
-                    binding: 4,
-                    visibility: wgpu::ShaderStages::COMPUTE,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Storage { read_only: false },
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                },
-            ],
-        });
+        let Pipeline {
+            bind_group_layout,
+            pipeline,
+        } = context.compute_pipeline(
+            module_path!(),
+            include_str!("shader.wgsl"),
+            &[true, true, true, true, false, false, true, true],
+            size_of::<Pc>() as _,
+        );
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: None,
-            layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: buf_storage_amp.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: buf_storage_phase.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 2,
-                    resource: buf_storage_trans_pos.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 3,
-                    resource: buf_storage_target_pos.as_entire_binding(),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 4,
-                    resource: buf_storage_dst.as_entire_binding(),
-                },
+        let bind_group = context.bind_group(
+            &bind_group_layout,
+            &[
+                &buf_storage_amp,
+                &buf_storage_phase,
+                &buf_storage_trans_pos,
+                &buf_storage_target_pos,
+                &buf_storage_dst,
+                &buf_storage_velocity,
+                &buf_storage_scattered,
+                &buf_storage_scattered_gradient,
             ],
-        });
-
-        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-            label: None,
-            bind_group_layouts: &[Some(&bind_group_layout)],
-            immediate_size: std::mem::size_of::<Pc>() as u32,
-        });
-
-        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
-            label: None,
-            layout: Some(&pipeline_layout),
-            module: &cs_module,
-            entry_point: None,
-            compilation_options: Default::default(),
-            cache: None,
-        });
+        );
 
         Ok(Self {
-            num_transducers: transducer_pos.len() as _,
-            device,
-            queue,
+            num_transducers: records.len() as _,
+            context,
             pipeline,
             bind_group,
             buf_storage_dst,
             buf_staging_dst,
-            buffer: vec![0.; target_pos.len()],
+            buf_storage_velocity,
+            buf_staging_velocity,
+            buffer: vec![[0.; 2]; target_pos.len()],
+            pressure_output: vec![Complex::new(0., 0.); target_pos.len()],
+            velocity_scale,
+            velocity_buffer: if velocity_scale.is_some() {
+                vec![[0.; 4]; 2 * target_pos.len()]
+            } else {
+                Vec::new()
+            },
+            phasor_output: if velocity_scale.is_some() {
+                vec![[Complex::new(0., 0.); 4]; target_pos.len()]
+            } else {
+                Vec::new()
+            },
             stride: stride as _,
+            scatter,
+            statistics: None,
         })
     }
 
+    // Reduces the RMS of each period on GPU instead of returning it until `finish_statistics` is called.
+    pub(crate) fn start_statistics(&mut self) -> Result<(), EmulatorError> {
+        self.statistics = Some(StatisticsGpu::new(
+            &self.context,
+            &self.buf_storage_dst,
+            self.buffer.len(),
+            true,
+        )?);
+        Ok(())
+    }
+
+    pub(crate) fn finish_statistics(
+        &mut self,
+        aggregator: &mut Aggregator,
+    ) -> Result<(), EmulatorError> {
+        self.statistics
+            .take()
+            .map_or(Ok(()), |statistics| statistics.finish(aggregator))
+    }
+
     pub(crate) fn compute(
         &mut self,
         idx: usize,
         wavenumber: f32,
-    ) -> Result<&Vec<f32>, EmulatorError> {
+    ) -> Result<(&[Complex], &[Phasors]), EmulatorError> {
         let pc = Pc {
             idx: idx as _,
             wavenumber,
             num_trans: self.num_transducers,
             stride: self.stride,
+            velocity: self.velocity_scale.is_some() as _,
+            velocity_scale: self.velocity_scale.unwrap_or(0.),
+            scatter: self.scatter as _,
+            _pad: 0,
         };
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-
-        {
-            let mut cpass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
-                label: None,
-                timestamp_writes: None,
-            });
-            cpass.set_pipeline(&self.pipeline);
-            cpass.set_bind_group(0, &self.bind_group, &[]);
-            cpass.set_immediates(0, bytemuck::bytes_of(&pc));
-            cpass.dispatch_workgroups(((self.buffer.len() - 1) / 64 + 1) as _, 1, 1);
+        if let Some(statistics) = &mut self.statistics {
+            self.context.dispatch(
+                &self.pipeline,
+                &self.bind_group,
+                bytemuck::bytes_of(&pc),
+                self.buffer.len(),
+                &[],
+            );
+            statistics.accumulate();
+            return Ok((&[], &[]));
         }
-        encoder.copy_buffer_to_buffer(
-            &self.buf_storage_dst,
-            0,
-            &self.buf_staging_dst,
-            0,
-            (self.buffer.len() * size_of::<f32>()) as u64,
-        );
 
-        self.queue.submit(Some(encoder.finish()));
+        let outputs = [
+            (&self.buf_storage_dst, &self.buf_staging_dst),
+            (&self.buf_storage_velocity, &self.buf_staging_velocity),
+        ];
+        self.context.dispatch(
+            &self.pipeline,
+            &self.bind_group,
+            bytemuck::bytes_of(&pc),
+            self.buffer.len(),
+            &outputs[..if self.velocity_scale.is_some() { 2 } else { 1 }],
+        );
 
-        let buffer_slice = self.buf_staging_dst.slice(..);
-        let pair = Arc::new((Mutex::new(false), Condvar::new()));
-        let pair2 = Arc::clone(&pair);
-        buffer_slice.map_async(wgpu::MapMode::Read, move |_| {
-            let (lock, cvar) = &*pair2;
-            let mut started = lock.lock().unwrap();
-            *started = true;
-            cvar.notify_one();
-        });
-        self.device
-            .poll(wgpu::PollType::wait_indefinitely())
-            .expect("failed to poll device");
-        let (lock, cvar) = &*pair;
-        let mut started = lock.lock().unwrap();
-        // GRCOV_EXCL_START
-        while !*started {
-            started = cvar.wait(started).unwrap();
-        }
-        // GRCOV_EXCL_STOP
-        {
-            let data = buffer_slice.get_mapped_range();
-            self.buffer.copy_from_slice(bytemuck::cast_slice(&data));
+        self.context
+            .read_buffer(&self.buf_staging_dst, &mut self.buffer)?;
+        self.pressure_output
+            .iter_mut()
+            .zip(self.buffer.iter())
+            .for_each(|(dst, &[re, im])| *dst = Complex::new(re, im));
+        if self.velocity_scale.is_some() {
+            self.context
+                .read_buffer(&self.buf_staging_velocity, &mut self.velocity_buffer)?;
+            self.phasor_output
+                .iter_mut()
+                .zip(self.velocity_buffer.chunks_exact(2))
+                .for_each(|(dst, src)| {
+                    *dst = [
+                        Complex::new(src[0][0], src[0][1]),
+                        Complex::new(src[0][2], src[0][3]),
+                        Complex::new(src[1][0], src[1][1]),
+                        Complex::new(src[1][2], src[1][3]),
+                    ]
+                });
         }
-        self.buf_staging_dst.unmap();
 
-        Ok(&self.buffer)
+        Ok((&self.pressure_output, &self.phasor_output))
     }
 }
@@ -1,7 +1,10 @@
+mod angular_spectrum;
 mod cpu;
+mod emission;
 #[cfg(feature = "gpu")]
 mod gpu;
 mod option;
+mod window;
 
 use std::{
     f32::consts::{PI, SQRT_2},
@@ -9,36 +12,83 @@ use std::{
 };
 
 use autd3::{
-    driver::common::ULTRASOUND_PERIOD,
+    driver::{
+        common::ULTRASOUND_PERIOD,
+        geometry::{Complex, Point3},
+    },
     prelude::{Phase, ULTRASOUND_FREQ},
 };
 #[cfg(feature = "polars")]
 use polars::{df, frame::DataFrame, prelude::Column};
 
-use super::{super::Record, SoundFieldOption};
-use crate::{EmulatorError, Range, record::ULTRASOUND_PERIOD_COUNT};
+use super::{
+    super::Record,
+    SoundFieldOption,
+    field::Field,
+    precision::Precision,
+    scatterer,
+    statistics::{Aggregator, Statistic},
+};
+use crate::{EmulatorError, OutputUnit, Range, RangeAxis, record::ULTRASOUND_PERIOD_COUNT};
 
-pub use option::RmsRecordOption;
+pub use option::{EmissionModel, Propagation, RmsRecordOption};
 
 #[derive(Debug)]
-struct RmsTransducerRecord {
+pub(crate) struct RmsTransducerRecord {
     pub(crate) amp: Vec<f32>,
     pub(crate) phase: Vec<f32>,
 }
 
+// The phasors of the pressure and the x, y and z components of the particle velocity at a point.
+pub(crate) type Phasors = [Complex; 4];
+
 #[derive(Debug)]
-enum ComputeDevice {
+pub(crate) enum ComputeDevice {
     Cpu(cpu::Cpu),
+    CpuDouble(cpu::Cpu<f64>),
+    AngularSpectrum(Box<angular_spectrum::AngularSpectrum>),
     #[cfg(feature = "gpu")]
     Gpu(gpu::Gpu),
 }
 
 impl ComputeDevice {
-    fn compute(&mut self, idx: usize, sound_speed: f32) -> Result<&Vec<f32>, EmulatorError> {
+    // Starts reducing the RMS of each period into the statistics on the device, and returns false if the device does not support it.
+    fn start_statistics(&mut self) -> Result<bool, EmulatorError> {
+        match self {
+            #[cfg(feature = "gpu")]
+            Self::Gpu(gpu) => gpu.start_statistics().map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    fn finish_statistics(&mut self, _aggregator: &mut Aggregator) -> Result<(), EmulatorError> {
         match self {
-            Self::Cpu(cpu) => Ok(cpu.compute(idx, sound_speed)),
             #[cfg(feature = "gpu")]
-            Self::Gpu(gpu) => gpu.compute(idx, sound_speed),
+            Self::Gpu(gpu) => gpu.finish_statistics(_aggregator),
+            _ => Ok(()),
+        }
+    }
+
+    // Returns the phasors of the pressure with the RMS amplitudes, and the phasors of the pressure and the particle velocity if enabled, in the period `idx`.
+    pub(crate) fn compute(
+        &mut self,
+        idx: usize,
+        harmonic: usize,
+        sound_speed: f32,
+    ) -> Result<(&[Complex], &[Phasors]), EmulatorError> {
+        let wavenumber = 2. * PI * harmonic as f32 * ULTRASOUND_FREQ.hz() as f32 / sound_speed;
+        match self {
+            Self::Cpu(cpu) => Ok(cpu.compute(idx, wavenumber)),
+            Self::CpuDouble(cpu) => Ok(cpu.compute(
+                idx,
+                2. * std::f64::consts::PI * harmonic as f64 * ULTRASOUND_FREQ.hz() as f64
+                    / sound_speed as f64,
+            )),
+            Self::AngularSpectrum(angular_spectrum) => {
+                Ok(angular_spectrum.compute(idx, wavenumber))
+            }
+            #[cfg(feature = "gpu")]
+            Self::Gpu(gpu) => gpu.compute(idx, wavenumber),
         }
     }
 }
@@ -52,7 +102,11 @@ pub struct Rms {
     x: Vec<f32>,
     y: Vec<f32>,
     z: Vec<f32>,
+    axes: Vec<RangeAxis>,
     compute_device: ComputeDevice,
+    window: window::Window,
+    // The RMS of the pressure in the current period.
+    rms: Vec<f32>,
 }
 
 impl Rms {
@@ -68,11 +122,15 @@ impl Rms {
     }
 
     #[cfg(feature = "polars")]
-    /// Progresses by the specified time and calculates the RMS of the sound field during that time.
+    /// Progresses by the specified time and calculates the RMS of the sound field of each window ending during that time. See [`RmsRecordOption::window`].
+    ///
+    /// If [`RmsRecordOption::particle_velocity`] is true, the real and imaginary parts of the phasors of the pressure and the x, y and z components of the particle velocity follow the RMS of the pressure for each window.
+    /// The phasors are averaged over the window, and they are not converted by [`RmsRecordOption::unit`].
     pub fn next(&mut self, duration: Duration) -> Result<DataFrame, EmulatorError> {
         let n = self.next_time_len(duration);
+        let cols = self.next_cols_per_time();
         let mut time = vec![0; n];
-        let mut v = vec![vec![0.0; self.next_points_len()]; n];
+        let mut v = vec![vec![0.0; self.next_points_len()]; cols * n];
         self.next_inplace(
             duration,
             false,
@@ -83,13 +141,60 @@ impl Rms {
         Ok(DataFrame::new(
             self.next_points_len(),
             time.iter()
-                .zip(v.iter())
-                .map(|(t, v)| Column::new(format!("rms[Pa]@{t}[ns]").into(), v))
+                .zip(v.chunks(cols))
+                .flat_map(|(t, v)| {
+                    [
+                        format!("{}@{t}[ns]", self.option.unit.column_name("rms")),
+                        format!("rms_re[Pa]@{t}[ns]"),
+                        format!("rms_im[Pa]@{t}[ns]"),
+                        format!("rms_vx_re[m/s]@{t}[ns]"),
+                        format!("rms_vx_im[m/s]@{t}[ns]"),
+                        format!("rms_vy_re[m/s]@{t}[ns]"),
+                        format!("rms_vy_im[m/s]@{t}[ns]"),
+                        format!("rms_vz_re[m/s]@{t}[ns]"),
+                        format!("rms_vz_im[m/s]@{t}[ns]"),
+                    ]
+                    .into_iter()
+                    .zip(v.iter())
+                    .map(|(name, v)| Column::new(name.into(), v))
+                })
                 .collect::<Vec<_>>(),
         )
         .unwrap())
     }
 
+    /// Progresses by the specified time and calculates the RMS of the sound pressure of each window ending during that time reshaped onto the axes of the range.
+    pub fn next_field(&mut self, duration: Duration) -> Result<Field, EmulatorError> {
+        let mut field = Field::new(self.axes.clone());
+        self.for_each_frame(duration, |t, r| field.push(Duration::from_nanos(t), r))?;
+        Ok(field)
+    }
+
+    /// Progresses by the specified time and calls `f` with the start time \[ns\] and the RMS of the sound pressure of each window ending during that time.
+    ///
+    /// Unlike [`Rms::next`], the RMS is passed by reference to the internal buffer, so that the memory usage does not depend on the duration.
+    pub fn for_each_frame(
+        &mut self,
+        duration: Duration,
+        mut f: impl FnMut(u64, &[f32]),
+    ) -> Result<(), EmulatorError> {
+        let (unit, density, sound_speed) = (
+            self.option.unit,
+            self.option.density,
+            self.option.sound_speed,
+        );
+        let mut buf = Vec::new();
+        self.process(duration, false, |t, r, _| {
+            if unit == OutputUnit::Pascal {
+                return f(t, r);
+            }
+            buf.clear();
+            buf.extend_from_slice(r);
+            unit.convert(&mut buf, density, sound_speed);
+            f(t, &buf)
+        })
+    }
+
     /// Progresses by the specified time.
     pub fn skip(&mut self, duration: Duration) -> Result<&mut Self, EmulatorError> {
         self.next_inplace(duration, true, &mut [], std::iter::empty())?;
@@ -115,7 +220,8 @@ impl Rms {
 
     #[doc(hidden)]
     pub fn next_time_len(&self, duration: Duration) -> usize {
-        (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize
+        let num_frames = (duration.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize;
+        self.window.count(self.cursor..self.cursor + num_frames)
     }
 
     #[doc(hidden)]
@@ -123,6 +229,11 @@ impl Rms {
         self.x.len()
     }
 
+    #[doc(hidden)]
+    pub fn next_cols_per_time(&self) -> usize {
+        if self.option.particle_velocity { 9 } else { 1 }
+    }
+
     #[doc(hidden)]
     pub fn next_inplace(
         &mut self,
@@ -130,6 +241,99 @@ impl Rms {
         skip: bool,
         time: &mut [u64],
         mut v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        let particle_velocity = self.option.particle_velocity;
+        let (unit, density, sound_speed) = (
+            self.option.unit,
+            self.option.density,
+            self.option.sound_speed,
+        );
+        let mut i = 0;
+        self.process(duration, skip, |t, r, phasors| {
+            time[i] = t;
+            unsafe {
+                let dst = v.next().unwrap();
+                std::ptr::copy_nonoverlapping(r.as_ptr(), dst, r.len());
+                unit.convert(
+                    std::slice::from_raw_parts_mut(dst, r.len()),
+                    density,
+                    sound_speed,
+                );
+            }
+            if particle_velocity {
+                (0..4).for_each(|c| {
+                    let (re, im) = (v.next().unwrap(), v.next().unwrap());
+                    phasors.iter().enumerate().for_each(|(j, p)| unsafe {
+                        *re.add(j) = p[c].re;
+                        *im.add(j) = p[c].im;
+                    });
+                });
+            }
+            i += 1;
+        })
+    }
+
+    /// Progresses by the specified time and calculates the statistics of the RMS of each window for each point during that time.
+    ///
+    /// Unlike [`Rms::next`], the RMS of each window is not returned, so that the memory usage does not depend on the duration.
+    /// On GPU, if the window is a single period, the statistics except for [`Statistic::Percentile`] are reduced on the device without reading back the RMS of each period.
+    #[cfg(feature = "polars")]
+    pub fn aggregate(
+        &mut self,
+        duration: Duration,
+        statistics: &[Statistic],
+    ) -> Result<DataFrame, EmulatorError> {
+        let mut v = vec![vec![0.0; self.next_points_len()]; statistics.len()];
+        self.aggregate_inplace(duration, statistics, v.iter_mut().map(|v| v.as_mut_ptr()))?;
+
+        Ok(DataFrame::new(
+            self.next_points_len(),
+            statistics
+                .iter()
+                .zip(v.iter())
+                .map(|(s, v)| Column::new(format!("rms_{s}[Pa]").into(), v))
+                .collect::<Vec<_>>(),
+        )
+        .unwrap())
+    }
+
+    #[doc(hidden)]
+    pub fn aggregate_inplace(
+        &mut self,
+        duration: Duration,
+        statistics: &[Statistic],
+        v: impl Iterator<Item = *mut f32>,
+    ) -> Result<(), EmulatorError> {
+        let mut aggregator = Aggregator::new(
+            statistics,
+            self.next_points_len(),
+            self.option
+                .memory_limits_hint_mb
+                .saturating_mul(1024 * 1024),
+        )?;
+        // The windows of more than one period are averaged on CPU.
+        let reduce = self.window.len() == 1
+            && !Aggregator::needs_samples(statistics)
+            && self.compute_device.start_statistics()?;
+        let result = self.process(duration, false, |_, r, _| {
+            if !reduce {
+                aggregator.push(r)
+            }
+        });
+        if reduce {
+            self.compute_device.finish_statistics(&mut aggregator)?;
+        }
+        result?;
+        aggregator.finish(v);
+        Ok(())
+    }
+
+    // Calls `f` with the start time, the RMS of the pressure and the mean phasors (empty if the particle velocity is disabled) of each window.
+    fn process(
+        &mut self,
+        duration: Duration,
+        skip: bool,
+        mut f: impl FnMut(u64, &[f32], &[Phasors]),
     ) -> Result<(), EmulatorError> {
         if !duration
             .as_nanos()
@@ -144,21 +348,38 @@ impl Rms {
             return Err(EmulatorError::NotRecorded);
         }
 
-        if !skip {
-            let wavenumber = 2. * PI * ULTRASOUND_FREQ.hz() as f32 / self.option.sound_speed;
-            let mut i = 0;
-            while i < num_frames {
-                let cur_frame = self.cursor + i;
-                let r = self.compute_device.compute(cur_frame, wavenumber)?;
-                time[i] = (cur_frame as u32 * ULTRASOUND_PERIOD).as_nanos() as u64;
-                unsafe {
-                    std::ptr::copy_nonoverlapping(r.as_ptr(), v.next().unwrap(), r.len());
-                }
-                i += 1;
+        // Even if skipped, the last periods are needed for the windows ending after them.
+        let end = self.cursor + num_frames;
+        let begin = if skip {
+            end.saturating_sub(self.window.len() - 1).max(self.cursor)
+        } else {
+            self.cursor
+        };
+        (begin..end).try_for_each(|cur_frame| {
+            // The periods which do not end a window are not needed if a window is a single period.
+            if self.window.len() == 1 && self.window.count(cur_frame..cur_frame + 1) == 0 {
+                return Ok(());
             }
-        }
+            let (p, phasors) = self.compute_device.compute(
+                cur_frame,
+                self.option.harmonic,
+                self.option.sound_speed,
+            )?;
+            self.rms.clear();
+            self.rms.extend(p.iter().map(|p| p.norm()));
+            if let Some((start, r, phasors)) = self.window.push(cur_frame, &self.rms, phasors)
+                && !skip
+            {
+                f(
+                    (start as u32 * ULTRASOUND_PERIOD).as_nanos() as u64,
+                    r,
+                    phasors,
+                );
+            }
+            Ok::<_, EmulatorError>(())
+        })?;
 
-        self.cursor += num_frames;
+        self.cursor = end;
 
         Ok(())
     }
@@ -167,17 +388,8 @@ impl Rms {
 impl Record {
     pub(crate) const P0: f32 = autd3::driver::common::T4010A1_AMPLITUDE / (4. * PI) / SQRT_2;
 
-    fn sound_field_rms(
-        &self,
-        range: impl Range,
-        option: RmsRecordOption,
-    ) -> Result<Rms, EmulatorError> {
-        let max_frame = self.records[0].pulse_width.len();
-
-        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
-
-        let records = self
-            .records
+    pub(crate) fn rms_transducer_records(&self) -> Vec<RmsTransducerRecord> {
+        self.records
             .iter()
             .map(|tr| RmsTransducerRecord {
                 amp: (tr
@@ -187,42 +399,193 @@ impl Record {
                     .collect()),
                 phase: tr.phase.iter().map(|&p| Phase(p).radian()).collect(),
             })
-            .collect();
-
-        #[cfg(feature = "gpu")]
-        let compute_device = if option.gpu {
-            ComputeDevice::Gpu(gpu::Gpu::new(
-                &x,
-                &y,
-                &z,
-                self.records.iter().map(|tr| tr.tr.position()),
-                records,
-            )?)
-        } else {
-            ComputeDevice::Cpu(cpu::Cpu::new(
-                &x,
-                &y,
-                &z,
-                self.records.iter().map(|tr| tr.tr.position()),
-                records,
-            ))
+            .collect()
+    }
+
+    // The amplitude and phase of each transducer in each period according to the emission model and the order of the harmonic.
+    pub(crate) fn rms_transducer_records_of(
+        &self,
+        option: &RmsRecordOption,
+    ) -> Result<Vec<RmsTransducerRecord>, EmulatorError> {
+        if option.harmonic == 0 {
+            return Err(EmulatorError::InvalidHarmonic);
+        }
+        Ok(match (option.emission, option.harmonic, option.precision) {
+            (EmissionModel::Ideal, 1, _) => self.rms_transducer_records(),
+            (EmissionModel::Ideal, n, Precision::Single) => {
+                self.rms_transducer_records_ideal_harmonic::<f32>(n)
+            }
+            (EmissionModel::Ideal, n, Precision::Double) => {
+                self.rms_transducer_records_ideal_harmonic::<f64>(n)
+            }
+            (EmissionModel::Bvd, n, Precision::Single) => self.rms_transducer_records_bvd::<f32>(n),
+            (EmissionModel::Bvd, n, Precision::Double) => self.rms_transducer_records_bvd::<f64>(n),
+        })
+    }
+
+    // The device to compute the sound field at the points in each period, which is shared with [`Phasor`].
+    //
+    // [`Phasor`]: crate::Phasor
+    pub(crate) fn rms_compute_device(
+        &self,
+        x: &[f32],
+        y: &[f32],
+        z: &[f32],
+        axes: &[RangeAxis],
+        option: &RmsRecordOption,
+    ) -> Result<ComputeDevice, EmulatorError> {
+        let records = self.rms_transducer_records_of(option)?;
+        let sources = self.sources(&option.reflectors, option.reflection_order);
+
+        let velocity_scale = option.particle_velocity.then(|| {
+            crate::record::sound_field::VELOCITY_SCALE
+                / (2. * PI * option.harmonic as f32 * ULTRASOUND_FREQ.hz() as f32 * option.density)
+        });
+
+        let wavenumber =
+            2. * PI * option.harmonic as f32 * ULTRASOUND_FREQ.hz() as f32 / option.sound_speed;
+
+        // The positions and the results of the points are also on memory.
+        let memory_limits = option
+            .memory_limits_hint_mb
+            .saturating_mul(1024 * 1024)
+            .saturating_sub(x.len() * 4 * size_of::<f32>());
+
+        Ok(match option.propagation {
+            Propagation::AngularSpectrum { margin } => {
+                if !option.reflectors.is_empty() || !option.scatterers.is_empty() {
+                    return Err(EmulatorError::InvalidAngularSpectrum(
+                        "reflectors and scatterers are not supported".into(),
+                    ));
+                }
+                #[cfg(feature = "gpu")]
+                if option.gpu {
+                    return Err(EmulatorError::InvalidAngularSpectrum(
+                        "GPU is not supported".into(),
+                    ));
+                }
+                ComputeDevice::AngularSpectrum(Box::new(angular_spectrum::AngularSpectrum::new(
+                    axes,
+                    sources,
+                    records,
+                    velocity_scale,
+                    wavenumber,
+                    margin,
+                    memory_limits,
+                )?))
+            }
+            Propagation::Direct => {
+                // The scattered pressure, and its gradient for the particle velocity, of each source at each point.
+                let scattered_size = if option.scatterers.is_empty() {
+                    0
+                } else {
+                    x.len()
+                        .saturating_mul(sources.len())
+                        .saturating_mul(size_of::<Complex>())
+                        .saturating_mul(if velocity_scale.is_some() { 4 } else { 1 })
+                };
+                if scattered_size > memory_limits {
+                    return Err(EmulatorError::MemoryLimitsExceeded {
+                        size: scattered_size,
+                        limit: memory_limits,
+                    });
+                }
+                let memory_limits = memory_limits - scattered_size;
+
+                let scattered = scatterer::scattered_field(
+                    &option.scatterers,
+                    &sources,
+                    &x.iter()
+                        .zip(y.iter())
+                        .zip(z.iter())
+                        .map(|((&x, &y), &z)| Point3::new(x, y, z))
+                        .collect::<Vec<_>>(),
+                    wavenumber,
+                    velocity_scale.is_some(),
+                );
+
+                let cpu = |sources, records, scattered| match option.precision {
+                    Precision::Single => ComputeDevice::Cpu(cpu::Cpu::new(
+                        x,
+                        y,
+                        z,
+                        sources,
+                        records,
+                        velocity_scale,
+                        scattered,
+                        memory_limits,
+                    )),
+                    Precision::Double => ComputeDevice::CpuDouble(cpu::Cpu::new(
+                        x,
+                        y,
+                        z,
+                        sources,
+                        records,
+                        velocity_scale,
+                        scattered,
+                        memory_limits,
+                    )),
+                };
+                #[cfg(feature = "gpu")]
+                let compute_device = if option.gpu {
+                    ComputeDevice::Gpu(gpu::Gpu::new(
+                        x,
+                        y,
+                        z,
+                        sources,
+                        records,
+                        velocity_scale,
+                        scattered,
+                        crate::GpuContext::get_or_new(
+                            option.gpu_context.as_ref(),
+                            option.gpu_option,
+                        )?,
+                    )?)
+                } else {
+                    cpu(sources, records, scattered)
+                };
+                #[cfg(not(feature = "gpu"))]
+                let compute_device = cpu(sources, records, scattered);
+                compute_device
+            }
+        })
+    }
+
+    fn sound_field_rms(
+        &self,
+        range: impl Range,
+        option: RmsRecordOption,
+    ) -> Result<Rms, EmulatorError> {
+        let window_len = |window: Duration| {
+            (!window.is_zero()
+                && window
+                    .as_nanos()
+                    .is_multiple_of(ULTRASOUND_PERIOD.as_nanos()))
+            .then(|| (window.as_nanos() / ULTRASOUND_PERIOD.as_nanos()) as usize)
+            .ok_or(EmulatorError::InvalidWindow)
         };
-        #[cfg(not(feature = "gpu"))]
-        let compute_device = ComputeDevice::Cpu(cpu::Cpu::new(
-            &x,
-            &y,
-            &z,
-            self.records.iter().map(|tr| tr.tr.position()),
-            records,
-        ));
+        let window = window::Window::new(
+            window_len(option.window)?,
+            window_len(option.window_step.unwrap_or(option.window))?,
+        );
+
+        let max_frame = self.records[0].pulse_width.len();
+
+        let (x, y, z): (Vec<_>, Vec<_>, Vec<_>) = range.points().collect();
+        let axes = range.axes();
+
+        let compute_device = self.rms_compute_device(&x, &y, &z, &axes, &option)?;
 
         Ok(Rms {
             compute_device,
             cursor: 0,
             max_frame,
+            rms: Vec::with_capacity(x.len()),
             x,
             y,
             z,
+            axes,
+            window,
             option,
         })
     }
@@ -1,22 +1,128 @@
-use autd3::prelude::mm;
+use std::time::Duration;
+
+use autd3::{driver::common::ULTRASOUND_PERIOD, prelude::mm};
+
+#[cfg(feature = "gpu")]
+use crate::{GpuContext, GpuOption};
+use crate::{OutputUnit, Precision, Reflector, Scatterer};
+
+/// Model of the ultrasound emitted from each transducer in each period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmissionModel {
+    /// Steady-state sinusoid whose amplitude and phase are determined by the pulse width and phase of the period.
+    #[default]
+    Ideal,
+    /// Fundamental component of the waveform filtered by the BVD model of the transducer as in [`Instant`], which captures the transient response.
+    ///
+    /// [`Instant`]: crate::Instant
+    Bvd,
+}
+
+/// Method to propagate the ultrasound from the transducers to the observed points.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Propagation {
+    /// Summation over the transducers at each point.
+    #[default]
+    Direct,
+    /// Angular spectrum method.
+    ///
+    /// The sound field is calculated by the direct summation only on the plane of the smallest z of the range, and is propagated to the other planes by the FFT.
+    /// This is much faster than [`Propagation::Direct`] for ranges with many planes, such as [`RangeXYZ`].
+    ///
+    /// The range must be a grid of at least 2 points along both x and y axes whose resolution is smaller than half the wavelength, and all the transducers must be below it.
+    /// Reflectors, scatterers and GPU are not supported, and the computation is always in single precision.
+    ///
+    /// [`RangeXYZ`]: crate::RangeXYZ
+    AngularSpectrum {
+        /// Margin of the calculated plane around the transducers and the range \[mm\].
+        /// The field outside it is truncated, which is the main source of the error near the edges of the range.
+        margin: f32,
+    },
+}
 
 /// Options for RMS recording.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct RmsRecordOption {
     /// Sound speed [mm/s].
     pub sound_speed: f32,
+    /// Density of the medium \[kg/m³\]. This is used to calculate the particle velocity and the intensity.
+    pub density: f32,
+    /// If true, the phasors of the pressure and each component of the particle velocity are also calculated.
+    ///
+    /// The phasors have the RMS amplitudes, so that the time-averaged intensity is `Re(p v*)` without the factor of 1/2.
+    pub particle_velocity: bool,
+    /// Planar reflectors.
+    pub reflectors: Vec<Reflector>,
+    /// Maximum number of reflections of each path.
+    pub reflection_order: usize,
+    /// Rigid spherical scatterers.
+    ///
+    /// The scattered field at each point for each transducer is calculated beforehand, which requires memory proportional to the number of points times the number of transducers and their images.
+    /// It is counted in [`RmsRecordOption::memory_limits_hint_mb`], and [`EmulatorError::MemoryLimitsExceeded`] is returned if it does not fit.
+    ///
+    /// [`EmulatorError::MemoryLimitsExceeded`]: crate::EmulatorError::MemoryLimitsExceeded
+    pub scatterers: Vec<Scatterer>,
+    /// Model of the emitted ultrasound.
+    pub emission: EmissionModel,
+    /// Order of the harmonic. The sound field of the component at `harmonic` times the ultrasound frequency is calculated, and 1 is the fundamental component.
+    ///
+    /// The harmonics originate from the PWM drive partly radiated through the transducer, whose amplitude and phase are derived from the BVD model.
+    /// With [`EmissionModel::Ideal`], the steady state of the pulse width of each period is assumed.
+    pub harmonic: usize,
+    /// Length of the averaging window. Must be a non-zero multiple of the ultrasound period.
+    ///
+    /// The RMS over a window is the root of the mean of the squared RMS of each period in it, so that the changes of the amplitude and phase within the window are taken into account.
+    pub window: Duration,
+    /// Interval between the starts of successive windows. Must be a non-zero multiple of the ultrasound period.
+    /// If `None`, it is the same as [`RmsRecordOption::window`], i.e., the windows do not overlap.
+    /// The windows are aligned to the start of the record.
+    pub window_step: Option<Duration>,
+    /// Memory limits hint \[MB\].
+    ///
+    /// If the distances between the points and the sources do not fit in it, they are calculated for each tile of points on CPU every period.
+    pub memory_limits_hint_mb: usize,
+    /// Unit of the RMS of the sound pressure in the results. The statistics of `aggregate` are always in \[Pa\].
+    pub unit: OutputUnit,
+    /// Floating-point precision of the computation on CPU, which also applies to [`EmissionModel::Bvd`] and the harmonics.
+    /// The scattered field of [`RmsRecordOption::scatterers`] is stored in single precision, and the computation on GPU is always in single precision.
+    pub precision: Precision,
+    /// Method to propagate the ultrasound.
+    pub propagation: Propagation,
     #[cfg_attr(docsrs, doc(cfg(feature = "remote")))]
     #[cfg(feature = "gpu")]
     /// If true, use GPU for computation.
     pub gpu: bool,
+    #[cfg(feature = "gpu")]
+    /// Context of GPU shared across the computations. If `None`, a new context is created for each computation with `gpu`.
+    pub gpu_context: Option<GpuContext>,
+    #[cfg(feature = "gpu")]
+    /// Selection of the adapter for the new context. Ignored if `gpu_context` is given.
+    pub gpu_option: GpuOption,
 }
 
 impl std::default::Default for RmsRecordOption {
     fn default() -> Self {
         Self {
             sound_speed: 340e3 * mm,
+            density: 1.18,
+            particle_velocity: false,
+            reflectors: Vec::new(),
+            reflection_order: 1,
+            scatterers: Vec::new(),
+            emission: EmissionModel::Ideal,
+            harmonic: 1,
+            window: ULTRASOUND_PERIOD,
+            window_step: None,
+            memory_limits_hint_mb: 128,
+            unit: OutputUnit::Pascal,
+            precision: Precision::Single,
+            propagation: Propagation::Direct,
             #[cfg(feature = "gpu")]
             gpu: false,
+            #[cfg(feature = "gpu")]
+            gpu_context: None,
+            #[cfg(feature = "gpu")]
+            gpu_option: GpuOption::default(),
         }
     }
 }
@@ -8,7 +8,7 @@ var<storage, read> v_phase: array<f32>;
 
 @group(0)
 @binding(2)
-var<storage, read> v_tr_pos: array<vec3<f32>>;
+var<storage, read> v_src: array<vec4<f32>>;
 
 @group(0)
 @binding(3)
@@ -16,13 +16,30 @@ var<storage, read> v_tar_pos: array<vec3<f32>>;
 
 @group(0)
 @binding(4)
-var<storage, read_write> v_dst: array<f32>;
+var<storage, read_write> v_dst: array<vec2<f32>>;
+
+@group(0)
+@binding(5)
+var<storage, read_write> v_vel: array<vec4<f32>>;
+
+// The scattered pressure and its gradient of each source with the unit amplitude at each target.
+@group(0)
+@binding(6)
+var<storage, read> v_scat: array<vec2<f32>>;
+
+@group(0)
+@binding(7)
+var<storage, read> v_scat_grad: array<vec2<f32>>;
 
 struct Pc {
     idx: u32,
     wavenumber: f32,
     num_trans: u32,
     stride: u32,
+    velocity: u32,
+    velocity_scale: f32,
+    scatter: u32,
+    _pad: u32,
 }
 
 var<immediate> pc: Pc;
@@ -35,12 +52,46 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
     }
     var re: f32 = 0.;
     var im: f32 = 0.;
-    for (var i: u32 = 0; i < pc.num_trans; i++) {
-        let dist = distance(v_tar_pos[global_id.x], v_tr_pos[i]);
-        let phase = pc.wavenumber * dist + v_phase[i * pc.stride + pc.idx];
-        let r = v_amp[i * pc.stride + pc.idx] / dist;
-        re += r * cos(phase);
-        im += r * sin(phase);
+    var grad_re = vec3<f32>(0., 0., 0.);
+    var grad_im = vec3<f32>(0., 0., 0.);
+    for (var i: u32 = 0; i < arrayLength(&v_src); i++) {
+        let d = v_tar_pos[global_id.x] - v_src[i].xyz;
+        let dist = length(d);
+        let j = (i % pc.num_trans) * pc.stride + pc.idx;
+        let phase = pc.wavenumber * dist + v_phase[j];
+        let r = v_src[i].w * v_amp[j] / dist;
+        let p_re = r * cos(phase);
+        let p_im = r * sin(phase);
+        re += p_re;
+        im += p_im;
+        if pc.velocity != 0u {
+            // p * (ik - 1/r) * d / r
+            let n = d / (dist * dist);
+            grad_re += (-pc.wavenumber * p_im - p_re / dist) * n;
+            grad_im += (pc.wavenumber * p_re - p_im / dist) * n;
+        }
+        if pc.scatter != 0u {
+            let e_re = v_src[i].w * v_amp[j] * cos(v_phase[j]);
+            let e_im = v_src[i].w * v_amp[j] * sin(v_phase[j]);
+            let k = global_id.x * arrayLength(&v_src) + i;
+            let s = v_scat[k];
+            re += e_re * s.x - e_im * s.y;
+            im += e_re * s.y + e_im * s.x;
+            if pc.velocity != 0u {
+                let gx = v_scat_grad[3u * k];
+                let gy = v_scat_grad[3u * k + 1u];
+                let gz = v_scat_grad[3u * k + 2u];
+                grad_re += e_re * vec3<f32>(gx.x, gy.x, gz.x) - e_im * vec3<f32>(gx.y, gy.y, gz.y);
+                grad_im += e_re * vec3<f32>(gx.y, gy.y, gz.y) + e_im * vec3<f32>(gx.x, gy.x, gz.x);
+            }
+        }
+    }
+    v_dst[global_id.x] = vec2<f32>(re, im);
+    if pc.velocity != 0u {
+        // the phasors of the pressure and the particle velocity -i grad p / (ω ρ)
+        let v_re = grad_im * pc.velocity_scale;
+        let v_im = -grad_re * pc.velocity_scale;
+        v_vel[2u * global_id.x] = vec4<f32>(re, im, v_re.x, v_im.x);
+        v_vel[2u * global_id.x + 1u] = vec4<f32>(v_re.y, v_im.y, v_re.z, v_im.z);
     }
-    v_dst[global_id.x] = sqrt(re * re + im * im);
 }
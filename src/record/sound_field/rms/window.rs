@@ -0,0 +1,172 @@
+use std::{collections::VecDeque, ops::Range};
+
+use autd3::driver::geometry::Complex;
+
+use super::Phasors;
+
+// The first period, the RMS of the pressure and the mean phasors of a window.
+type Mean<'a> = (usize, &'a [f32], &'a [Phasors]);
+
+// Averages the mean squares of the sound field and the phasors of each period over the windows.
+// The `k`-th window covers the periods `[k * step, k * step + len)`.
+#[derive(Debug)]
+pub(crate) struct Window {
+    len: usize,
+    step: usize,
+    // The squares of the RMS of the last `len` periods at most.
+    squares: VecDeque<Vec<f32>>,
+    phasors: VecDeque<Vec<Phasors>>,
+    // The index of the period following the last pushed one.
+    end: usize,
+    rms: Vec<f32>,
+    mean_phasors: Vec<Phasors>,
+}
+
+impl Window {
+    pub(crate) fn new(len: usize, step: usize) -> Self {
+        Self {
+            len,
+            step,
+            squares: VecDeque::with_capacity(len),
+            phasors: VecDeque::with_capacity(len),
+            end: 0,
+            rms: Vec::new(),
+            mean_phasors: Vec::new(),
+        }
+    }
+
+    pub(crate) const fn len(&self) -> usize {
+        self.len
+    }
+
+    // Returns the number of the windows ending in the periods.
+    pub(crate) fn count(&self, periods: Range<usize>) -> usize {
+        periods
+            .filter(|&i| i + 1 >= self.len && (i + 1 - self.len).is_multiple_of(self.step))
+            .count()
+    }
+
+    // Pushes the RMS of the period `idx`, and returns the first period and the RMS of the window if a window ends at the period.
+    pub(crate) fn push<'a>(
+        &'a mut self,
+        idx: usize,
+        rms: &'a [f32],
+        phasors: &'a [Phasors],
+    ) -> Option<Mean<'a>> {
+        let ends = idx + 1 >= self.len && (idx + 1 - self.len).is_multiple_of(self.step);
+        if self.len == 1 {
+            return ends.then_some((idx, rms, phasors));
+        }
+
+        if idx != self.end {
+            self.squares.clear();
+            self.phasors.clear();
+        }
+        self.end = idx + 1;
+
+        let mut square = if self.squares.len() == self.len {
+            self.squares.pop_front().unwrap()
+        } else {
+            Vec::with_capacity(rms.len())
+        };
+        square.clear();
+        square.extend(rms.iter().map(|r| r * r));
+        self.squares.push_back(square);
+        if !phasors.is_empty() {
+            let mut p = if self.phasors.len() == self.len {
+                self.phasors.pop_front().unwrap()
+            } else {
+                Vec::with_capacity(phasors.len())
+            };
+            p.clear();
+            p.extend_from_slice(phasors);
+            self.phasors.push_back(p);
+        }
+
+        if !ends || self.squares.len() < self.len {
+            return None;
+        }
+
+        let n = self.len as f32;
+        self.rms.clear();
+        self.rms.resize(rms.len(), 0.);
+        self.squares.iter().for_each(|square| {
+            self.rms
+                .iter_mut()
+                .zip(square.iter())
+                .for_each(|(acc, s)| *acc += s)
+        });
+        self.rms.iter_mut().for_each(|r| *r = (*r / n).sqrt());
+
+        self.mean_phasors.clear();
+        self.mean_phasors
+            .resize(phasors.len(), [Complex::new(0., 0.); 4]);
+        self.phasors.iter().for_each(|p| {
+            self.mean_phasors
+                .iter_mut()
+                .zip(p.iter())
+                .for_each(|(acc, p)| (0..4).for_each(|c| acc[c] += p[c]))
+        });
+        self.mean_phasors
+            .iter_mut()
+            .for_each(|p| *p = p.map(|p| p / n));
+
+        Some((idx + 1 - self.len, &self.rms, &self.mean_phasors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(vec![0, 1, 2, 3, 4], 1, 1, vec![0, 1, 2, 3, 4])]
+    #[case(vec![0, 2, 4], 1, 2, vec![0, 1, 2, 3, 4])]
+    #[case(vec![0, 2, 4], 3, 2, vec![0, 1, 2, 3, 4, 5, 6])]
+    #[case(vec![0, 1, 2], 3, 1, vec![0, 1, 2, 3, 4])]
+    #[case(vec![3], 3, 1, vec![0, 1, 3, 4, 5])]
+    fn push(
+        #[case] expect: Vec<usize>,
+        #[case] len: usize,
+        #[case] step: usize,
+        #[case] periods: Vec<usize>,
+    ) {
+        let mut window = Window::new(len, step);
+        let starts = periods
+            .into_iter()
+            .filter_map(|i| window.push(i, &[1.], &[]).map(|(start, _, _)| start))
+            .collect::<Vec<_>>();
+        assert_eq!(expect, starts);
+    }
+
+    #[rstest::rstest]
+    #[case(5, 1, 1, 0..5)]
+    #[case(3, 1, 2, 0..5)]
+    #[case(3, 3, 2, 0..7)]
+    #[case(3, 3, 1, 0..5)]
+    #[case(1, 3, 1, 4..5)]
+    fn count(
+        #[case] expect: usize,
+        #[case] len: usize,
+        #[case] step: usize,
+        #[case] periods: Range<usize>,
+    ) {
+        assert_eq!(expect, Window::new(len, step).count(periods));
+    }
+
+    #[test]
+    fn mean_square() {
+        let phasors = |re: f32, im: f32| [[Complex::new(re, im); 4]; 2];
+        let mut window = Window::new(2, 1);
+        let (first, second) = (phasors(1., 2.), phasors(3., 0.));
+        assert!(window.push(0, &[3., 0.], &first).is_none());
+        let (start, rms, mean) = window.push(1, &[4., 1.], &second).unwrap();
+        assert_eq!(0, start);
+        approx::assert_abs_diff_eq!(
+            [12.5f32.sqrt(), 0.5f32.sqrt()].as_slice(),
+            rms,
+            epsilon = 1e-6
+        );
+        assert_eq!(phasors(2., 1.), mean);
+    }
+}
@@ -0,0 +1,436 @@
+use autd3::driver::geometry::{Complex, Point3};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::reflector::Source;
+
+/// A rigid sphere scattering the sound field, such as a levitated particle or a fingertip.
+///
+/// The scattered wave of each source is calculated by the analytic multipole solution, and the multiple scattering between the scatterers and the reflectors is ignored.
+/// The sound field inside the sphere is zero.
+/// As the solution assumes the steady state, the scatterers are available only in [`RmsRecordOption::scatterers`].
+///
+/// [`RmsRecordOption::scatterers`]: crate::RmsRecordOption::scatterers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Scatterer {
+    /// The center of the sphere.
+    pub center: Point3,
+    /// The radius of the sphere.
+    pub radius: f32,
+}
+
+// A complex number in double precision for the multipole expansion.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct C64 {
+    re: f64,
+    im: f64,
+}
+
+impl C64 {
+    const ZERO: Self = Self { re: 0., im: 0. };
+
+    const fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(
+            self.re * rhs.re - self.im * rhs.im,
+            self.re * rhs.im + self.im * rhs.re,
+        )
+    }
+
+    fn div(self, rhs: Self) -> Self {
+        let d = rhs.re * rhs.re + rhs.im * rhs.im;
+        Self::new(
+            (self.re * rhs.re + self.im * rhs.im) / d,
+            (self.im * rhs.re - self.re * rhs.im) / d,
+        )
+    }
+
+    fn scale(self, s: f64) -> Self {
+        Self::new(self.re * s, self.im * s)
+    }
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.re + rhs.re, self.im + rhs.im)
+    }
+
+    fn to_f32(self) -> Complex {
+        Complex::new(self.re as f32, self.im as f32)
+    }
+}
+
+// Spherical Bessel functions of the first and second kinds of orders `0..=n` for `n >= 1`.
+fn spherical_bessel(n: usize, x: f64) -> (Vec<f64>, Vec<f64>) {
+    let (s, c) = x.sin_cos();
+    let j0 = s / x;
+    let j1 = s / (x * x) - c / x;
+
+    let mut y = vec![-c / x, -c / (x * x) - s / x];
+    (1..n).for_each(|k| y.push((2 * k + 1) as f64 / x * y[k] - y[k - 1]));
+
+    // The upward recurrence of the first kind is unstable for the orders above `x`, where Miller's downward recurrence is used instead.
+    let j = if x > n as f64 {
+        let mut j = vec![j0, j1];
+        (1..n).for_each(|k| j.push((2 * k + 1) as f64 / x * j[k] - j[k - 1]));
+        j
+    } else {
+        let m = 2 * n + 20;
+        let mut j = vec![0.; m + 2];
+        j[m] = 1e-30;
+        (1..=m).rev().for_each(|k| {
+            j[k - 1] = (2 * k + 1) as f64 / x * j[k] - j[k + 1];
+            if j[k - 1].abs() > 1e200 {
+                j.iter_mut().for_each(|v| *v *= 1e-200);
+            }
+        });
+        let scale = if j0.abs() > j1.abs() {
+            j0 / j[0]
+        } else {
+            j1 / j[1]
+        };
+        j.truncate(n + 1);
+        j.iter_mut().for_each(|v| *v *= scale);
+        j
+    };
+    (j, y)
+}
+
+// The derivatives of the spherical Bessel functions `f` of orders `0..=n`.
+fn derivative(f: &[f64], x: f64) -> Vec<f64> {
+    (0..f.len())
+        .map(|k| {
+            if k == 0 {
+                -f[1]
+            } else {
+                f[k - 1] - (k + 1) as f64 / x * f[k]
+            }
+        })
+        .collect()
+}
+
+// Spherical Hankel functions of the first kind of orders `0..=n` and their derivatives.
+fn spherical_hankel(n: usize, x: f64) -> (Vec<C64>, Vec<C64>) {
+    let (j, y) = spherical_bessel(n, x);
+    let (dj, dy) = (derivative(&j, x), derivative(&y, x));
+    (
+        j.iter()
+            .zip(y.iter())
+            .map(|(&j, &y)| C64::new(j, y))
+            .collect(),
+        dj.iter()
+            .zip(dy.iter())
+            .map(|(&j, &y)| C64::new(j, y))
+            .collect(),
+    )
+}
+
+// Legendre polynomials of orders `0..=n` and their derivatives.
+fn legendre(n: usize, x: f64) -> (Vec<f64>, Vec<f64>) {
+    let mut p = vec![1., x];
+    let mut dp = vec![0., 1.];
+    (1..n).for_each(|k| {
+        p.push(((2 * k + 1) as f64 * x * p[k] - k as f64 * p[k - 1]) / (k + 1) as f64);
+        dp.push(dp[k - 1] + (2 * k + 1) as f64 * p[k]);
+    });
+    (p, dp)
+}
+
+// The free-field Green's function `exp(ikr) / r` from `src` and its gradient at `p`.
+fn green(p: Point3, src: Point3, wavenumber: f64) -> (C64, [C64; 3]) {
+    let d = p - src;
+    let r = d.norm() as f64;
+    let (s, c) = (wavenumber * r).sin_cos();
+    let g = C64::new(c / r, s / r);
+    let n = g.mul(C64::new(-1. / r, wavenumber)).scale(1. / r);
+    (g, [d.x, d.y, d.z].map(|d| n.scale(d as f64)))
+}
+
+struct Expansion {
+    center: Point3,
+    radius: f64,
+    // `-ik (2n + 1) j_n'(ka) / h_n'(ka)` for each order.
+    coefficients: Vec<C64>,
+    // `h_n(k r_s)` of each source for each order.
+    sources: Vec<Option<Vec<C64>>>,
+}
+
+impl Expansion {
+    fn new(scatterer: &Scatterer, sources: &[Source], wavenumber: f64) -> Self {
+        let ka = wavenumber * scatterer.radius as f64;
+        let order = (ka + 4.05 * ka.cbrt()).ceil() as usize + 10;
+        let (j, y) = spherical_bessel(order, ka);
+        let (dj, dy) = (derivative(&j, ka), derivative(&y, ka));
+        let coefficients = (0..=order)
+            .map(|n| {
+                C64::new(dj[n], 0.)
+                    .div(C64::new(dj[n], dy[n]))
+                    .mul(C64::new(0., -wavenumber * (2 * n + 1) as f64))
+            })
+            .collect();
+        let sources = sources
+            .iter()
+            .map(|src| {
+                let r = (src.pos - scatterer.center).norm() as f64;
+                (r >= scatterer.radius as f64).then(|| spherical_hankel(order, wavenumber * r).0)
+            })
+            .collect();
+        Self {
+            center: scatterer.center,
+            radius: scatterer.radius as f64,
+            coefficients,
+            sources,
+        }
+    }
+
+    fn is_inside(&self, p: Point3) -> bool {
+        ((p - self.center).norm() as f64) < self.radius
+    }
+
+    // Adds the scattered wave of each source and its gradient at `p` outside the sphere.
+    fn scatter(
+        &self,
+        p: Point3,
+        sources: &[Source],
+        wavenumber: f64,
+        pressure: &mut [C64],
+        gradient: &mut [[C64; 3]],
+    ) {
+        let order = self.coefficients.len() - 1;
+        let d = p - self.center;
+        let r = d.norm() as f64;
+        let r_hat = [d.x, d.y, d.z].map(|v| v as f64 / r);
+        let (h, dh) = spherical_hankel(order, wavenumber * r);
+
+        sources
+            .iter()
+            .zip(self.sources.iter())
+            .enumerate()
+            .for_each(|(i, (src, h_src))| {
+                let Some(h_src) = h_src else {
+                    return;
+                };
+                let ds = src.pos - self.center;
+                let rs = ds.norm() as f64;
+                let s_hat = [ds.x, ds.y, ds.z].map(|v| v as f64 / rs);
+                let cos = (0..3)
+                    .map(|c| r_hat[c] * s_hat[c])
+                    .sum::<f64>()
+                    .clamp(-1., 1.);
+                let (pn, dpn) = legendre(order, cos);
+
+                let (mut radial, mut tangential) = (C64::ZERO, C64::ZERO);
+                (0..=order).for_each(|n| {
+                    let a = self.coefficients[n].mul(h_src[n]);
+                    pressure[i] = pressure[i].add(a.mul(h[n]).scale(pn[n]));
+                    radial = radial.add(a.mul(dh[n]).scale(wavenumber * pn[n]));
+                    tangential = tangential.add(a.mul(h[n]).scale(dpn[n] / r));
+                });
+                if !gradient.is_empty() {
+                    (0..3).for_each(|c| {
+                        gradient[i][c] = gradient[i][c]
+                            .add(radial.scale(r_hat[c]))
+                            .add(tangential.scale(s_hat[c] - cos * r_hat[c]));
+                    });
+                }
+            });
+    }
+}
+
+// The scattered pressure and its gradient (empty if `gradient` is false) at each target for a unit source at each source position.
+pub(crate) type Scattered = (Vec<Vec<Complex>>, Vec<Vec<[Complex; 3]>>);
+
+// Calculates the scattered field at each target for each source with the unit amplitude and zero phase.
+// The scattered field at the targets inside the scatterers cancels the direct wave.
+pub(crate) fn scattered_field(
+    scatterers: &[Scatterer],
+    sources: &[Source],
+    targets: &[Point3],
+    wavenumber: f32,
+    gradient: bool,
+) -> Scattered {
+    if scatterers.is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let wavenumber = wavenumber as f64;
+    let expansions = scatterers
+        .iter()
+        .map(|scatterer| Expansion::new(scatterer, sources, wavenumber))
+        .collect::<Vec<_>>();
+
+    let field = |&p: &Point3| {
+        let mut pressure = vec![C64::ZERO; sources.len()];
+        let mut grad = if gradient {
+            vec![[C64::ZERO; 3]; sources.len()]
+        } else {
+            Vec::new()
+        };
+        if expansions.iter().any(|e| e.is_inside(p)) {
+            sources.iter().enumerate().for_each(|(i, src)| {
+                let (g, dg) = green(p, src.pos, wavenumber);
+                pressure[i] = g.scale(-1.);
+                if gradient {
+                    grad[i] = dg.map(|v| v.scale(-1.));
+                }
+            });
+        } else {
+            expansions
+                .iter()
+                .for_each(|e| e.scatter(p, sources, wavenumber, &mut pressure, &mut grad));
+        }
+        (
+            pressure.into_iter().map(C64::to_f32).collect::<Vec<_>>(),
+            grad.into_iter()
+                .map(|g| g.map(C64::to_f32))
+                .collect::<Vec<_>>(),
+        )
+    };
+
+    #[cfg(feature = "parallel")]
+    let field = targets.par_iter().map(field).unzip();
+    #[cfg(not(feature = "parallel"))]
+    let field = targets.iter().map(field).unzip();
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use autd3::driver::geometry::Vector3;
+
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(0.1)]
+    #[case(1.)]
+    #[case(5.)]
+    #[case(30.)]
+    fn spherical_bessel_closed_form(#[case] x: f64) {
+        let (j, y) = spherical_bessel(4, x);
+        let (s, c) = x.sin_cos();
+        let j2 = (3. / (x * x) - 1.) * s / x - 3. * c / (x * x);
+        let y2 = -(3. / (x * x) - 1.) * c / x - 3. * s / (x * x);
+        approx::assert_relative_eq!(s / x, j[0], max_relative = 1e-9);
+        approx::assert_relative_eq!(j2, j[2], max_relative = 1e-6);
+        approx::assert_relative_eq!(y2, y[2], max_relative = 1e-9);
+    }
+
+    #[test]
+    fn wronskian() {
+        // j_n(x) y_n'(x) - j_n'(x) y_n(x) = 1 / x²
+        let x = 3.;
+        let (j, y) = spherical_bessel(20, x);
+        let (dj, dy) = (derivative(&j, x), derivative(&y, x));
+        (0..=20).for_each(|n| {
+            approx::assert_relative_eq!(
+                1. / (x * x),
+                j[n] * dy[n] - dj[n] * y[n],
+                max_relative = 1e-8
+            );
+        });
+    }
+
+    #[test]
+    fn legendre_closed_form() {
+        let x = 0.3;
+        let (p, dp) = legendre(3, x);
+        approx::assert_abs_diff_eq!(0.5 * (3. * x * x - 1.), p[2], epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(0.5 * (5. * x * x * x - 3. * x), p[3], epsilon = 1e-12);
+        approx::assert_abs_diff_eq!(0.5 * (15. * x * x - 3.), dp[3], epsilon = 1e-12);
+    }
+
+    fn source(x: f32, y: f32, z: f32) -> Source {
+        Source {
+            pos: Point3::new(x, y, z),
+            coef: 1.,
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(1.)]
+    #[case(5.)]
+    #[case(20.)]
+    fn rigid_boundary(#[case] radius: f32) {
+        let wavenumber = 2. * std::f32::consts::PI / 8.5;
+        let scatterer = Scatterer {
+            center: Point3::new(1., 2., 100.),
+            radius,
+        };
+        let sources = [source(0., 0., 0.), source(30., -20., 10.)];
+        let targets = [
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 0., -1.),
+            Vector3::new(0.6, 0., 0.8),
+        ]
+        .map(|n| scatterer.center + n * radius);
+        let (_, grad) = scattered_field(&[scatterer], &sources, &targets, wavenumber, true);
+
+        targets.iter().enumerate().for_each(|(t, &p)| {
+            let n = (p - scatterer.center) / radius;
+            sources.iter().enumerate().for_each(|(i, src)| {
+                let (_, dg) = green(p, src.pos, wavenumber as f64);
+                let normal = |g: [C64; 3]| {
+                    g[0].scale(n.x as f64)
+                        .add(g[1].scale(n.y as f64))
+                        .add(g[2].scale(n.z as f64))
+                };
+                let scattered = grad[t][i].map(|g| C64::new(g.re as f64, g.im as f64));
+                let total = normal(dg).add(normal(scattered));
+                let norm = |c: C64| c.re * c.re + c.im * c.im;
+                let scale = dg.iter().map(|&c| norm(c)).sum::<f64>().sqrt();
+                assert!(norm(total).sqrt() < 1e-3 * scale);
+            });
+        });
+    }
+
+    #[test]
+    fn reciprocity() {
+        let wavenumber = 2. * std::f32::consts::PI / 8.5;
+        let scatterer = Scatterer {
+            center: Point3::new(0., 0., 50.),
+            radius: 8.,
+        };
+        let a = Point3::new(10., -5., 0.);
+        let b = Point3::new(-20., 30., 80.);
+        let (ab, _) = scattered_field(
+            &[scatterer],
+            &[source(a.x, a.y, a.z)],
+            &[b],
+            wavenumber,
+            false,
+        );
+        let (ba, _) = scattered_field(
+            &[scatterer],
+            &[source(b.x, b.y, b.z)],
+            &[a],
+            wavenumber,
+            false,
+        );
+        approx::assert_relative_eq!(ab[0][0].re, ba[0][0].re, max_relative = 1e-4);
+        approx::assert_relative_eq!(ab[0][0].im, ba[0][0].im, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn inside() {
+        let wavenumber = 2. * std::f32::consts::PI / 8.5;
+        let scatterer = Scatterer {
+            center: Point3::new(0., 0., 50.),
+            radius: 8.,
+        };
+        let p = Point3::new(1., 0., 52.);
+        let (field, grad) =
+            scattered_field(&[scatterer], &[source(0., 0., 0.)], &[p], wavenumber, true);
+        let (g, dg) = green(p, Point3::origin(), wavenumber as f64);
+        approx::assert_relative_eq!(-g.re as f32, field[0][0].re, max_relative = 1e-5);
+        approx::assert_relative_eq!(-g.im as f32, field[0][0].im, max_relative = 1e-5);
+        approx::assert_relative_eq!(-dg[2].re as f32, grad[0][0][2].re, max_relative = 1e-5);
+    }
+
+    #[test]
+    fn empty() {
+        let (field, grad) =
+            scattered_field(&[], &[source(0., 0., 0.)], &[Point3::origin()], 1., true);
+        assert!(field.is_empty());
+        assert!(grad.is_empty());
+    }
+}
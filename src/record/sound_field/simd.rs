@@ -0,0 +1,702 @@
+use autd3::driver::geometry::Complex;
+
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+use crate::Interpolation;
+
+// The kernels over the transducers are written once over `Vector` and compiled with the intrinsics of each instruction set:
+// SSE2, and AVX2 with FMA which is selected at runtime, on x86_64, and NEON on aarch64.
+// The sine and cosine are approximated by polynomials, and only the samples of the emitted ultrasound are gathered lane by lane.
+const MAX_LANES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Kernel {
+    // Plain iterator chains with the functions of the standard library, which are the reference of the others.
+    Scalar,
+    #[cfg(target_arch = "x86_64")]
+    Sse2,
+    #[cfg(target_arch = "x86_64")]
+    Avx2,
+    #[cfg(target_arch = "aarch64")]
+    Neon,
+}
+
+impl Kernel {
+    pub(crate) fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if std::arch::is_x86_feature_detected!("avx2")
+                && std::arch::is_x86_feature_detected!("fma")
+            {
+                Self::Avx2
+            } else {
+                Self::Sse2
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            Self::Neon
+        }
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        {
+            Self::Scalar
+        }
+    }
+}
+
+// Lanes of `f32` in a register of an instruction set.
+// The methods must be called only on the CPU supporting the instruction set, and they are inlined into the kernels compiled for it.
+trait Vector: Copy {
+    const LANES: usize;
+
+    unsafe fn splat(v: f32) -> Self;
+    // Loads the first `LANES` values.
+    unsafe fn load(v: &[f32]) -> Self;
+    // Stores into the first `LANES` values.
+    unsafe fn store(self, dst: &mut [f32]);
+    unsafe fn add(self, rhs: Self) -> Self;
+    unsafe fn sub(self, rhs: Self) -> Self;
+    unsafe fn mul(self, rhs: Self) -> Self;
+    unsafe fn div(self, rhs: Self) -> Self;
+    // `self * a + b`, which is fused if the instruction set has FMA.
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self;
+    unsafe fn sum(self) -> f32;
+}
+
+// A single lane without intrinsics to test the approximations.
+#[cfg(test)]
+impl Vector for f32 {
+    const LANES: usize = 1;
+
+    unsafe fn splat(v: f32) -> Self {
+        v
+    }
+    unsafe fn load(v: &[f32]) -> Self {
+        v[0]
+    }
+    unsafe fn store(self, dst: &mut [f32]) {
+        dst[0] = self;
+    }
+    unsafe fn add(self, rhs: Self) -> Self {
+        self + rhs
+    }
+    unsafe fn sub(self, rhs: Self) -> Self {
+        self - rhs
+    }
+    unsafe fn mul(self, rhs: Self) -> Self {
+        self * rhs
+    }
+    unsafe fn div(self, rhs: Self) -> Self {
+        self / rhs
+    }
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self {
+        self * a + b
+    }
+    unsafe fn sum(self) -> f32 {
+        self
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Vector for __m128 {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    unsafe fn splat(v: f32) -> Self {
+        // SAFETY: SSE2 is in the baseline of x86_64.
+        unsafe { _mm_set1_ps(v) }
+    }
+    #[inline(always)]
+    unsafe fn load(v: &[f32]) -> Self {
+        assert!(v.len() >= Self::LANES);
+        // SAFETY: `v` has at least `LANES` values.
+        unsafe { _mm_loadu_ps(v.as_ptr()) }
+    }
+    #[inline(always)]
+    unsafe fn store(self, dst: &mut [f32]) {
+        assert!(dst.len() >= Self::LANES);
+        // SAFETY: `dst` has at least `LANES` values.
+        unsafe { _mm_storeu_ps(dst.as_mut_ptr(), self) }
+    }
+    #[inline(always)]
+    unsafe fn add(self, rhs: Self) -> Self {
+        unsafe { _mm_add_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn sub(self, rhs: Self) -> Self {
+        unsafe { _mm_sub_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul(self, rhs: Self) -> Self {
+        unsafe { _mm_mul_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn div(self, rhs: Self) -> Self {
+        unsafe { _mm_div_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self {
+        unsafe { _mm_add_ps(_mm_mul_ps(self, a), b) }
+    }
+    #[inline(always)]
+    unsafe fn sum(self) -> f32 {
+        unsafe {
+            let v = _mm_add_ps(self, _mm_movehl_ps(self, self));
+            _mm_cvtss_f32(_mm_add_ss(v, _mm_shuffle_ps::<0b01>(v, v)))
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+impl Vector for __m256 {
+    const LANES: usize = 8;
+
+    #[inline(always)]
+    unsafe fn splat(v: f32) -> Self {
+        // SAFETY: the caller guarantees that the CPU supports AVX2 and FMA.
+        unsafe { _mm256_set1_ps(v) }
+    }
+    #[inline(always)]
+    unsafe fn load(v: &[f32]) -> Self {
+        assert!(v.len() >= Self::LANES);
+        // SAFETY: `v` has at least `LANES` values.
+        unsafe { _mm256_loadu_ps(v.as_ptr()) }
+    }
+    #[inline(always)]
+    unsafe fn store(self, dst: &mut [f32]) {
+        assert!(dst.len() >= Self::LANES);
+        // SAFETY: `dst` has at least `LANES` values.
+        unsafe { _mm256_storeu_ps(dst.as_mut_ptr(), self) }
+    }
+    #[inline(always)]
+    unsafe fn add(self, rhs: Self) -> Self {
+        unsafe { _mm256_add_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn sub(self, rhs: Self) -> Self {
+        unsafe { _mm256_sub_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul(self, rhs: Self) -> Self {
+        unsafe { _mm256_mul_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn div(self, rhs: Self) -> Self {
+        unsafe { _mm256_div_ps(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self {
+        unsafe { _mm256_fmadd_ps(self, a, b) }
+    }
+    #[inline(always)]
+    unsafe fn sum(self) -> f32 {
+        unsafe {
+            (_mm256_castps256_ps128(self))
+                .add(_mm256_extractf128_ps::<1>(self))
+                .sum()
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+impl Vector for float32x4_t {
+    const LANES: usize = 4;
+
+    #[inline(always)]
+    unsafe fn splat(v: f32) -> Self {
+        // SAFETY: NEON is in the baseline of aarch64.
+        unsafe { vdupq_n_f32(v) }
+    }
+    #[inline(always)]
+    unsafe fn load(v: &[f32]) -> Self {
+        assert!(v.len() >= Self::LANES);
+        // SAFETY: `v` has at least `LANES` values.
+        unsafe { vld1q_f32(v.as_ptr()) }
+    }
+    #[inline(always)]
+    unsafe fn store(self, dst: &mut [f32]) {
+        assert!(dst.len() >= Self::LANES);
+        // SAFETY: `dst` has at least `LANES` values.
+        unsafe { vst1q_f32(dst.as_mut_ptr(), self) }
+    }
+    #[inline(always)]
+    unsafe fn add(self, rhs: Self) -> Self {
+        unsafe { vaddq_f32(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn sub(self, rhs: Self) -> Self {
+        unsafe { vsubq_f32(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul(self, rhs: Self) -> Self {
+        unsafe { vmulq_f32(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn div(self, rhs: Self) -> Self {
+        unsafe { vdivq_f32(self, rhs) }
+    }
+    #[inline(always)]
+    unsafe fn mul_add(self, a: Self, b: Self) -> Self {
+        unsafe { vfmaq_f32(b, self, a) }
+    }
+    #[inline(always)]
+    unsafe fn sum(self) -> f32 {
+        unsafe { vaddvq_f32(self) }
+    }
+}
+
+// Cody-Waite reduction by π/2 with the constants of few significant bits, so that `n * DP1` and `n * DP2` are exact.
+const DP1: f32 = 1.5703125;
+const DP2: f32 = 4.837_513e-4;
+const DP3: f32 = 7.549_79e-8;
+// Adding and subtracting 1.5 × 2^23 rounds to the nearest integer for |x| < 2^22.
+const ROUND: f32 = 12_582_912.;
+
+#[inline(always)]
+unsafe fn round<V: Vector>(x: V) -> V {
+    unsafe { x.add(V::splat(ROUND)).sub(V::splat(ROUND)) }
+}
+
+// Approximates `(sin(x), cos(x))` by the minimax polynomials on [-π/4, π/4] after the range reduction.
+// The absolute error is about 1e-7 for |x| < 1e4.
+#[inline(always)]
+unsafe fn sin_cos<V: Vector>(x: V) -> (V, V) {
+    unsafe {
+        let splat = V::splat;
+        let n = round(x.mul(splat(std::f32::consts::FRAC_2_PI)));
+        let r = x
+            .sub(n.mul(splat(DP1)))
+            .sub(n.mul(splat(DP2)))
+            .sub(n.mul(splat(DP3)));
+        let r2 = r.mul(r);
+        let s = r2.mul_add(splat(-1.951_529_6e-4), splat(8.332_161e-3));
+        let s = r2.mul_add(s, splat(-1.666_665_5e-1));
+        let s = r.mul(r2).mul_add(s, r);
+        let c = r2.mul_add(splat(2.443_315_7e-5), splat(-1.388_731_6e-3));
+        let c = r2.mul_add(c, splat(4.166_664_6e-2));
+        let c = r2.mul(r2).mul_add(c, r2.mul_add(splat(-0.5), splat(1.)));
+
+        // The quadrant `m = n mod 4` is decomposed into `m = 2 high + odd` with the exact arithmetic of the small integers.
+        let m = n.sub(splat(4.).mul(round(n.mul_add(splat(0.25), splat(-0.375)))));
+        let high = round(m.mul_add(splat(0.5), splat(-0.25)));
+        let odd = m.sub(splat(2.).mul(high));
+        let even = splat(1.).sub(odd);
+        let (s, c) = (s.mul(even).add(c.mul(odd)), c.mul(even).add(s.mul(odd)));
+        // The sine is negative in the quadrants 2 and 3, and the cosine is in 1 and 2.
+        let neg_s = high;
+        let neg_c = odd.add(high).sub(splat(2.).mul(odd.mul(high)));
+        (
+            s.mul(splat(-2.).mul_add(neg_s, splat(1.))),
+            c.mul(splat(-2.).mul_add(neg_c, splat(1.))),
+        )
+    }
+}
+
+#[inline(always)]
+fn chunk(v: &[f32], lanes: usize, i: usize) -> &[f32] {
+    &v[i * lanes..][..lanes]
+}
+
+// Returns `Σ a exp(i(k d + φ)) / d` over the sources with the distance `d`, amplitude `a` and phase `φ`.
+pub(crate) fn rms(
+    kernel: Kernel,
+    dists: &[f32],
+    amp: &[f32],
+    phase: &[f32],
+    wavenumber: f32,
+) -> Complex {
+    match kernel {
+        Kernel::Scalar => dists
+            .iter()
+            .zip(amp.iter())
+            .zip(phase.iter())
+            .map(|((dist, a), p)| {
+                let r = a / dist;
+                let theta = wavenumber * dist + p;
+                Complex::new(r * theta.cos(), r * theta.sin())
+            })
+            .sum(),
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: SSE2 is in the baseline of x86_64.
+        Kernel::Sse2 => unsafe { rms_lanes::<__m128>(dists, amp, phase, wavenumber) },
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Kernel::Avx2` is selected only if the CPU supports AVX2 and FMA.
+        Kernel::Avx2 => unsafe { rms_avx2(dists, amp, phase, wavenumber) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: NEON is in the baseline of aarch64.
+        Kernel::Neon => unsafe { rms_lanes::<float32x4_t>(dists, amp, phase, wavenumber) },
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+fn rms_avx2(dists: &[f32], amp: &[f32], phase: &[f32], wavenumber: f32) -> Complex {
+    // SAFETY: this function is compiled with AVX2 and FMA.
+    unsafe { rms_lanes::<__m256>(dists, amp, phase, wavenumber) }
+}
+
+#[inline(always)]
+unsafe fn rms_lanes<V: Vector>(
+    dists: &[f32],
+    amp: &[f32],
+    phase: &[f32],
+    wavenumber: f32,
+) -> Complex {
+    let chunks = dists.len() / V::LANES;
+    let (re, im) = unsafe {
+        let k = V::splat(wavenumber);
+        let (mut re, mut im) = (V::splat(0.), V::splat(0.));
+        for i in 0..chunks {
+            let d = V::load(chunk(dists, V::LANES, i));
+            let a = V::load(chunk(amp, V::LANES, i));
+            let p = V::load(chunk(phase, V::LANES, i));
+            let r = a.div(d);
+            // The phase is not fused so that it is rounded in the same way as `Kernel::Scalar` and the other paths.
+            let (s, c) = sin_cos(k.mul(d).add(p));
+            re = r.mul_add(c, re);
+            im = r.mul_add(s, im);
+        }
+        (re.sum(), im.sum())
+    };
+    let rem = chunks * V::LANES;
+    let rem = rms(
+        Kernel::Scalar,
+        &dists[rem..],
+        &amp[rem..],
+        &phase[rem..],
+        wavenumber,
+    );
+    Complex::new(re + rem.re, im + rem.im)
+}
+
+// The samples of the emitted ultrasound of each source and how to look them up at the retarded time.
+pub(crate) struct Retarded<'a> {
+    pub(crate) samples: &'a [&'a [f32]],
+    pub(crate) coef: &'a [f32],
+    pub(crate) sound_speed: f32,
+    pub(crate) sampling_period: f32,
+    // The index of the first sample.
+    pub(crate) offset: isize,
+    pub(crate) interpolation: Interpolation,
+}
+
+impl Retarded<'_> {
+    #[inline(always)]
+    fn sample(&self, i: usize, t: f32, dist: f32) -> f32 {
+        let a = (t - dist / self.sound_speed) / self.sampling_period;
+        let idx = a.floor();
+        let alpha = a - idx;
+        let idx = (idx as isize - self.offset) as usize;
+        self.interpolation.interpolate(self.samples[i], idx, alpha)
+    }
+}
+
+// Returns `Σ c s(t - d / c0) / d` over the sources with the distance `d` and coefficient `c`, where the samples `s` are interpolated.
+// The windowed sinc interpolation is always computed by `Kernel::Scalar`.
+pub(crate) fn instant(kernel: Kernel, dists: &[f32], retarded: &Retarded, t: f32) -> f32 {
+    match kernel {
+        Kernel::Scalar => dists
+            .iter()
+            .enumerate()
+            .map(|(i, &dist)| retarded.coef[i] * retarded.sample(i, t, dist) / dist)
+            .sum(),
+        #[cfg_attr(
+            not(any(target_arch = "x86_64", target_arch = "aarch64")),
+            allow(unreachable_patterns)
+        )]
+        _ if retarded.interpolation == Interpolation::WindowedSinc => {
+            instant(Kernel::Scalar, dists, retarded, t)
+        }
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: SSE2 is in the baseline of x86_64.
+        Kernel::Sse2 => unsafe { instant_lanes::<__m128>(dists, retarded, t) },
+        #[cfg(target_arch = "x86_64")]
+        // SAFETY: `Kernel::Avx2` is selected only if the CPU supports AVX2 and FMA.
+        Kernel::Avx2 => unsafe { instant_avx2(dists, retarded, t) },
+        #[cfg(target_arch = "aarch64")]
+        // SAFETY: NEON is in the baseline of aarch64.
+        Kernel::Neon => unsafe { instant_lanes::<float32x4_t>(dists, retarded, t) },
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2,fma")]
+fn instant_avx2(dists: &[f32], retarded: &Retarded, t: f32) -> f32 {
+    // SAFETY: this function is compiled with AVX2 and FMA.
+    unsafe { instant_lanes::<__m256>(dists, retarded, t) }
+}
+
+#[inline(always)]
+unsafe fn instant_lanes<V: Vector>(dists: &[f32], retarded: &Retarded, t: f32) -> f32 {
+    // The offsets of the gathered samples from the one before the retarded time.
+    // The nearest sample is selected when it is gathered.
+    let (first, taps) = match retarded.interpolation {
+        Interpolation::Nearest => (0, 1),
+        Interpolation::Linear => (0, 2),
+        _ => (-1, 4),
+    };
+    let chunks = dists.len() / V::LANES;
+    let acc = unsafe {
+        let splat = V::splat;
+        let mut acc = splat(0.);
+        for i in 0..chunks {
+            let d = V::load(chunk(dists, V::LANES, i));
+            let coef = V::load(chunk(retarded.coef, V::LANES, i));
+            let a = splat(t)
+                .sub(d.div(splat(retarded.sound_speed)))
+                .div(splat(retarded.sampling_period));
+            let mut buf = [0.; MAX_LANES];
+            a.store(&mut buf);
+            let mut idx = [0.; MAX_LANES];
+            let mut v = [[0.; MAX_LANES]; 4];
+            for l in 0..V::LANES {
+                // Floor by truncation, which is corrected for the negative values.
+                // The index is in `isize` since it exceeds the range of `i32` in a long recording.
+                let trunc = buf[l] as isize;
+                let floor = trunc - (buf[l] < trunc as f32) as isize;
+                idx[l] = floor as f32;
+                let mut j = floor - retarded.offset + first;
+                if retarded.interpolation == Interpolation::Nearest && buf[l] - idx[l] >= 0.5 {
+                    j += 1;
+                }
+                let s = retarded.samples[i * V::LANES + l];
+                (0..taps).for_each(|k| v[k][l] = s[j as usize + k]);
+            }
+            let alpha = a.sub(V::load(&idx));
+            let v = v.map(|v| V::load(&v));
+            let s = match retarded.interpolation {
+                Interpolation::Nearest => v[0],
+                Interpolation::Linear => v[0].mul(splat(1.).sub(alpha)).add(v[1].mul(alpha)),
+                _ => {
+                    // The weights of the cubic convolution at the distances `1 + α`, `α`, `1 - α` and `2 - α`.
+                    let outer = |x: V| {
+                        let w = x.mul_add(splat(-0.5), splat(2.5));
+                        let w = w.mul_add(x, splat(-4.));
+                        w.mul_add(x, splat(2.))
+                    };
+                    let inner = |x: V| {
+                        let w = x.mul_add(splat(1.5), splat(-2.5));
+                        w.mul(x).mul_add(x, splat(1.))
+                    };
+                    let w = [
+                        outer(splat(1.).add(alpha)),
+                        inner(alpha),
+                        inner(splat(1.).sub(alpha)),
+                        outer(splat(2.).sub(alpha)),
+                    ];
+                    let sum = (0..4).fold(splat(0.), |acc, k| v[k].mul_add(w[k], acc));
+                    sum.div(w[0].add(w[1]).add(w[2]).add(w[3]))
+                }
+            };
+            acc = coef.mul(s).div(d).add(acc);
+        }
+        acc.sum()
+    };
+    acc + (chunks * V::LANES..dists.len())
+        .map(|i| retarded.coef[i] * retarded.sample(i, t, dists[i]) / dists[i])
+        .sum::<f32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use rand::RngExt;
+
+    use super::*;
+
+    fn kernels() -> Vec<Kernel> {
+        #[allow(unused_mut)]
+        let mut kernels = Vec::new();
+        #[cfg(target_arch = "x86_64")]
+        {
+            kernels.push(Kernel::Sse2);
+            if Kernel::detect() == Kernel::Avx2 {
+                kernels.push(Kernel::Avx2);
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        kernels.push(Kernel::Neon);
+        kernels
+    }
+
+    #[test]
+    fn sin_cos_accuracy() {
+        // SAFETY: `f32` requires no instruction set.
+        let sin_cos = |x: f32| unsafe { sin_cos(x) };
+        let mut rng = rand::rng();
+        (0..10000).for_each(|_| {
+            let x = rng.random_range(-1e4..1e4f32);
+            let (s, c) = sin_cos(x);
+            approx::assert_abs_diff_eq!((x as f64).sin() as f32, s, epsilon = 1e-6);
+            approx::assert_abs_diff_eq!((x as f64).cos() as f32, c, epsilon = 1e-6);
+        });
+        [0., std::f32::consts::FRAC_PI_4, std::f32::consts::PI]
+            .iter()
+            .for_each(|&x| {
+                let (s, c) = sin_cos(x);
+                approx::assert_abs_diff_eq!(x.sin(), s, epsilon = 1e-7);
+                approx::assert_abs_diff_eq!(x.cos(), c, epsilon = 1e-7);
+            });
+    }
+
+    fn rms_input(n: usize) -> (Vec<f32>, Vec<f32>, Vec<f32>) {
+        let mut rng = rand::rng();
+        let dists = (0..n)
+            .map(|_| rng.random_range(10.0..1000.0f32))
+            .collect::<Vec<_>>();
+        let amp = (0..n)
+            .map(|_| rng.random_range(0.0..10.0f32))
+            .collect::<Vec<_>>();
+        let phase = (0..n)
+            .map(|_| rng.random_range(0.0..std::f32::consts::TAU))
+            .collect::<Vec<_>>();
+        (dists, amp, phase)
+    }
+
+    #[rstest::rstest]
+    #[case(1)]
+    #[case(8)]
+    #[case(249)]
+    #[case(2000)]
+    fn rms_matches_scalar(#[case] n: usize) {
+        let (dists, amp, phase) = rms_input(n);
+        let wavenumber = std::f32::consts::TAU / 8.5;
+        let expect = rms(Kernel::Scalar, &dists, &amp, &phase, wavenumber);
+        let scale = amp
+            .iter()
+            .zip(dists.iter())
+            .map(|(a, d)| a / d)
+            .sum::<f32>();
+        kernels().into_iter().for_each(|kernel| {
+            let v = rms(kernel, &dists, &amp, &phase, wavenumber);
+            approx::assert_abs_diff_eq!(expect.re, v.re, epsilon = 1e-5 * scale);
+            approx::assert_abs_diff_eq!(expect.im, v.im, epsilon = 1e-5 * scale);
+        });
+    }
+
+    struct InstantInput {
+        samples: Vec<Vec<f32>>,
+        coef: Vec<f32>,
+        dists: Vec<f32>,
+        offset: isize,
+    }
+
+    const SAMPLING_PERIOD: f32 = 25e-6 / 512.;
+
+    fn instant_input(n: usize, t: f32) -> InstantInput {
+        let mut rng = rand::rng();
+        InstantInput {
+            samples: (0..n)
+                .map(|_| {
+                    (0..16384)
+                        .map(|_| rng.random_range(-1.0..1.0f32))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
+            coef: (0..n).map(|_| rng.random_range(0.0..1.0f32)).collect(),
+            dists: (0..n).map(|_| rng.random_range(10.0..100.0f32)).collect(),
+            offset: (t / SAMPLING_PERIOD) as isize - 12000,
+        }
+    }
+
+    #[rstest::rstest]
+    #[case(1, 300e-6)]
+    #[case(8, 300e-6)]
+    #[case(249, 300e-6)]
+    // The indices of the samples exceed the range of i32.
+    #[case(8, 150.)]
+    fn instant_matches_scalar(
+        #[case] n: usize,
+        #[case] t: f32,
+        #[values(
+            Interpolation::Nearest,
+            Interpolation::Linear,
+            Interpolation::Cubic,
+            Interpolation::WindowedSinc
+        )]
+        interpolation: Interpolation,
+    ) {
+        let input = instant_input(n, t);
+        let samples = input.samples.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let retarded = Retarded {
+            samples: &samples,
+            coef: &input.coef,
+            sound_speed: 340e3,
+            sampling_period: SAMPLING_PERIOD,
+            offset: input.offset,
+            interpolation,
+        };
+        let expect = instant(Kernel::Scalar, &input.dists, &retarded, t);
+        kernels().into_iter().for_each(|kernel| {
+            approx::assert_abs_diff_eq!(
+                expect,
+                instant(kernel, &input.dists, &retarded, t),
+                epsilon = 1e-5
+            );
+        });
+    }
+
+    fn elapsed(f: impl Fn()) -> Duration {
+        f();
+        let start = Instant::now();
+        (0..1000).for_each(|_| f());
+        start.elapsed()
+    }
+
+    // Run with `cargo test --release -- --ignored --nocapture` to measure the kernels against `Kernel::Scalar`.
+    #[test]
+    #[ignore]
+    fn kernels_outperform_scalar() {
+        let n = 2000;
+        let (dists, amp, phase) = rms_input(n);
+        let rms_elapsed = |kernel| {
+            elapsed(|| {
+                std::hint::black_box(rms(kernel, &dists, &amp, &phase, 0.74));
+            })
+        };
+
+        let input = instant_input(n, 300e-6);
+        let samples = input.samples.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let instant_elapsed = |kernel, interpolation| {
+            let retarded = Retarded {
+                samples: &samples,
+                coef: &input.coef,
+                sound_speed: 340e3,
+                sampling_period: SAMPLING_PERIOD,
+                offset: input.offset,
+                interpolation,
+            };
+            elapsed(|| {
+                std::hint::black_box(instant(kernel, &input.dists, &retarded, 300e-6));
+            })
+        };
+
+        let scalar = rms_elapsed(Kernel::Scalar);
+        kernels().into_iter().for_each(|kernel| {
+            let v = rms_elapsed(kernel);
+            println!("rms: {:?} {:?} (Scalar {:?})", kernel, v, scalar);
+            assert!(v < scalar);
+        });
+        [
+            Interpolation::Nearest,
+            Interpolation::Linear,
+            Interpolation::Cubic,
+        ]
+        .into_iter()
+        .for_each(|interpolation| {
+            let scalar = instant_elapsed(Kernel::Scalar, interpolation);
+            kernels().into_iter().for_each(|kernel| {
+                let v = instant_elapsed(kernel, interpolation);
+                println!(
+                    "instant ({:?}): {:?} {:?} (Scalar {:?})",
+                    interpolation, kernel, v, scalar
+                );
+                assert!(v < scalar);
+            });
+        });
+    }
+}
@@ -0,0 +1,338 @@
+use crate::EmulatorError;
+
+/// Statistic of the sound field over time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Statistic {
+    /// Maximum value.
+    Max,
+    /// Minimum value.
+    Min,
+    /// Arithmetic mean.
+    Mean,
+    /// Root mean square.
+    Rms,
+    /// Percentile in \[0, 100\]. The value between the closest ranks is linearly interpolated.
+    ///
+    /// The samples of each point are kept while they fit in the memory limits hint of the options, and the percentile is estimated by the P² algorithm beyond it.
+    Percentile(f32),
+}
+
+impl std::fmt::Display for Statistic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Statistic::Max => write!(f, "max"),
+            Statistic::Min => write!(f, "min"),
+            Statistic::Mean => write!(f, "mean"),
+            Statistic::Rms => write!(f, "rms"),
+            Statistic::Percentile(q) => write!(f, "percentile{}", q),
+        }
+    }
+}
+
+// The P² estimator of a quantile, which tracks five markers instead of storing the samples (Jain and Chlamtac, 1985).
+#[derive(Debug, Clone)]
+struct P2 {
+    // The heights, the positions and the desired positions of the markers.
+    q: [f64; 5],
+    n: [f64; 5],
+    np: [f64; 5],
+    // The increments of the desired positions for each sample.
+    dn: [f64; 5],
+}
+
+impl P2 {
+    // Places the markers at the quantiles of the sorted samples, whose number must be at least 5.
+    fn new(p: f64, sorted: &[f32]) -> Self {
+        let last = (sorted.len() - 1) as f64;
+        let dn = [0., p / 2., p, (1. + p) / 2., 1.];
+        let np = dn.map(|dn| dn * last);
+        // The positions must be strictly increasing.
+        let mut n = np.map(f64::round);
+        (1..4).for_each(|i| n[i] = n[i].clamp(n[i - 1] + 1., last - (4 - i) as f64));
+        Self {
+            q: n.map(|n| sorted[n as usize] as f64),
+            n,
+            np,
+            dn,
+        }
+    }
+
+    fn push(&mut self, x: f32) {
+        let x = x as f64;
+        let (q, n) = (&mut self.q, &mut self.n);
+        let k = if x < q[0] {
+            q[0] = x;
+            0
+        } else if x >= q[4] {
+            q[4] = x;
+            3
+        } else {
+            (1..5).find(|&i| x < q[i]).unwrap() - 1
+        };
+        n[k + 1..].iter_mut().for_each(|n| *n += 1.);
+        self.np
+            .iter_mut()
+            .zip(self.dn.iter())
+            .for_each(|(np, dn)| *np += dn);
+
+        (1..4).for_each(|i| {
+            let d = self.np[i] - n[i];
+            if (d >= 1. && n[i + 1] - n[i] > 1.) || (d <= -1. && n[i - 1] - n[i] < -1.) {
+                let d = d.signum();
+                // The piecewise-parabolic prediction, and the linear one if it breaks the order of the heights.
+                let parabolic = q[i]
+                    + d / (n[i + 1] - n[i - 1])
+                        * ((n[i] - n[i - 1] + d) * (q[i + 1] - q[i]) / (n[i + 1] - n[i])
+                            + (n[i + 1] - n[i] - d) * (q[i] - q[i - 1]) / (n[i] - n[i - 1]));
+                q[i] = if q[i - 1] < parabolic && parabolic < q[i + 1] {
+                    parabolic
+                } else {
+                    let j = (i as f64 + d) as usize;
+                    q[i] + d * (q[j] - q[i]) / (n[j] - n[i])
+                };
+                n[i] += d;
+            }
+        });
+    }
+
+    fn value(&self) -> f32 {
+        self.q[2] as f32
+    }
+}
+
+// Accumulates the statistics of each point in one pass.
+// The samples are kept only if a percentile is requested, and are replaced by the P² estimators once they exceed the memory limits.
+#[derive(Debug)]
+pub(crate) struct Aggregator {
+    statistics: Vec<Statistic>,
+    count: usize,
+    max: Vec<f32>,
+    min: Vec<f32>,
+    sum: Vec<f64>,
+    sum_sq: Vec<f64>,
+    samples: Vec<Vec<f32>>,
+    // The maximum number of the samples kept for each point.
+    capacity: usize,
+    // The estimators of each point for each requested percentile.
+    estimators: Vec<(f32, Vec<P2>)>,
+}
+
+impl Aggregator {
+    // `memory_limits` is the number of bytes available for the samples.
+    pub(crate) fn new(
+        statistics: &[Statistic],
+        num_points: usize,
+        memory_limits: usize,
+    ) -> Result<Self, EmulatorError> {
+        if let Some(&Statistic::Percentile(q)) = statistics
+            .iter()
+            .find(|s| matches!(s, Statistic::Percentile(q) if !(0.0..=100.0).contains(q)))
+        {
+            return Err(EmulatorError::InvalidPercentile(q));
+        }
+        Ok(Self {
+            statistics: statistics.to_vec(),
+            count: 0,
+            max: vec![f32::NEG_INFINITY; num_points],
+            min: vec![f32::INFINITY; num_points],
+            sum: vec![0.; num_points],
+            sum_sq: vec![0.; num_points],
+            samples: if Self::needs_samples(statistics) {
+                vec![Vec::new(); num_points]
+            } else {
+                Vec::new()
+            },
+            // The estimators are initialized with at least 5 samples.
+            capacity: (memory_limits / (num_points.max(1) * size_of::<f32>())).max(5),
+            estimators: Vec::new(),
+        })
+    }
+
+    // Returns true if the statistics require each sample, i.e., they cannot be merged from the partial sums.
+    pub(crate) fn needs_samples(statistics: &[Statistic]) -> bool {
+        statistics
+            .iter()
+            .any(|s| matches!(s, Statistic::Percentile(_)))
+    }
+
+    pub(crate) fn push(&mut self, v: &[f32]) {
+        self.count += 1;
+        v.iter().enumerate().for_each(|(i, &v)| {
+            self.max[i] = self.max[i].max(v);
+            self.min[i] = self.min[i].min(v);
+            self.sum[i] += v as f64;
+            self.sum_sq[i] += v as f64 * v as f64;
+        });
+        if !self.estimators.is_empty() {
+            self.estimators.iter_mut().for_each(|(_, estimators)| {
+                estimators
+                    .iter_mut()
+                    .zip(v.iter())
+                    .for_each(|(e, &v)| e.push(v))
+            });
+            return;
+        }
+        self.samples
+            .iter_mut()
+            .zip(v.iter())
+            .for_each(|(s, &v)| s.push(v));
+        if !self.samples.is_empty() && self.count > self.capacity {
+            self.samples
+                .iter_mut()
+                .for_each(|s| s.sort_unstable_by(f32::total_cmp));
+            self.estimators = self
+                .statistics
+                .iter()
+                .filter_map(|s| match *s {
+                    Statistic::Percentile(q) => Some((
+                        q,
+                        self.samples
+                            .iter()
+                            .map(|s| P2::new(q as f64 / 100., s))
+                            .collect(),
+                    )),
+                    _ => None,
+                })
+                .collect();
+            self.samples = Vec::new();
+        }
+    }
+
+    // Merges the maximum, the minimum, the sum and the sum of squares of `count` samples of each point reduced elsewhere.
+    #[cfg(feature = "gpu")]
+    pub(crate) fn merge(&mut self, count: usize, reduced: &[[f32; 4]]) {
+        self.count += count;
+        reduced
+            .iter()
+            .enumerate()
+            .for_each(|(i, &[max, min, sum, sum_sq])| {
+                self.max[i] = self.max[i].max(max);
+                self.min[i] = self.min[i].min(min);
+                self.sum[i] += sum as f64;
+                self.sum_sq[i] += sum_sq as f64;
+            });
+    }
+
+    // `v` must yield a destination for each statistic.
+    pub(crate) fn finish(mut self, v: impl Iterator<Item = *mut f32>) {
+        self.samples
+            .iter_mut()
+            .for_each(|s| s.sort_unstable_by(f32::total_cmp));
+        let n = self.count as f64;
+        self.statistics
+            .iter()
+            .zip(v)
+            .for_each(|(statistic, dst)| match *statistic {
+                Statistic::Max => unsafe {
+                    std::ptr::copy_nonoverlapping(self.max.as_ptr(), dst, self.max.len())
+                },
+                Statistic::Min => unsafe {
+                    std::ptr::copy_nonoverlapping(self.min.as_ptr(), dst, self.min.len())
+                },
+                Statistic::Mean => self.sum.iter().enumerate().for_each(|(i, &s)| unsafe {
+                    *dst.add(i) = (s / n) as f32;
+                }),
+                Statistic::Rms => self.sum_sq.iter().enumerate().for_each(|(i, &s)| unsafe {
+                    *dst.add(i) = (s / n).sqrt() as f32;
+                }),
+                // The extremes are exact even if estimated.
+                Statistic::Percentile(q) if !self.estimators.is_empty() && q == 0. => unsafe {
+                    std::ptr::copy_nonoverlapping(self.min.as_ptr(), dst, self.min.len())
+                },
+                Statistic::Percentile(q) if !self.estimators.is_empty() && q == 100. => unsafe {
+                    std::ptr::copy_nonoverlapping(self.max.as_ptr(), dst, self.max.len())
+                },
+                Statistic::Percentile(q) if !self.estimators.is_empty() => {
+                    let (_, estimators) = self.estimators.iter().find(|(p, _)| *p == q).unwrap();
+                    estimators
+                        .iter()
+                        .enumerate()
+                        .for_each(|(i, e)| unsafe { *dst.add(i) = e.value() });
+                }
+                Statistic::Percentile(q) => {
+                    self.samples.iter().enumerate().for_each(|(i, s)| {
+                        let v = if s.is_empty() {
+                            f32::NAN
+                        } else {
+                            let pos = q / 100. * (s.len() - 1) as f32;
+                            let lo = pos.floor() as usize;
+                            let hi = pos.ceil() as usize;
+                            s[lo] + (s[hi] - s[lo]) * (pos - lo as f32)
+                        };
+                        unsafe { *dst.add(i) = v };
+                    });
+                }
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(Statistic::Max, 4.)]
+    #[case(Statistic::Min, -2.)]
+    #[case(Statistic::Mean, 1.)]
+    #[case(Statistic::Rms, 5f32.sqrt())]
+    #[case(Statistic::Percentile(0.), -2.)]
+    #[case(Statistic::Percentile(50.), 1.)]
+    #[case(Statistic::Percentile(62.5), 1.5)]
+    #[case(Statistic::Percentile(100.), 4.)]
+    #[test]
+    fn aggregate(#[case] statistic: Statistic, #[case] expect: f32) {
+        let mut aggregator = Aggregator::new(&[statistic], 1, usize::MAX).unwrap();
+        [1., -2., 4., 0., 2.]
+            .iter()
+            .for_each(|v| aggregator.push(&[*v]));
+        let mut v = [0.];
+        aggregator.finish(std::iter::once(v.as_mut_ptr()));
+        approx::assert_relative_eq!(expect, v[0]);
+    }
+
+    #[rstest::rstest]
+    #[case(0.)]
+    #[case(5.)]
+    #[case(50.)]
+    #[case(99.)]
+    #[case(100.)]
+    #[test]
+    fn percentile_estimate(#[case] q: f32) {
+        // 10 samples of 2 points fit in the memory limits.
+        let mut aggregator = Aggregator::new(&[Statistic::Percentile(q)], 2, 80).unwrap();
+        // Uniform in [0, 1) and a sinusoid of the uniform phase.
+        (0..100000).for_each(|k| {
+            let u = (k as f32 * 0.618_034).fract();
+            aggregator.push(&[u, (2. * std::f32::consts::PI * u).sin()]);
+        });
+        assert!(aggregator.samples.is_empty());
+        let mut v = [0.; 2];
+        aggregator.finish(std::iter::once(v.as_mut_ptr()));
+        approx::assert_abs_diff_eq!(q / 100., v[0], epsilon = 1e-2);
+        approx::assert_abs_diff_eq!(
+            (std::f32::consts::PI * (q / 100. - 0.5)).sin(),
+            v[1],
+            epsilon = 1e-2
+        );
+    }
+
+    #[rstest::rstest]
+    #[case(-1.)]
+    #[case(100.5)]
+    #[test]
+    fn invalid_percentile(#[case] q: f32) {
+        assert!(matches!(
+            Aggregator::new(&[Statistic::Mean, Statistic::Percentile(q)], 1, usize::MAX),
+            Err(EmulatorError::InvalidPercentile(_))
+        ));
+    }
+
+    #[rstest::rstest]
+    #[case("max", Statistic::Max)]
+    #[case("percentile99", Statistic::Percentile(99.))]
+    #[case("percentile99.9", Statistic::Percentile(99.9))]
+    #[test]
+    fn display(#[case] expect: &str, #[case] statistic: Statistic) {
+        assert_eq!(expect, statistic.to_string());
+    }
+}
@@ -0,0 +1,39 @@
+@group(0)
+@binding(0)
+var<storage, read> v_src: array<f32>;
+
+// The maximum, the minimum, the sum and the sum of squares of each point followed by the compensations of the sums.
+@group(0)
+@binding(1)
+var<storage, read_write> v_acc: array<vec4<f32>>;
+
+struct Pc {
+    num_points: u32,
+    complex: u32,
+}
+
+var<immediate> pc: Pc;
+
+@compute
+@workgroup_size(64)
+fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
+    let i = global_id.x;
+    if i >= pc.num_points {
+        return;
+    }
+    var v = v_src[i];
+    if pc.complex != 0u {
+        v = length(vec2<f32>(v_src[2u * i], v_src[2u * i + 1u]));
+    }
+    var acc = v_acc[2u * i];
+    let c = v_acc[2u * i + 1u].xy;
+    acc.x = max(acc.x, v);
+    acc.y = min(acc.y, v);
+    // Kahan summation, so that the sums over many times keep the precision.
+    let y = vec2<f32>(v, v * v) - c;
+    let t = acc.zw + y;
+    v_acc[2u * i + 1u] = vec4<f32>((t - acc.zw) - y, 0., 0.);
+    acc.z = t.x;
+    acc.w = t.y;
+    v_acc[2u * i] = acc;
+}
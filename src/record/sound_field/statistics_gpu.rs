@@ -0,0 +1,108 @@
+use bytemuck::NoUninit;
+use wgpu::{Buffer, BufferAddress};
+
+use crate::{EmulatorError, GpuContext, record::sound_field::gpu_context::Pipeline};
+
+use super::statistics::Aggregator;
+
+// GRCOV_EXCL_START
+#[derive(NoUninit, Clone, Copy, Debug)]
+#[repr(C)]
+struct Pc {
+    num_points: u32,
+    complex: u32,
+}
+// GRCOV_EXCL_STOP
+
+// Reduces the sound field computed on GPU into the maximum, the minimum, the sum and the sum of squares of each point, so that it is not read back every time.
+#[derive(Debug)]
+pub(crate) struct StatisticsGpu {
+    context: GpuContext,
+    pipeline: wgpu::ComputePipeline,
+    bind_group: wgpu::BindGroup,
+    buf_storage_acc: Buffer,
+    buf_staging_acc: Buffer,
+    pc: Pc,
+    count: usize,
+}
+
+impl StatisticsGpu {
+    // `src` holds the value of each point, or the complex value whose magnitude is reduced if `complex` is true.
+    pub(crate) fn new(
+        context: &GpuContext,
+        src: &Buffer,
+        num_points: usize,
+        complex: bool,
+    ) -> Result<Self, EmulatorError> {
+        let init = (0..num_points)
+            .flat_map(|_| [[f32::NEG_INFINITY, f32::INFINITY, 0., 0.], [0.; 4]])
+            .collect::<Vec<_>>();
+        let buf_acc_size = (init.len() * size_of::<[f32; 4]>()) as BufferAddress;
+        context.check_binding_size(buf_acc_size)?;
+
+        let (buf_storage_acc, buf_staging_acc) = context.output_buffers(buf_acc_size);
+        context
+            .queue()
+            .write_buffer(&buf_storage_acc, 0, bytemuck::cast_slice(&init));
+
+        let Pipeline {
+            bind_group_layout,
+            pipeline,
+        } = context.compute_pipeline(
+            module_path!(),
+            include_str!("statistics.wgsl"),
+            &[true, false],
+            size_of::<Pc>() as _,
+        );
+        let bind_group = context.bind_group(&bind_group_layout, &[src, &buf_storage_acc]);
+
+        Ok(Self {
+            context: context.clone(),
+            pipeline,
+            bind_group,
+            buf_storage_acc,
+            buf_staging_acc,
+            pc: Pc {
+                num_points: num_points as _,
+                complex: complex as _,
+            },
+            count: 0,
+        })
+    }
+
+    // Accumulates the values in the source buffer after the work submitted so far.
+    pub(crate) fn accumulate(&mut self) {
+        self.context.dispatch(
+            &self.pipeline,
+            &self.bind_group,
+            bytemuck::bytes_of(&self.pc),
+            self.pc.num_points as _,
+            &[],
+        );
+        self.count += 1;
+    }
+
+    // Reads back the statistics and merges them into `aggregator`.
+    pub(crate) fn finish(self, aggregator: &mut Aggregator) -> Result<(), EmulatorError> {
+        let mut encoder = self
+            .context
+            .device()
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+        encoder.copy_buffer_to_buffer(
+            &self.buf_storage_acc,
+            0,
+            &self.buf_staging_acc,
+            0,
+            self.buf_staging_acc.size(),
+        );
+        self.context.queue().submit(Some(encoder.finish()));
+
+        let mut acc = vec![[0.; 4]; 2 * self.pc.num_points as usize];
+        self.context.read_buffer(&self.buf_staging_acc, &mut acc)?;
+        aggregator.merge(
+            self.count,
+            &acc.iter().step_by(2).copied().collect::<Vec<_>>(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+/// Unit of the sound pressure in the results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputUnit {
+    /// Sound pressure \[Pa\].
+    #[default]
+    Pascal,
+    /// Sound pressure level re 20 µPa \[dB\]. Zero pressure results in negative infinity.
+    DecibelSpl,
+    /// Acoustic intensity under the plane wave approximation, i.e., `p² / (ρc)` \[W/m²\].
+    Intensity,
+    /// Sound pressure normalized by the maximum absolute value among the points at each time.
+    Normalized,
+}
+
+impl OutputUnit {
+    /// Reference sound pressure for the sound pressure level \[Pa\].
+    pub const P_REF: f32 = 20e-6;
+
+    #[cfg(feature = "polars")]
+    // `quantity` is the prefix of the column name in Pa, e.g., `p` or `rms`.
+    pub(crate) fn column_name(&self, quantity: &str) -> String {
+        match self {
+            OutputUnit::Pascal => format!("{quantity}[Pa]"),
+            OutputUnit::DecibelSpl => "spl[dB]".to_string(),
+            OutputUnit::Intensity => "I[W/m^2]".to_string(),
+            OutputUnit::Normalized => format!("{quantity}[normalized]"),
+        }
+    }
+
+    // `density` is in kg/m³ and `sound_speed` is in mm/s.
+    pub(crate) fn convert(&self, v: &mut [f32], density: f32, sound_speed: f32) {
+        match self {
+            OutputUnit::Pascal => {}
+            OutputUnit::DecibelSpl => v
+                .iter_mut()
+                .for_each(|v| *v = 20. * (v.abs() / Self::P_REF).log10()),
+            OutputUnit::Intensity => {
+                let impedance = density * sound_speed / 1000.;
+                v.iter_mut().for_each(|v| *v = *v * *v / impedance)
+            }
+            OutputUnit::Normalized => {
+                let max = v.iter().fold(0., |acc: f32, v| acc.max(v.abs()));
+                if max > 0. {
+                    v.iter_mut().for_each(|v| *v /= max);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[rstest::rstest]
+    #[case(OutputUnit::Pascal, vec![-2., 0., 1.], vec![-2., 0., 1.])]
+    #[case(OutputUnit::DecibelSpl, vec![-20e-6, 200e-6, 2.], vec![0., 20., 100.])]
+    #[case(OutputUnit::Intensity, vec![-340. * 1.18, 0., 340. * 1.18], vec![340. * 1.18, 0., 340. * 1.18])]
+    #[case(OutputUnit::Normalized, vec![-4., 0., 2.], vec![-1., 0., 0.5])]
+    #[case(OutputUnit::Normalized, vec![0., 0.], vec![0., 0.])]
+    fn convert(#[case] unit: OutputUnit, #[case] mut v: Vec<f32>, #[case] expect: Vec<f32>) {
+        unit.convert(&mut v, 1.18, 340e3);
+        approx::assert_relative_eq!(expect.as_slice(), v.as_slice(), max_relative = 1e-5);
+    }
+
+    #[test]
+    fn spl_of_zero() {
+        let mut v = vec![0.];
+        OutputUnit::DecibelSpl.convert(&mut v, 1.18, 340e3);
+        assert_eq!(f32::NEG_INFINITY, v[0]);
+    }
+
+    #[cfg(feature = "polars")]
+    #[rstest::rstest]
+    #[case("p[Pa]", OutputUnit::Pascal)]
+    #[case("spl[dB]", OutputUnit::DecibelSpl)]
+    #[case("I[W/m^2]", OutputUnit::Intensity)]
+    #[case("p[normalized]", OutputUnit::Normalized)]
+    fn column_name(#[case] expect: &str, #[case] unit: OutputUnit) {
+        assert_eq!(expect, unit.column_name("p"));
+    }
+}
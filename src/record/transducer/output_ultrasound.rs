@@ -1,39 +1,58 @@
-use crate::record::ULTRASOUND_PERIOD_COUNT;
+use crate::record::{ULTRASOUND_PERIOD_COUNT, sound_field::precision::Float};
 
 use super::TransducerRecord;
 
 #[derive(Debug)]
-pub struct OutputUltrasound<'a> {
+pub struct OutputUltrasound<'a, T = f32> {
     pub(crate) cursor: usize,
     pub(crate) record: &'a TransducerRecord,
-    model: T4010A1BVDModel,
+    model: T4010A1BVDModel<T>,
 }
 
-impl OutputUltrasound<'_> {
-    pub(crate) fn _next_inplace(&mut self, n: usize, v: &mut [f32]) -> Option<()> {
+impl<T: Float> OutputUltrasound<'_, T> {
+    pub(crate) fn _next_inplace(&mut self, n: usize, v: &mut [T]) -> Option<()> {
         let output_volage = self.record._output_voltage_within(self.cursor, n)?;
         self.cursor += n;
         output_volage
             .into_iter()
             .zip(v.iter_mut())
-            .for_each(|(v, dst)| *dst = self.model.rk4(v));
+            .for_each(|(v, dst)| *dst = self.model.rk4(v.into()));
         Some(())
     }
 
-    pub(crate) fn _next(&mut self, n: usize) -> Option<Vec<f32>> {
-        let mut v = vec![0.0; n * ULTRASOUND_PERIOD_COUNT];
+    pub(crate) fn _next(&mut self, n: usize) -> Option<Vec<T>> {
+        let mut v = vec![T::default(); n * ULTRASOUND_PERIOD_COUNT];
         self._next_inplace(n, &mut v)?;
         Some(v)
     }
+
+    // Returns the emitted ultrasound and its time integral [s], which is the charge of the motional branch.
+    pub(crate) fn _next_with_integral(&mut self, n: usize) -> Option<(Vec<T>, Vec<T>)> {
+        let output_volage = self.record._output_voltage_within(self.cursor, n)?;
+        self.cursor += n;
+        Some(
+            output_volage
+                .into_iter()
+                .map(|v| {
+                    let integral = self.model.integral();
+                    (self.model.rk4(v.into()), integral)
+                })
+                .unzip(),
+        )
+    }
 }
 
 impl TransducerRecord {
     pub(crate) fn output_ultrasound(&self) -> OutputUltrasound<'_> {
+        self.output_ultrasound_with_precision()
+    }
+
+    pub(crate) fn output_ultrasound_with_precision<T: Float>(&self) -> OutputUltrasound<'_, T> {
         OutputUltrasound {
             record: self,
             model: T4010A1BVDModel {
-                state: (0., 0., 0.),
-                last_v: -Self::V,
+                state: (T::default(), T::default(), T::default()),
+                last_v: (-Self::V).into(),
             },
             cursor: 0,
         }
@@ -41,71 +60,95 @@ impl TransducerRecord {
 }
 
 #[derive(Debug)]
-struct T4010A1BVDModel {
-    state: (f32, f32, f32),
-    last_v: f32,
+struct T4010A1BVDModel<T> {
+    state: (T, T, T),
+    last_v: T,
 }
 
+// The parameters are converted to `T` from `f64` so that they are exact in double precision.
 #[allow(non_upper_case_globals)]
-impl T4010A1BVDModel {
-    const Cs: f32 = 200e-9; // mF
-    const L: f32 = 80e-6; // kH
-    const R: f32 = 0.7; // kΩ
-    const Cp: f32 = 2700e-9; // mF
-    const Rd: f32 = 150e-3; // kΩ
-    const h: f32 = TransducerRecord::TS;
-    const NORMALIZE: f32 = 0.057430573;
-
-    pub(crate) fn rk4(&mut self, input: f32) -> f32 {
+impl<T: Float> T4010A1BVDModel<T> {
+    const Cs: f64 = 200e-9; // mF
+    const L: f64 = 80e-6; // kH
+    const R: f64 = 0.7; // kΩ
+    const Cp: f64 = 2700e-9; // mF
+    const Rd: f64 = 150e-3; // kΩ
+    const NORMALIZE: f64 = 0.057430573;
+
+    fn c(v: f64) -> T {
+        T::from_f64(v)
+    }
+
+    fn h() -> T {
+        TransducerRecord::ts()
+    }
+
+    pub(crate) fn rk4(&mut self, input: T) -> T {
         let state = &self.state;
-        let y = state.1 * Self::NORMALIZE;
-        let k00 = Self::h * Self::f0(state);
-        let k01 = Self::h * self.f1(self.last_v, state);
-        let k02 = Self::h * self.f2(self.last_v, state);
-        let y1 = (state.0 + k00 / 2., state.1 + k01 / 2., state.2 + k02 / 2.);
-
-        let v = (self.last_v + input) / 2.;
-        let k10 = Self::h * Self::f0(&y1);
-        let k11 = Self::h * self.f1(v, &y1);
-        let k12 = Self::h * self.f2(v, &y1);
-        let y2 = (state.0 + k10 / 2., state.1 + k11 / 2., state.2 + k12 / 2.);
-
-        let k20 = Self::h * Self::f0(&y2);
-        let k21 = Self::h * self.f1(v, &y2);
-        let k22 = Self::h * self.f2(v, &y2);
+        let y = state.1 * Self::c(Self::NORMALIZE);
+        let k00 = Self::h() * Self::f0(state);
+        let k01 = Self::h() * self.f1(self.last_v, state);
+        let k02 = Self::h() * self.f2(self.last_v, state);
+        let two: T = 2f32.into();
+        let y1 = (
+            state.0 + k00 / two,
+            state.1 + k01 / two,
+            state.2 + k02 / two,
+        );
+
+        let v = (self.last_v + input) / two;
+        let k10 = Self::h() * Self::f0(&y1);
+        let k11 = Self::h() * self.f1(v, &y1);
+        let k12 = Self::h() * self.f2(v, &y1);
+        let y2 = (
+            state.0 + k10 / two,
+            state.1 + k11 / two,
+            state.2 + k12 / two,
+        );
+
+        let k20 = Self::h() * Self::f0(&y2);
+        let k21 = Self::h() * self.f1(v, &y2);
+        let k22 = Self::h() * self.f2(v, &y2);
         let y3 = (state.0 + k20, state.1 + k21, state.2 + k22);
 
         self.last_v = v;
-        let k30 = Self::h * Self::f0(&y3);
-        let k31 = Self::h * self.f1(input, &y3);
-        let k32 = Self::h * self.f2(input, &y3);
+        let k30 = Self::h() * Self::f0(&y3);
+        let k31 = Self::h() * self.f1(input, &y3);
+        let k32 = Self::h() * self.f2(input, &y3);
 
         self.last_v = input;
+        let six: T = 6f32.into();
         self.state = (
-            state.0 + (k00 + 2. * k10 + 2. * k20 + k30) / 6.,
-            state.1 + (k01 + 2. * k11 + 2. * k21 + k31) / 6.,
-            state.2 + (k02 + 2. * k12 + 2. * k22 + k32) / 6.,
+            state.0 + (k00 + two * k10 + two * k20 + k30) / six,
+            state.1 + (k01 + two * k11 + two * k21 + k31) / six,
+            state.2 + (k02 + two * k12 + two * k22 + k32) / six,
         );
         y
     }
 
-    fn f0(y: &(f32, f32, f32)) -> f32 {
+    pub(crate) fn integral(&self) -> T {
+        self.state.0 * Self::c(Self::NORMALIZE)
+    }
+
+    fn f0(y: &(T, T, T)) -> T {
         y.1
     }
 
-    fn f1(&self, v: f32, y: &(f32, f32, f32)) -> f32 {
-        -y.0 / (Self::L * Self::Cs)
-            - (Self::R + Self::Rd) / Self::L * y.1
-            - Self::Rd / Self::L * y.2
-            + v / Self::L
+    fn f1(&self, v: T, y: &(T, T, T)) -> T {
+        -y.0 / (Self::c(Self::L) * Self::c(Self::Cs))
+            - (Self::c(Self::R) + Self::c(Self::Rd)) / Self::c(Self::L) * y.1
+            - Self::c(Self::Rd) / Self::c(Self::L) * y.2
+            + v / Self::c(Self::L)
     }
 
-    fn f2(&self, v: f32, y: &(f32, f32, f32)) -> f32 {
-        let dt = (v - self.last_v) / Self::h * 2.;
-        y.0 / (Self::L * Self::Cs)
-            + (Self::R + Self::Rd) / Self::L * y.1
-            + (Self::Rd / Self::L - 1. / (Self::Rd * Self::Cp)) * y.2
-            + 1. / Self::Rd * dt
-            - v / Self::L
+    fn f2(&self, v: T, y: &(T, T, T)) -> T {
+        let dt = (v - self.last_v) / Self::h() * 2f32.into();
+        y.0 / (Self::c(Self::L) * Self::c(Self::Cs))
+            + (Self::c(Self::R) + Self::c(Self::Rd)) / Self::c(Self::L) * y.1
+            + (Self::c(Self::Rd) / Self::c(Self::L)
+                - T::from(1.) / (Self::c(Self::Rd) * Self::c(Self::Cp)))
+                * y.2
+            + T::from(1.) / Self::c(Self::Rd) * dt
+            - v / Self::c(Self::L)
     }
 }
@@ -1,6 +1,6 @@
 use autd3::driver::common::ULTRASOUND_FREQ;
 
-use crate::record::ULTRASOUND_PERIOD_COUNT;
+use crate::record::{ULTRASOUND_PERIOD_COUNT, sound_field::precision::Float};
 
 use super::TransducerRecord;
 
@@ -8,6 +8,11 @@ impl TransducerRecord {
     pub(crate) const TS: f32 = 1. / (ULTRASOUND_FREQ.hz() as f32 * ULTRASOUND_PERIOD_COUNT as f32);
     pub(crate) const V: f32 = 12.0;
 
+    // The sampling period in `T`, which is calculated in `f64` so that it is exact in double precision.
+    pub(crate) fn ts<T: Float>() -> T {
+        T::from_f64(1. / (ULTRASOUND_FREQ.hz() as f64 * ULTRASOUND_PERIOD_COUNT as f64))
+    }
+
     pub(crate) fn _output_voltage_within_inplace(&self, start: usize, n: usize, v: &mut [f32]) {
         const T: u16 = ULTRASOUND_PERIOD_COUNT as u16;
         self.pulse_width[start..]
@@ -0,0 +1,182 @@
+use autd3::{driver::common::ULTRASOUND_PERIOD, prelude::*};
+use autd3_emulator::*;
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_gorkov(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let point = Vector3::new(0., 0., 100. * mm);
+    let mut gorkov = record.sound_field(
+        RangeXY {
+            x: point.x - 10.0..=point.x + 10.0,
+            y: point.y - 10.0..=point.y + 10.0,
+            z: point.z,
+            resolution: 10.,
+        },
+        GorkovRecordOption {
+            #[cfg(feature = "gpu")]
+            gpu,
+            ..Default::default()
+        },
+    )?;
+
+    let df = gorkov.observe_points();
+    assert_eq!(9, df.height());
+
+    let df = gorkov
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    assert_eq!(
+        vec![
+            "U[J]@125000[ns]",
+            "Fx[N]@125000[ns]",
+            "Fy[N]@125000[ns]",
+            "Fz[N]@125000[ns]",
+            "U[J]@150000[ns]",
+            "Fx[N]@150000[ns]",
+            "Fy[N]@150000[ns]",
+            "Fz[N]@150000[ns]",
+        ],
+        df.get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(9, df.height());
+
+    assert!(gorkov.next(4 * ULTRASOUND_PERIOD).is_err());
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn gorkov_force_is_negative_gradient_of_potential(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 100. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let h = 0.01 * mm;
+    let p = focus + Vector3::new(1.0 * mm, 2.0 * mm, 3.0 * mm);
+    let points = vec![
+        p,
+        p - Vector3::x() * h,
+        p + Vector3::x() * h,
+        p - Vector3::y() * h,
+        p + Vector3::y() * h,
+        p - Vector3::z() * h,
+        p + Vector3::z() * h,
+    ];
+    let df = record
+        .sound_field(
+            points,
+            GorkovRecordOption {
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .next(ULTRASOUND_PERIOD)?;
+
+    let u = df[0].f32()?.into_no_null_iter().collect::<Vec<_>>();
+    (0..3).try_for_each(|i| -> Result<(), EmulatorError> {
+        let f = df[i + 1].f32()?.get(0).unwrap();
+        let expect = -(u[2 * i + 2] - u[2 * i + 1]) / (2. * h * 1e-3);
+        approx::assert_relative_eq!(expect, f, max_relative = 5e-2);
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn record_gorkov_gpu_eq_cpu() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 100. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXZ {
+        x: focus.x - 5.0..=focus.x + 5.0,
+        y: focus.y,
+        z: focus.z - 5.0..=focus.z + 5.0,
+        resolution: 1.,
+    };
+    let cpu = record
+        .sound_field(range.clone(), GorkovRecordOption::default())?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    let gpu = record
+        .sound_field(
+            range,
+            GorkovRecordOption {
+                gpu: true,
+                ..Default::default()
+            },
+        )?
+        .next(2 * ULTRASOUND_PERIOD)?;
+
+    assert_eq!(cpu.shape(), gpu.shape());
+    cpu.columns().iter().zip(gpu.columns()).try_for_each(
+        |(cpu, gpu)| -> Result<(), EmulatorError> {
+            cpu.f32()?
+                .into_no_null_iter()
+                .zip(gpu.f32()?.into_no_null_iter())
+                .for_each(|(cpu, gpu)| {
+                    approx::assert_relative_eq!(cpu, gpu, epsilon = 1e-12, max_relative = 1e-3);
+                });
+            Ok(())
+        },
+    )?;
+
+    Ok(())
+}
@@ -1,6 +1,8 @@
 mod drive;
+mod gorkov;
 mod output_ultrasound;
 mod output_voltage;
+mod phasor;
 mod rms;
 mod sound_field;
 
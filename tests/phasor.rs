@@ -0,0 +1,334 @@
+use autd3::{driver::common::ULTRASOUND_PERIOD, prelude::*};
+use autd3_emulator::*;
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_phasor(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -50.0..=50.0,
+        y: -50.0..=50.0,
+        z: 150.,
+        resolution: 50.,
+    };
+    let mut phasor = record.sound_field(
+        range.clone(),
+        PhasorRecordOption {
+            #[cfg(feature = "gpu")]
+            gpu,
+            ..Default::default()
+        },
+    )?;
+
+    let df = phasor
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    assert_eq!(
+        vec![
+            "re[Pa]@125000[ns]",
+            "im[Pa]@125000[ns]",
+            "re[Pa]@150000[ns]",
+            "im[Pa]@150000[ns]",
+        ],
+        df.get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(9, df.height());
+    assert!(phasor.next(4 * ULTRASOUND_PERIOD).is_err());
+
+    let polar = record
+        .sound_field(
+            range.clone(),
+            PhasorRecordOption {
+                format: PhasorFormat::Polar,
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(ULTRASOUND_PERIOD)?;
+    assert_eq!(
+        vec!["amp[Pa]@125000[ns]", "phase[rad]@125000[ns]"],
+        polar
+            .get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    let rms = record
+        .sound_field(
+            range,
+            RmsRecordOption {
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(ULTRASOUND_PERIOD)?;
+
+    let re = df[0].f32()?.into_no_null_iter();
+    let im = df[1].f32()?.into_no_null_iter();
+    let amp = polar[0].f32()?.into_no_null_iter();
+    let phase = polar[1].f32()?.into_no_null_iter();
+    let rms = rms[0].f32()?.into_no_null_iter();
+    re.zip(im)
+        .zip(amp.zip(phase))
+        .zip(rms)
+        .for_each(|(((re, im), (amp, phase)), rms)| {
+            approx::assert_relative_eq!(rms, amp, max_relative = 1e-5);
+            approx::assert_relative_eq!(amp * phase.cos(), re, epsilon = 1e-3, max_relative = 1e-4);
+            approx::assert_relative_eq!(amp * phase.sin(), im, epsilon = 1e-3, max_relative = 1e-4);
+        });
+
+    Ok(())
+}
+
+#[test]
+fn phasor_phase_follows_drive_phase() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let df = record
+        .sound_field(
+            emulator.center() + Vector3::new(0., 0., 100. * mm),
+            PhasorRecordOption {
+                format: PhasorFormat::Polar,
+                ..Default::default()
+            },
+        )?
+        .next(2 * ULTRASOUND_PERIOD)?;
+
+    let amp0 = df[0].f32()?.get(0).unwrap();
+    let phase0 = df[1].f32()?.get(0).unwrap();
+    let amp1 = df[2].f32()?.get(0).unwrap();
+    let phase1 = df[3].f32()?.get(0).unwrap();
+    approx::assert_relative_eq!(amp0, amp1, max_relative = 1e-5);
+    let diff = autd3::driver::geometry::Complex::new(0., phase1 - phase0).exp();
+    approx::assert_relative_eq!(Phase(0x40).radian(), diff.arg(), epsilon = 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn record_phasor_tiled() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -50.0..=50.0,
+        y: -50.0..=50.0,
+        z: 100. * mm,
+        resolution: 1.,
+    };
+
+    // the distances do not fit in the memory limits, so that they are calculated for each tile of points
+    let expect = record
+        .sound_field(range.clone(), PhasorRecordOption::default())?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    let v = record
+        .sound_field(
+            range,
+            PhasorRecordOption {
+                memory_limits_hint_mb: 1,
+                ..Default::default()
+            },
+        )?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    assert_eq!(expect, v);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(EmissionModel::Ideal, 1, Precision::Single)]
+#[case(EmissionModel::Bvd, 3, Precision::Single)]
+#[case(EmissionModel::Bvd, 1, Precision::Double)]
+#[test]
+fn phasor_eq_rms_phasor(
+    #[case] emission: EmissionModel,
+    #[case] harmonic: usize,
+    #[case] precision: Precision,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 100. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let reflectors = vec![Reflector {
+        pos: Point3::new(0., 0., 200.),
+        normal: UnitVector3::new_unchecked(-Vector3::z()),
+        reflection_coefficient: 0.5,
+    }];
+    let scatterers = vec![Scatterer {
+        center: focus + Vector3::new(0., 0., 10.),
+        radius: 2.,
+    }];
+    let range = RangeXY {
+        x: focus.x - 10.0..=focus.x + 10.0,
+        y: focus.y - 10.0..=focus.y + 10.0,
+        z: focus.z,
+        resolution: 5.,
+    };
+    let phasor = record
+        .sound_field(
+            range.clone(),
+            PhasorRecordOption {
+                reflectors: reflectors.clone(),
+                scatterers: scatterers.clone(),
+                emission,
+                harmonic,
+                precision,
+                ..Default::default()
+            },
+        )?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    let rms = record
+        .sound_field(
+            range,
+            RmsRecordOption {
+                particle_velocity: true,
+                reflectors,
+                scatterers,
+                emission,
+                harmonic,
+                precision,
+                ..Default::default()
+            },
+        )?
+        .next(2 * ULTRASOUND_PERIOD)?;
+
+    // The phasors of the pressure follow the RMS in each period of `Rms`.
+    (0..2).try_for_each(|t| -> Result<(), EmulatorError> {
+        (0..2).try_for_each(|c| -> Result<(), EmulatorError> {
+            phasor[2 * t + c]
+                .f32()?
+                .into_no_null_iter()
+                .zip(rms[9 * t + 1 + c].f32()?.into_no_null_iter())
+                .for_each(|(phasor, rms)| {
+                    approx::assert_relative_eq!(rms, phasor, epsilon = 1e-4, max_relative = 1e-5);
+                });
+            Ok(())
+        })
+    })?;
+
+    Ok(())
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn record_phasor_gpu_eq_cpu() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 100. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXZ {
+        x: focus.x - 20.0..=focus.x + 20.0,
+        y: focus.y,
+        z: focus.z - 20.0..=focus.z + 20.0,
+        resolution: 1.,
+    };
+    let cpu = record
+        .sound_field(range.clone(), PhasorRecordOption::default())?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    let gpu = record
+        .sound_field(
+            range,
+            PhasorRecordOption {
+                gpu: true,
+                ..Default::default()
+            },
+        )?
+        .next(2 * ULTRASOUND_PERIOD)?;
+
+    assert_eq!(cpu.shape(), gpu.shape());
+    cpu.columns().iter().zip(gpu.columns()).try_for_each(
+        |(cpu, gpu)| -> Result<(), EmulatorError> {
+            cpu.f32()?
+                .into_no_null_iter()
+                .zip(gpu.f32()?.into_no_null_iter())
+                .for_each(|(cpu, gpu)| {
+                    approx::assert_abs_diff_eq!(cpu, gpu, epsilon = 0.1);
+                });
+            Ok(())
+        },
+    )?;
+
+    Ok(())
+}
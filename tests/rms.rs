@@ -204,6 +204,48 @@ fn record_rms_skip() -> Result<(), EmulatorError> {
     Ok(())
 }
 
+#[test]
+fn record_rms_tiled() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -50.0..=50.0,
+        y: -50.0..=50.0,
+        z: 100. * mm,
+        resolution: 1.,
+    };
+
+    // the distances do not fit in the memory limits, so that they are calculated for each tile of points
+    let expect = record
+        .sound_field(range.clone(), RmsRecordOption::default())?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    let v = record
+        .sound_field(
+            range,
+            RmsRecordOption {
+                memory_limits_hint_mb: 1,
+                ..Default::default()
+            },
+        )?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    assert_eq!(expect, v);
+
+    Ok(())
+}
+
 #[cfg(feature = "gpu")]
 #[test]
 fn record_rms_gpu_eq_cpu() -> Result<(), EmulatorError> {
@@ -265,6 +307,102 @@ fn record_rms_gpu_eq_cpu() -> Result<(), EmulatorError> {
     Ok(())
 }
 
+#[cfg(feature = "gpu")]
+#[test]
+fn record_rms_gpu_context() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: emulator.center() + Vector3::new(0., 0., 150. * mm),
+            option: Default::default(),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let rms = |z: f32,
+               gpu_context: Option<GpuContext>|
+     -> Result<polars::frame::DataFrame, EmulatorError> {
+        record
+            .sound_field(
+                RangeXY {
+                    x: 0.0..=100.0,
+                    y: 0.0..=100.0,
+                    z,
+                    resolution: 5.,
+                },
+                RmsRecordOption {
+                    particle_velocity: true,
+                    gpu: true,
+                    gpu_context,
+                    ..Default::default()
+                },
+            )?
+            .next(10 * ULTRASOUND_PERIOD)
+    };
+
+    // the device and the pipelines are reused across the computations of different ranges and kinds
+    let context = GpuContext::new()?;
+    [100., 150.].into_iter().try_for_each(|z| {
+        assert_eq!(rms(z, None)?, rms(z, Some(context.clone()))?);
+        Ok::<_, EmulatorError>(())
+    })?;
+    let instant = |gpu_context| {
+        record
+            .sound_field(
+                emulator.center() + Vector3::new(0., 0., 150. * mm),
+                InstantRecordOption {
+                    gpu: true,
+                    gpu_context,
+                    ..Default::default()
+                },
+            )?
+            .next(10 * ULTRASOUND_PERIOD)
+    };
+    assert_eq!(instant(None)?, instant(Some(context))?);
+
+    Ok(())
+}
+
+#[cfg(feature = "gpu")]
+#[test]
+fn record_rms_gpu_adapter_not_found() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let gpu_option = GpuOption {
+        adapter_index: Some(usize::MAX),
+        ..Default::default()
+    };
+    let count = GpuContext::adapters(gpu_option.backends).len();
+    assert!(matches!(
+        record.sound_field(
+            emulator.center(),
+            RmsRecordOption {
+                gpu: true,
+                gpu_option,
+                ..Default::default()
+            },
+        ),
+        Err(EmulatorError::GpuAdapterNotFound { index: usize::MAX, count: c }) if c == count
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn not_recorded() -> Result<(), EmulatorError> {
     let emulator = Emulator::new([AUTD3 {
@@ -297,3 +435,1529 @@ fn not_recorded() -> Result<(), EmulatorError> {
 
     Ok(())
 }
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_particle_velocity(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let option = RmsRecordOption {
+        particle_velocity: true,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+    let df = record
+        .sound_field(
+            emulator.center() + Vector3::new(0., 0., 1000. * mm),
+            option.clone(),
+        )?
+        .next(ULTRASOUND_PERIOD)?;
+    assert_eq!(
+        vec![
+            "rms[Pa]@0[ns]",
+            "rms_re[Pa]@0[ns]",
+            "rms_im[Pa]@0[ns]",
+            "rms_vx_re[m/s]@0[ns]",
+            "rms_vx_im[m/s]@0[ns]",
+            "rms_vy_re[m/s]@0[ns]",
+            "rms_vy_im[m/s]@0[ns]",
+            "rms_vz_re[m/s]@0[ns]",
+            "rms_vz_im[m/s]@0[ns]",
+        ],
+        df.get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    let value = |c: usize| df[c].f32().unwrap().get(0).unwrap();
+    let p = value(0);
+    let phasor = |c: usize| (value(c), value(c + 1));
+    let (p_re, p_im) = phasor(1);
+    approx::assert_relative_eq!(p, p_re.hypot(p_im), max_relative = 1e-4);
+
+    // plane wave approximation in the far field on the axis
+    let impedance = option.density * option.sound_speed * 1e-3;
+    let [vx, vy, vz] = [3, 5, 7].map(|c| {
+        let (re, im) = phasor(c);
+        re.hypot(im)
+    });
+    approx::assert_relative_eq!(p / impedance, vz, max_relative = 1e-2);
+    assert!(vx < vz * 1e-1);
+    assert!(vy < vz * 1e-1);
+
+    // the active intensity Re(p v*) flows away from the array with the magnitude p² / ρc, and the reactive intensity Im(p v*) vanishes
+    let [ix, iy, iz] = [3, 5, 7].map(|c| {
+        let (v_re, v_im) = phasor(c);
+        (p_re * v_re + p_im * v_im, p_im * v_re - p_re * v_im)
+    });
+    approx::assert_relative_eq!(p * p / impedance, iz.0, max_relative = 2e-2);
+    assert!(iz.1.abs() < iz.0 * 1e-1);
+    assert!(ix.0.abs() < iz.0 * 1e-1);
+    assert!(iy.0.abs() < iz.0 * 1e-1);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_aggregate(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::default())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -10.0..=10.0,
+        y: -10.0..=10.0,
+        z: 100.,
+        resolution: 10.,
+    };
+    let option = RmsRecordOption {
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let df = record
+        .sound_field(range.clone(), option.clone())?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    let aggregated = record
+        .sound_field(range.clone(), option.clone())?
+        .aggregate(
+            10 * ULTRASOUND_PERIOD,
+            &[
+                Statistic::Max,
+                Statistic::Min,
+                Statistic::Mean,
+                Statistic::Percentile(50.),
+            ],
+        )?;
+    // Without the percentiles, the statistics are reduced on GPU without reading back each period.
+    let reduced = record.sound_field(range, option.clone())?.aggregate(
+        10 * ULTRASOUND_PERIOD,
+        &[Statistic::Max, Statistic::Min, Statistic::Mean],
+    )?;
+
+    assert_eq!(
+        vec![
+            "rms_max[Pa]",
+            "rms_min[Pa]",
+            "rms_mean[Pa]",
+            "rms_percentile50[Pa]"
+        ],
+        aggregated
+            .get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    (0..df.height()).try_for_each(|i| -> Result<(), EmulatorError> {
+        let mut v = df
+            .columns()
+            .iter()
+            .map(|c| c.f32().map(|c| c.get(i).unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        v.sort_by(f32::total_cmp);
+        let get = |c: usize| aggregated[c].f32().map(|c| c.get(i).unwrap());
+        assert_eq!(v[v.len() - 1], get(0)?);
+        assert_eq!(v[0], get(1)?);
+        approx::assert_relative_eq!(
+            v.iter().sum::<f32>() / v.len() as f32,
+            get(2)?,
+            max_relative = 1e-5
+        );
+        approx::assert_relative_eq!((v[4] + v[5]) / 2., get(3)?, max_relative = 1e-5);
+        (0..3).try_for_each(|c| -> Result<(), EmulatorError> {
+            approx::assert_relative_eq!(
+                get(c)?,
+                reduced[c].f32()?.get(i).unwrap(),
+                max_relative = 1e-5
+            );
+            Ok(())
+        })?;
+        Ok(())
+    })?;
+
+    assert!(matches!(
+        record
+            .sound_field(
+                RangeXY {
+                    x: -10.0..=10.0,
+                    y: -10.0..=10.0,
+                    z: 100.,
+                    resolution: 10.,
+                },
+                option
+            )?
+            .aggregate(ULTRASOUND_PERIOD, &[Statistic::Percentile(101.)]),
+        Err(EmulatorError::InvalidPercentile(_))
+    ));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(1., false)]
+#[case(0.5, false)]
+#[case(0., false)]
+#[cfg_attr(feature = "gpu", case(1., true))]
+#[cfg_attr(feature = "gpu", case(0.5, true))]
+#[test]
+fn record_rms_reflector(
+    #[case] reflection_coefficient: f32,
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    // on the reflector, the direct and reflected waves have the same path length
+    let z = 150. * mm;
+    let range = RangeXY {
+        x: -50.0..=250.0,
+        y: -50.0..=200.0,
+        z,
+        resolution: 50.,
+    };
+    let direct = record
+        .sound_field(
+            range.clone(),
+            RmsRecordOption {
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .next(ULTRASOUND_PERIOD)?;
+    let reflected = record
+        .sound_field(
+            range,
+            RmsRecordOption {
+                reflectors: vec![Reflector {
+                    pos: Point3::new(0., 0., z),
+                    normal: UnitVector3::new_unchecked(-Vector3::z()),
+                    reflection_coefficient,
+                }],
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .next(ULTRASOUND_PERIOD)?;
+
+    direct[0]
+        .f32()?
+        .into_no_null_iter()
+        .zip(reflected[0].f32()?.into_no_null_iter())
+        .for_each(|(direct, reflected)| {
+            approx::assert_relative_eq!(
+                (1. + reflection_coefficient) * direct,
+                reflected,
+                max_relative = 1e-4
+            );
+        });
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_reflector_standing_wave() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let center = emulator.center();
+    let range = RangeZ {
+        x: center.x,
+        y: center.y,
+        z: 130.0..=145.0,
+        resolution: 0.5,
+    };
+    let rms = |reflection_order: usize| -> Result<Vec<f32>, EmulatorError> {
+        let df = record
+            .sound_field(
+                range.clone(),
+                RmsRecordOption {
+                    reflectors: vec![Reflector {
+                        pos: Point3::new(0., 0., 150. * mm),
+                        normal: UnitVector3::new_unchecked(-Vector3::z()),
+                        reflection_coefficient: 1.,
+                    }],
+                    reflection_order,
+                    ..Default::default()
+                },
+            )?
+            .next(ULTRASOUND_PERIOD)?;
+        Ok(df[0].f32()?.into_no_null_iter().collect())
+    };
+
+    // nodes appear every half wavelength when the reflected wave is added
+    let direct = rms(0)?;
+    let reflected = rms(1)?;
+    let contrast = |v: &[f32]| {
+        v.iter().copied().fold(f32::NEG_INFINITY, f32::max)
+            / v.iter().copied().fold(f32::INFINITY, f32::min)
+    };
+    assert!(contrast(&reflected) > 2. * contrast(&direct));
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_focal_spot() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(10., -5., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: focus.x - 20.0..=focus.x + 20.0,
+        y: focus.y - 20.0..=focus.y + 20.0,
+        z: focus.z,
+        resolution: 1.,
+    };
+    let df = record
+        .sound_field(range.clone(), RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?;
+    let values = df[0].f32()?.into_no_null_iter().collect::<Vec<_>>();
+
+    let spot = Grid::from(&range).focal_spot(&values, Some(focus))?;
+
+    assert!(spot.focus_error.unwrap() < 0.5 * mm);
+    approx::assert_relative_eq!(
+        values.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+        spot.peak_value,
+        max_relative = 1e-2
+    );
+    spot.fwhm.iter().for_each(|fwhm| {
+        let fwhm = fwhm.unwrap();
+        assert!(5. * mm < fwhm && fwhm < 15. * mm);
+    });
+    assert!(spot.side_lobe_level.unwrap() < 0.5);
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_mesh() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let obj = format!(
+        "v {} {} {}\nv {} {} {}\nv {} {} {}\nv {} {} {}\nf 1 2 3 4\n",
+        focus.x - 10.,
+        focus.y - 10.,
+        focus.z,
+        focus.x + 10.,
+        focus.y - 10.,
+        focus.z,
+        focus.x + 10.,
+        focus.y + 10.,
+        focus.z,
+        focus.x - 10.,
+        focus.y + 10.,
+        focus.z,
+    );
+    let mesh = MeshRange {
+        sampling: MeshSampling::Centroids,
+        ..MeshRange::read_obj(obj.as_bytes())?
+    };
+    let centroids = mesh.centroids().collect::<Vec<_>>();
+
+    let df = record
+        .sound_field(mesh.clone(), RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?;
+    let expect = record
+        .sound_field(centroids, RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?;
+    assert_eq!(expect, df);
+
+    let values = df[0].f32()?.into_no_null_iter().collect::<Vec<_>>();
+    let attribute = mesh.vertex_attribute(&values)?;
+    assert_eq!(mesh.vertices.len(), attribute.len());
+    approx::assert_relative_eq!((values[0] + values[1]) / 2., attribute[0]);
+    approx::assert_relative_eq!(values[0], attribute[1]);
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_arc() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let center = emulator.center();
+    let radius = 500. * mm;
+    let range = RangeArc {
+        center,
+        radius,
+        theta: -40. * deg..=40. * deg,
+        phi: 0. * deg,
+        resolution: 1. * deg,
+    };
+    let thetas = range.thetas();
+    assert_eq!(81, thetas.len());
+    let df = record
+        .sound_field(range, RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?;
+
+    let points = thetas
+        .iter()
+        .map(|theta| {
+            center
+                + Vector3::new(
+                    radius * theta.radian().sin(),
+                    0.,
+                    radius * theta.radian().cos(),
+                )
+        })
+        .collect::<Vec<_>>();
+    let expect = record
+        .sound_field(points, RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?;
+    df[0]
+        .f32()?
+        .into_no_null_iter()
+        .zip(expect[0].f32()?.into_no_null_iter())
+        .for_each(|(a, b)| approx::assert_relative_eq!(b, a, max_relative = 1e-4));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_next_field(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeZYX {
+        x: focus.x - 2.0..=focus.x + 2.0,
+        y: focus.y - 1.0..=focus.y + 1.0,
+        z: focus.z - 3.0..=focus.z + 3.0,
+        resolution: 1.,
+    };
+    let option = RmsRecordOption {
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+    let field = record
+        .sound_field(range.clone(), option.clone())?
+        .next_field(2 * ULTRASOUND_PERIOD)?;
+    assert_eq!(vec![2, 7, 3, 5], field.shape());
+    assert_eq!(
+        vec![0, ULTRASOUND_PERIOD.as_nanos()],
+        field.time.iter().map(|t| t.as_nanos()).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec!["z[mm]", "y[mm]", "x[mm]"],
+        field.axes.iter().map(|a| a.name).collect::<Vec<_>>()
+    );
+
+    let df = record
+        .sound_field(range, option)?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    let points = record
+        .sound_field(
+            RangeZYX {
+                x: focus.x - 2.0..=focus.x + 2.0,
+                y: focus.y - 1.0..=focus.y + 1.0,
+                z: focus.z - 3.0..=focus.z + 3.0,
+                resolution: 1.,
+            },
+            RmsRecordOption::default(),
+        )?
+        .observe_points();
+    let x = points["x[mm]"]
+        .f32()?
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+    let y = points["y[mm]"]
+        .f32()?
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+    let z = points["z[mm]"]
+        .f32()?
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+    (0..2).try_for_each(|t| -> Result<(), EmulatorError> {
+        df[t]
+            .f32()?
+            .into_no_null_iter()
+            .enumerate()
+            .for_each(|(i, v)| {
+                let iz = field.axes[0]
+                    .coords
+                    .iter()
+                    .position(|&c| c == z[i])
+                    .unwrap();
+                let iy = field.axes[1]
+                    .coords
+                    .iter()
+                    .position(|&c| c == y[i])
+                    .unwrap();
+                let ix = field.axes[2]
+                    .coords
+                    .iter()
+                    .position(|&c| c == x[i])
+                    .unwrap();
+                assert_eq!(Some(v), field.get(&[t, iz, iy, ix]));
+            });
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_adaptive() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let coarse = RangeXY {
+        x: focus.x - 40.0..=focus.x + 40.0,
+        y: focus.y - 40.0..=focus.y + 40.0,
+        z: focus.z,
+        resolution: 10.,
+    };
+    let peak = record
+        .sound_field(focus, RmsRecordOption::default())?
+        .next(ULTRASOUND_PERIOD)?[0]
+        .f32()?
+        .get(0)
+        .unwrap();
+    let range = AdaptiveRange {
+        grid: Grid::from(&coarse),
+        max_depth: 3,
+        gradient_threshold: None,
+        amplitude_threshold: Some(0.5 * peak),
+    };
+    let field =
+        record.sound_field_adaptive(&range, RmsRecordOption::default(), ULTRASOUND_PERIOD)?;
+
+    assert_eq!(64, field.roots.len());
+    assert!(field.nodes.len() > field.roots.len());
+    assert_eq!(field.points.len(), field.values.len());
+    field.nodes.iter().for_each(|node| {
+        assert!(node.depth <= 3);
+        assert_eq!(4, node.corners.len());
+        assert_eq!(2, node.edges.len());
+        approx::assert_abs_diff_eq!(10. / (1 << node.depth) as f32, node.edges[0].norm());
+        let max = node
+            .corners
+            .iter()
+            .map(|&i| field.values[i])
+            .fold(f32::NEG_INFINITY, f32::max);
+        assert_eq!(
+            node.depth < 3 && max > 0.5 * peak,
+            !node.children.is_empty()
+        );
+    });
+    assert!(field.leaves().any(|node| node.depth == 3));
+    assert!(
+        field
+            .leaves()
+            .all(|node| { node.depth == 0 || (node.origin - focus).norm() < 20. })
+    );
+
+    let expect = record
+        .sound_field(field.points.clone(), RmsRecordOption::default())?
+        .skip(ULTRASOUND_PERIOD)?
+        .next(ULTRASOUND_PERIOD)?;
+    expect[0]
+        .f32()?
+        .into_no_null_iter()
+        .zip(field.values.iter())
+        .for_each(|(e, &v)| approx::assert_relative_eq!(e, v, max_relative = 1e-4));
+
+    let cloud = field.point_cloud();
+    assert_eq!(field.points.len(), cloud.height());
+    let mut ply = Vec::new();
+    field.write_ply(&mut ply)?;
+    let ply = String::from_utf8(ply).unwrap();
+    assert!(ply.contains(&format!("element vertex {}\n", field.points.len())));
+    assert_eq!(field.points.len() + 8, ply.lines().count());
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(0, 64)]
+#[case(9, 64)]
+#[case(9, usize::MAX)]
+#[case(0, 3)]
+#[test]
+fn record_rms_adaptive_invalid(
+    #[case] len: usize,
+    #[case] max_depth: usize,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3::default()]);
+    let record = emulator.record(|autd| {
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = AdaptiveRange {
+        grid: Grid {
+            origin: emulator.center(),
+            axes: vec![
+                GridAxis {
+                    step: Vector3::new(0., 10., 0.),
+                    len,
+                },
+                GridAxis {
+                    step: Vector3::new(10., 0., 0.),
+                    len: 9,
+                },
+            ],
+        },
+        max_depth,
+        gradient_threshold: None,
+        amplitude_threshold: None,
+    };
+    assert!(matches!(
+        record.sound_field_adaptive(&range, RmsRecordOption::default(), Duration::ZERO),
+        Err(EmulatorError::InvalidAdaptiveRange(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_directivity() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let steer = 20. * deg;
+    let distance = 20e3 * mm;
+    let center = emulator.center();
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: center + Vector3::new(steer.radian().sin(), 0., steer.radian().cos()) * distance,
+            option: Default::default(),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = DirectivityRange {
+        theta: -90. * deg..=90. * deg,
+        phi: 0. * deg..=90. * deg,
+        resolution: 1. * deg,
+    };
+    let directivity = record.directivity(&range, RmsRecordOption::default(), Duration::ZERO)?;
+    assert_eq!(range.thetas().len(), directivity.thetas.len());
+    assert_eq!(range.phis().len(), directivity.phis.len());
+    assert!(directivity.values.iter().all(|&v| (0. ..=1.).contains(&v)));
+
+    let main = directivity.main_lobe();
+    assert_eq!(1., main.level);
+    approx::assert_abs_diff_eq!(steer.degree(), main.theta.degree(), epsilon = 1.);
+    approx::assert_abs_diff_eq!(0., main.phi.degree(), epsilon = 1e-3);
+    assert_eq!(main, directivity.lobes()[0]);
+
+    // The half-power beamwidth of a uniform linear array is about 0.886 λ / (L cos θ).
+    let wavelength = 340e3 * mm / 40e3;
+    let width = AUTD3::NUM_TRANS_X as f32 * AUTD3::TRANS_SPACING;
+    let expect = 0.886 * wavelength / (width * steer.radian().cos());
+    approx::assert_abs_diff_eq!(
+        expect.to_degrees(),
+        directivity.beamwidth(-3.).unwrap().degree(),
+        epsilon = 0.5
+    );
+
+    // The pitch is larger than the wavelength, so that grating lobes appear where the direction differs from the main lobe by multiples of λ / d in the xy plane.
+    let pitch = wavelength / AUTD3::TRANS_SPACING;
+    let lobes = directivity.grating_lobes(-3.);
+    assert!(!lobes.is_empty());
+    lobes.iter().for_each(|lobe| {
+        let (st, ct) = lobe.theta.radian().sin_cos();
+        let (sp, cp) = lobe.phi.radian().sin_cos();
+        let m = (st * cp - steer.radian().sin()) / pitch;
+        let n = st * sp / pitch;
+        approx::assert_abs_diff_eq!(m.round(), m, epsilon = 0.05);
+        approx::assert_abs_diff_eq!(n.round(), n, epsilon = 0.05);
+        assert!(ct > 0.);
+    });
+    let grating = lobes
+        .iter()
+        .find(|lobe| lobe.phi.degree().abs() < 1e-3)
+        .unwrap();
+    approx::assert_abs_diff_eq!(
+        (steer.radian().sin() - pitch).asin().to_degrees(),
+        grating.theta.degree(),
+        epsilon = 1.
+    );
+
+    // The far field calculated as the RMS agrees with the directivity.
+    let rms = record
+        .sound_field(
+            [main, *grating]
+                .iter()
+                .map(|lobe| {
+                    let (st, ct) = lobe.theta.radian().sin_cos();
+                    let (sp, cp) = lobe.phi.radian().sin_cos();
+                    center + Vector3::new(st * cp, st * sp, ct) * distance
+                })
+                .collect::<Vec<_>>(),
+            RmsRecordOption::default(),
+        )?
+        .next_field(ULTRASOUND_PERIOD)?
+        .values;
+    approx::assert_relative_eq!(directivity.peak, rms[0] * distance, max_relative = 0.02);
+    approx::assert_relative_eq!(grating.level, rms[1] / rms[0], max_relative = 0.02);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_unit(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeX {
+        x: emulator.center().x - 50.0..=emulator.center().x + 50.0,
+        y: emulator.center().y,
+        z: emulator.center().z + 150.,
+        resolution: 10.,
+    };
+    let rms = |unit| -> Result<(String, Vec<f32>), EmulatorError> {
+        let df = record
+            .sound_field(
+                range.clone(),
+                RmsRecordOption {
+                    unit,
+                    #[cfg(feature = "gpu")]
+                    gpu,
+                    ..Default::default()
+                },
+            )?
+            .next(ULTRASOUND_PERIOD)?;
+        Ok((
+            df[0].name().to_string(),
+            df[0].f32()?.into_no_null_iter().collect(),
+        ))
+    };
+
+    let (name, pa) = rms(OutputUnit::Pascal)?;
+    assert_eq!("rms[Pa]@0[ns]", name);
+    let max = pa.iter().copied().fold(0., f32::max);
+
+    let (name, spl) = rms(OutputUnit::DecibelSpl)?;
+    assert_eq!("spl[dB]@0[ns]", name);
+    pa.iter().zip(spl.iter()).for_each(|(&p, &l)| {
+        approx::assert_relative_eq!(20. * (p / 20e-6).log10(), l, max_relative = 1e-4)
+    });
+
+    let (name, intensity) = rms(OutputUnit::Intensity)?;
+    assert_eq!("I[W/m^2]@0[ns]", name);
+    pa.iter().zip(intensity.iter()).for_each(|(&p, &i)| {
+        approx::assert_relative_eq!(p * p / (1.18 * 340.), i, max_relative = 1e-4)
+    });
+
+    let (name, normalized) = rms(OutputUnit::Normalized)?;
+    assert_eq!("rms[normalized]@0[ns]", name);
+    pa.iter()
+        .zip(normalized.iter())
+        .for_each(|(&p, &n)| approx::assert_relative_eq!(p / max, n, max_relative = 1e-4));
+
+    let mut rms = record.sound_field(
+        range.clone(),
+        RmsRecordOption {
+            unit: OutputUnit::Normalized,
+            #[cfg(feature = "gpu")]
+            gpu,
+            ..Default::default()
+        },
+    )?;
+    let field = rms.next_field(ULTRASOUND_PERIOD)?;
+    assert_eq!(normalized, field.values);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_for_each_frame(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: emulator.center().x - 10.0..=emulator.center().x + 10.0,
+        y: emulator.center().y - 10.0..=emulator.center().y + 10.0,
+        z: emulator.center().z + 10.,
+        resolution: 10.,
+    };
+    let option = RmsRecordOption {
+        unit: OutputUnit::DecibelSpl,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let expect = record
+        .sound_field(range.clone(), option.clone())?
+        .next(10 * ULTRASOUND_PERIOD)?;
+
+    let mut rms = record.sound_field(range, option)?;
+    let mut n = 0;
+    rms.for_each_frame(10 * ULTRASOUND_PERIOD, |t, r| {
+        let column = &expect[n];
+        assert_eq!(format!("spl[dB]@{t}[ns]"), column.name().as_str());
+        assert_eq!(
+            column
+                .f32()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<_>>(),
+            r
+        );
+        n += 1;
+    })?;
+    assert_eq!(expect.width(), n);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_window(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send((
+            Sine {
+                freq: 1000 * Hz,
+                option: Default::default(),
+            },
+            Uniform {
+                phase: Phase::ZERO,
+                intensity: Intensity(0xFF),
+            },
+        ))?;
+        autd.tick(80 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeX {
+        x: emulator.center().x - 10.0..=emulator.center().x + 10.0,
+        y: emulator.center().y,
+        z: emulator.center().z + 10.,
+        resolution: 10.,
+    };
+    let option = |window, window_step| RmsRecordOption {
+        window,
+        window_step,
+        particle_velocity: true,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let periods = record
+        .sound_field(range.clone(), option(ULTRASOUND_PERIOD, None))?
+        .next(80 * ULTRASOUND_PERIOD)?;
+    // the RMS of the pressure is averaged in squares, and the phasors are averaged as they are
+    let expect = |start: usize, col: usize| {
+        let mut sum = vec![0.; range.points().count()];
+        (start..start + 40).for_each(|i| {
+            periods[9 * i + col]
+                .f32()
+                .unwrap()
+                .into_no_null_iter()
+                .zip(sum.iter_mut())
+                .for_each(|(r, s)| *s += if col == 0 { r * r } else { r })
+        });
+        sum.into_iter()
+            .map(|s| if col == 0 { (s / 40.).sqrt() } else { s / 40. })
+            .collect::<Vec<_>>()
+    };
+    let check = |df: &polars::frame::DataFrame, starts: &[usize]| {
+        assert_eq!(9 * starts.len(), df.width());
+        starts.iter().enumerate().for_each(|(i, &start)| {
+            (0..9).for_each(|col| {
+                let column = &df[9 * i + col];
+                assert!(column.name().ends_with(&format!(
+                    "@{}[ns]",
+                    (start as u32 * ULTRASOUND_PERIOD).as_nanos()
+                )));
+                let expect = expect(start, col);
+                let max = expect.iter().fold(0., |acc: f32, &v| acc.max(v.abs()));
+                column
+                    .f32()
+                    .unwrap()
+                    .into_no_null_iter()
+                    .zip(expect)
+                    .for_each(|(v, e)| approx::assert_abs_diff_eq!(e, v, epsilon = 1e-4 * max));
+            })
+        });
+    };
+
+    let window = 40 * ULTRASOUND_PERIOD;
+    let mut rms = record.sound_field(range.clone(), option(window, None))?;
+    assert_eq!(2, rms.next_time_len(80 * ULTRASOUND_PERIOD));
+    check(&rms.next(80 * ULTRASOUND_PERIOD)?, &[0, 40]);
+
+    let mut rms =
+        record.sound_field(range.clone(), option(window, Some(10 * ULTRASOUND_PERIOD)))?;
+    check(&rms.next(50 * ULTRASOUND_PERIOD)?, &[0, 10]);
+    check(&rms.next(30 * ULTRASOUND_PERIOD)?, &[20, 30, 40]);
+
+    let mut rms =
+        record.sound_field(range.clone(), option(window, Some(10 * ULTRASOUND_PERIOD)))?;
+    rms.skip(45 * ULTRASOUND_PERIOD)?;
+    check(&rms.next(35 * ULTRASOUND_PERIOD)?, &[10, 20, 30, 40]);
+
+    // the RMS over the modulation period is smaller than the maximum RMS of each period
+    let peak = (0..40)
+        .flat_map(|i| periods[9 * i].f32().unwrap().into_no_null_iter())
+        .fold(0., f32::max);
+    let mean = expect(0, 0).into_iter().fold(0., f32::max);
+    assert!(mean < peak);
+
+    assert!(matches!(
+        record.sound_field(range.clone(), option(Duration::ZERO, None)),
+        Err(EmulatorError::InvalidWindow)
+    ));
+    assert!(matches!(
+        record.sound_field(range, option(window, Some(Duration::from_micros(30)))),
+        Err(EmulatorError::InvalidWindow)
+    ));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_bvd(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(60 * ULTRASOUND_PERIOD)?;
+        autd.send(Uniform {
+            phase: Phase(0x80),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(40 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let point = emulator.center() + Vector3::new(0., 0., 300. * mm);
+    let rms = |emission| -> Result<Vec<f32>, EmulatorError> {
+        let df = record
+            .sound_field(
+                point,
+                RmsRecordOption {
+                    emission,
+                    #[cfg(feature = "gpu")]
+                    gpu,
+                    ..Default::default()
+                },
+            )?
+            .next(100 * ULTRASOUND_PERIOD)?;
+        Ok(df
+            .columns()
+            .iter()
+            .map(|c| c.f32().unwrap().get(0).unwrap())
+            .collect())
+    };
+    let ideal = rms(EmissionModel::Ideal)?;
+    let bvd = rms(EmissionModel::Bvd)?;
+
+    // the RMS of the instant sound pressure of each period delayed by the propagation time
+    let time_step = Duration::from_nanos(250);
+    let mut p = Vec::new();
+    record
+        .sound_field(
+            point,
+            InstantRecordOption {
+                time_step,
+                #[cfg(feature = "gpu")]
+                gpu,
+                ..Default::default()
+            },
+        )?
+        .for_each_frame(100 * ULTRASOUND_PERIOD, |_, v| p.push(v[0]))?;
+    let n = (ULTRASOUND_PERIOD.as_nanos() / time_step.as_nanos()) as usize;
+    let delay = (300. * mm / (340e3 * mm) / time_step.as_secs_f32()).round() as usize;
+    let instant = (0..100 - delay.div_ceil(n))
+        .map(|i| {
+            let s = &p[delay + i * n..delay + (i + 1) * n];
+            (s.iter().map(|p| p * p).sum::<f32>() / n as f32).sqrt()
+        })
+        .collect::<Vec<_>>();
+
+    // steady state
+    approx::assert_relative_eq!(ideal[55], bvd[55], max_relative = 1e-2);
+
+    // transient response after the start and the phase jump
+    assert!(bvd[0] < 0.5 * ideal[0]);
+    assert!(bvd[60..70].iter().fold(f32::INFINITY, |a, &b| a.min(b)) < 0.5 * ideal[65]);
+    let normalize = |v: &[f32]| v.iter().map(|x| x / v[55]).collect::<Vec<_>>();
+    let instant = normalize(&instant);
+    let error = |v: &[f32]| {
+        instant
+            .iter()
+            .zip(normalize(v))
+            .map(|(a, b)| (a - b).abs())
+            .sum::<f32>()
+    };
+    assert!(error(&bvd) < 0.2 * error(&ideal));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(EmissionModel::Ideal, 1)]
+#[case(EmissionModel::Ideal, 3)]
+#[case(EmissionModel::Bvd, 1)]
+#[test]
+fn record_rms_precision(
+    #[case] emission: EmissionModel,
+    #[case] harmonic: usize,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: emulator.center() + Vector3::new(0., 0., 150. * mm),
+            option: Default::default(),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let center = emulator.center();
+    let rms = |precision| -> Result<Vec<Vec<f32>>, EmulatorError> {
+        let df = record
+            .sound_field(
+                RangeXZ {
+                    x: center.x - 50.0..=center.x + 50.0,
+                    y: center.y,
+                    z: 100.0..=200.0,
+                    resolution: 5.,
+                },
+                RmsRecordOption {
+                    particle_velocity: true,
+                    reflectors: vec![Reflector {
+                        pos: Point3::new(0., 0., 250.),
+                        normal: UnitVector3::new_unchecked(-Vector3::z()),
+                        reflection_coefficient: 0.5,
+                    }],
+                    emission,
+                    harmonic,
+                    precision,
+                    ..Default::default()
+                },
+            )?
+            .next(10 * ULTRASOUND_PERIOD)?;
+        Ok(df
+            .columns()
+            .iter()
+            .map(|c| c.f32().unwrap().into_no_null_iter().collect())
+            .collect())
+    };
+    let single = rms(Precision::Single)?;
+    let double = rms(Precision::Double)?;
+
+    // the error of single precision is negligible near the array, though the phasors also carry the error of the phase of `kr`
+    single
+        .iter()
+        .zip(double.iter())
+        .enumerate()
+        .for_each(|(c, (s, d))| {
+            let max = d.iter().fold(0., |acc: f32, &v| acc.max(v.abs()));
+            assert!(max > 0.);
+            let tolerance = if c == 0 { 1e-4 } else { 1e-3 };
+            s.iter().zip(d.iter()).for_each(|(s, d)| {
+                approx::assert_abs_diff_eq!(d, s, epsilon = tolerance * max);
+            });
+        });
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_harmonic(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let point = emulator.center() + Vector3::new(20., 0., 30. * mm);
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: point,
+            option: Default::default(),
+        })?;
+        autd.tick(60 * ULTRASOUND_PERIOD)?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0x80),
+        })?;
+        autd.tick(60 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let rms = |emission, harmonic| -> Result<Vec<f32>, EmulatorError> {
+        let df = record
+            .sound_field(
+                point,
+                RmsRecordOption {
+                    emission,
+                    harmonic,
+                    #[cfg(feature = "gpu")]
+                    gpu,
+                    ..Default::default()
+                },
+            )?
+            .next(120 * ULTRASOUND_PERIOD)?;
+        Ok(df
+            .columns()
+            .iter()
+            .map(|c| c.f32().unwrap().get(0).unwrap())
+            .collect())
+    };
+
+    let fundamental = rms(EmissionModel::Ideal, 1)?;
+    let second = rms(EmissionModel::Ideal, 2)?;
+    let third = rms(EmissionModel::Ideal, 3)?;
+    let third_bvd = rms(EmissionModel::Bvd, 3)?;
+
+    // even harmonics vanish for the duty ratio of 50%
+    assert!(second[59] < 1e-3 * fundamental[59]);
+    // the transducer attenuates the harmonics more than the PWM
+    assert!(third[59] > 0.);
+    assert!(third[59] < fundamental[59] / 3.);
+    assert!(second[119] > 1e-3 * fundamental[119]);
+
+    // Ideal and Bvd coincide in the steady state even if the phases differ among the transducers
+    approx::assert_relative_eq!(third[59], third_bvd[59], max_relative = 1e-2);
+    approx::assert_relative_eq!(third[119], third_bvd[119], max_relative = 1e-2);
+
+    assert!(matches!(
+        record.sound_field(
+            point,
+            RmsRecordOption {
+                harmonic: 0,
+                ..Default::default()
+            }
+        ),
+        Err(EmulatorError::InvalidHarmonic)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn record_rms_angular_spectrum() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(2 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXYZ {
+        x: focus.x - 20.0..=focus.x + 20.0,
+        y: focus.y - 20.0..=focus.y + 20.0,
+        z: focus.z - 20.0..=focus.z + 20.0,
+        resolution: 2.,
+    };
+    let rms = |propagation| -> Result<Vec<Vec<f32>>, EmulatorError> {
+        let df = record
+            .sound_field(
+                range.clone(),
+                RmsRecordOption {
+                    particle_velocity: true,
+                    propagation,
+                    ..Default::default()
+                },
+            )?
+            .next(2 * ULTRASOUND_PERIOD)?;
+        Ok(df
+            .columns()
+            .iter()
+            .map(|c| c.f32().unwrap().into_no_null_iter().collect())
+            .collect())
+    };
+    let direct = rms(Propagation::Direct)?;
+    let angular_spectrum = rms(Propagation::AngularSpectrum { margin: 50. })?;
+
+    assert_eq!(direct.len(), angular_spectrum.len());
+    direct
+        .iter()
+        .zip(angular_spectrum.iter())
+        .for_each(|(direct, angular_spectrum)| {
+            let max = direct.iter().fold(0., |acc: f32, &v| acc.max(v.abs()));
+            assert!(max > 0.);
+            direct
+                .iter()
+                .zip(angular_spectrum.iter())
+                .for_each(|(d, a)| {
+                    approx::assert_abs_diff_eq!(d, a, epsilon = 2e-2 * max);
+                });
+        });
+
+    let invalid = |range: RangeXYZ, option: RmsRecordOption| {
+        matches!(
+            record.sound_field(
+                range,
+                RmsRecordOption {
+                    propagation: Propagation::AngularSpectrum { margin: 50. },
+                    ..option
+                }
+            ),
+            Err(EmulatorError::InvalidAngularSpectrum(_))
+        )
+    };
+    assert!(invalid(
+        RangeXYZ {
+            z: -10.0..=10.0,
+            ..range.clone()
+        },
+        Default::default()
+    ));
+    assert!(invalid(
+        RangeXYZ {
+            resolution: 5.,
+            ..range.clone()
+        },
+        Default::default()
+    ));
+    assert!(invalid(
+        range.clone(),
+        RmsRecordOption {
+            reflectors: vec![Reflector {
+                pos: Point3::new(0., 0., 250.),
+                normal: UnitVector3::new_unchecked(-Vector3::z()),
+                reflection_coefficient: 0.5,
+            }],
+            ..Default::default()
+        }
+    ));
+    assert!(matches!(
+        record.sound_field(
+            RangeXZ {
+                x: focus.x - 20.0..=focus.x + 20.0,
+                y: focus.y,
+                z: focus.z - 20.0..=focus.z + 20.0,
+                resolution: 2.,
+            },
+            RmsRecordOption {
+                propagation: Propagation::AngularSpectrum { margin: 50. },
+                ..Default::default()
+            }
+        ),
+        Err(EmulatorError::InvalidAngularSpectrum(_))
+    ));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_rms_scatterer(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+    let focus = emulator.center() + Vector3::new(0., 0., 150. * mm);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: focus,
+            option: Default::default(),
+        })?;
+        autd.tick(ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let center = emulator.center() + Vector3::new(0., 0., 100. * mm);
+    let radius = 20. * mm;
+    let points = vec![
+        focus,
+        center,
+        center + Vector3::new(radius * 1.0001, 0., 0.),
+        focus + Vector3::new(20. * mm, 0., 0.),
+    ];
+    let option = |scatterers| RmsRecordOption {
+        scatterers,
+        particle_velocity: true,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+    let rms = |scatterers| -> Result<Vec<Vec<f32>>, EmulatorError> {
+        let df = record
+            .sound_field(points.clone(), option(scatterers))?
+            .next(ULTRASOUND_PERIOD)?;
+        let column = |c: usize| df[c].f32().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        // the RMS of the pressure and the magnitude of each component of the particle velocity
+        Ok(std::iter::once(column(0))
+            .chain([3, 5, 7].map(|c| {
+                column(c)
+                    .into_iter()
+                    .zip(column(c + 1))
+                    .map(|(re, im)| re.hypot(im))
+                    .collect()
+            }))
+            .collect())
+    };
+
+    let free = rms(vec![])?;
+    let scattered = rms(vec![Scatterer { center, radius }])?;
+
+    // the sphere in front of the focus casts a shadow
+    assert!(scattered[0][0] < 0.7 * free[0][0]);
+    // no sound inside the sphere
+    (0..4).for_each(|c| assert!(scattered[c][1] < 1e-4 * free[c][1]));
+    // the normal component of the particle velocity vanishes on the rigid surface
+    assert!(scattered[1][2] < 1e-2 * scattered[3][2]);
+    assert!(scattered[1][2] < 1e-2 * free[1][2]);
+
+    // a tiny sphere hardly disturbs the field away from it
+    let tiny = rms(vec![Scatterer {
+        center,
+        radius: 0.1 * mm,
+    }])?;
+    [0, 3].into_iter().for_each(|i| {
+        approx::assert_relative_eq!(free[0][i], tiny[0][i], max_relative = 1e-2);
+    });
+
+    // the scattered field must fit in the memory limits hint
+    assert!(matches!(
+        record.sound_field(
+            points.clone(),
+            RmsRecordOption {
+                memory_limits_hint_mb: 0,
+                ..option(vec![Scatterer { center, radius }])
+            },
+        ),
+        Err(EmulatorError::MemoryLimitsExceeded { .. })
+    ));
+
+    Ok(())
+}
@@ -308,6 +308,120 @@ fn record_sound_field_with_limit(
     Ok(())
 }
 
+#[rstest::rstest]
+#[case(Interpolation::Linear)]
+#[case(Interpolation::WindowedSinc)]
+#[test]
+fn record_sound_field_precision(#[case] interpolation: Interpolation) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Focus {
+            pos: emulator.center() + Vector3::new(0., 0., 150. * mm),
+            option: Default::default(),
+        })?;
+        autd.tick(20 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let center = emulator.center();
+    let sound_field = |precision| -> Result<Vec<Vec<f32>>, EmulatorError> {
+        let df = record
+            .sound_field(
+                RangeXZ {
+                    x: center.x - 50.0..=center.x + 50.0,
+                    y: center.y,
+                    z: 100.0..=200.0,
+                    resolution: 10.,
+                },
+                InstantRecordOption {
+                    particle_velocity: true,
+                    time_step: Duration::from_micros(5),
+                    interpolation,
+                    precision,
+                    ..Default::default()
+                },
+            )?
+            .next(20 * ULTRASOUND_PERIOD)?;
+        Ok(df
+            .columns()
+            .iter()
+            .map(|c| c.f32().unwrap().into_no_null_iter().collect())
+            .collect())
+    };
+    let single = sound_field(Precision::Single)?;
+    let double = sound_field(Precision::Double)?;
+
+    // the error of single precision is negligible near the array and at the beginning
+    let max = double
+        .iter()
+        .step_by(4)
+        .flatten()
+        .fold(0., |acc: f32, &v| acc.max(v.abs()));
+    assert!(max > 0.);
+    single
+        .iter()
+        .zip(double.iter())
+        .step_by(4)
+        .for_each(|(s, d)| {
+            s.iter().zip(d.iter()).for_each(|(s, d)| {
+                approx::assert_abs_diff_eq!(d, s, epsilon = 1e-3 * max);
+            });
+        });
+
+    Ok(())
+}
+
+#[test]
+fn record_sound_field_tiled() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -50.0..=50.0,
+        y: -50.0..=50.0,
+        z: 100. * mm,
+        resolution: 10.,
+    };
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(5),
+        ..Default::default()
+    };
+
+    // the distances do not fit in the memory limits, so that they are calculated for each tile of points
+    let expect = record
+        .sound_field(range.clone(), option.clone())?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    let v = record
+        .sound_field(
+            range,
+            InstantRecordOption {
+                memory_limits_hint_mb: 0,
+                ..option
+            },
+        )?
+        .next(10 * ULTRASOUND_PERIOD)?;
+    assert_eq!(expect, v);
+
+    Ok(())
+}
+
 #[cfg(feature = "gpu")]
 #[test]
 fn record_sound_field_gpu_eq_cpu() -> Result<(), EmulatorError> {
@@ -408,3 +522,503 @@ fn not_recorded() -> Result<(), EmulatorError> {
 
     Ok(())
 }
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_sound_field_particle_velocity(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(50 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(1),
+        particle_velocity: true,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+    let mut sound_field = record.sound_field(
+        emulator.center() + Vector3::new(0., 0., 300. * mm),
+        option.clone(),
+    )?;
+    let df = sound_field
+        .skip(45 * ULTRASOUND_PERIOD)?
+        .next(ULTRASOUND_PERIOD)?;
+    assert_eq!(4 * 25, df.width());
+    assert_eq!(
+        vec![
+            "p[Pa]@1125000[ns]",
+            "vx[m/s]@1125000[ns]",
+            "vy[m/s]@1125000[ns]",
+            "vz[m/s]@1125000[ns]",
+        ],
+        df.get_column_names()
+            .iter()
+            .take(4)
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+
+    // plane wave approximation in the far field on the axis
+    let max_abs = |offset: usize| -> Result<f32, EmulatorError> {
+        Ok((0..25)
+            .map(|i| df[4 * i + offset].f32().map(|c| c.get(0).unwrap().abs()))
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .fold(0., f32::max))
+    };
+    let impedance = option.density * option.sound_speed * 1e-3;
+    approx::assert_relative_eq!(max_abs(0)? / impedance, max_abs(3)?, max_relative = 5e-2);
+    assert!(max_abs(1)? < max_abs(3)? * 1e-1);
+    assert!(max_abs(2)? < max_abs(3)? * 1e-1);
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_sound_field_aggregate(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase(0x40),
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: -10.0..=10.0,
+        y: -10.0..=10.0,
+        z: 10.,
+        resolution: 10.,
+    };
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(1),
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let df = record
+        .sound_field(range.clone(), option.clone())?
+        .skip(2 * ULTRASOUND_PERIOD)?
+        .next(5 * ULTRASOUND_PERIOD)?;
+    let statistics = [
+        Statistic::Max,
+        Statistic::Min,
+        Statistic::Mean,
+        Statistic::Rms,
+        Statistic::Percentile(100.),
+    ];
+    let aggregated = record
+        .sound_field(range.clone(), option.clone())?
+        .skip(2 * ULTRASOUND_PERIOD)?
+        .aggregate(5 * ULTRASOUND_PERIOD, &statistics)?;
+    // Without the percentiles, the statistics are reduced on GPU without reading back each time.
+    let reduced = record
+        .sound_field(range, option)?
+        .skip(2 * ULTRASOUND_PERIOD)?
+        .aggregate(5 * ULTRASOUND_PERIOD, &statistics[..4])?;
+
+    assert_eq!(
+        vec![
+            "p_max[Pa]",
+            "p_min[Pa]",
+            "p_mean[Pa]",
+            "p_rms[Pa]",
+            "p_percentile100[Pa]"
+        ],
+        aggregated
+            .get_column_names()
+            .iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(9, aggregated.height());
+
+    (0..df.height()).try_for_each(|i| -> Result<(), EmulatorError> {
+        let v = df
+            .columns()
+            .iter()
+            .map(|c| c.f32().map(|c| c.get(i).unwrap()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let n = v.len() as f32;
+        let max = v.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let min = v.iter().copied().fold(f32::INFINITY, f32::min);
+        let mean = v.iter().sum::<f32>() / n;
+        let rms = (v.iter().map(|v| v * v).sum::<f32>() / n).sqrt();
+        let get = |c: usize| aggregated[c].f32().map(|c| c.get(i).unwrap());
+        assert_eq!(max, get(0)?);
+        assert_eq!(min, get(1)?);
+        approx::assert_abs_diff_eq!(mean, get(2)?, epsilon = 1e-3);
+        approx::assert_relative_eq!(rms, get(3)?, max_relative = 1e-4);
+        assert_eq!(max, get(4)?);
+        (0..4).try_for_each(|c| -> Result<(), EmulatorError> {
+            approx::assert_abs_diff_eq!(get(c)?, reduced[c].f32()?.get(i).unwrap(), epsilon = 1e-3);
+            Ok(())
+        })?;
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_sound_field_reflector(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(40 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let z = 150. * mm;
+    let reflector = Reflector {
+        pos: Point3::new(0., 0., z),
+        normal: UnitVector3::new_unchecked(-Vector3::z()),
+        reflection_coefficient: 1.,
+    };
+    let center = emulator.center();
+    let points = vec![
+        Point3::new(center.x, center.y, z),
+        center + Vector3::z() * 50.,
+    ];
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(5),
+        memory_limits_hint_mb: 1,
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let direct = record
+        .sound_field(points.clone(), option.clone())?
+        .next(40 * ULTRASOUND_PERIOD)?;
+    let reflected = record
+        .sound_field(
+            points,
+            InstantRecordOption {
+                reflectors: vec![reflector],
+                ..option
+            },
+        )?
+        .next(40 * ULTRASOUND_PERIOD)?;
+
+    // on the reflector, the pressure is doubled
+    direct
+        .columns()
+        .iter()
+        .zip(reflected.columns())
+        .try_for_each(|(direct, reflected)| -> Result<(), EmulatorError> {
+            approx::assert_abs_diff_eq!(
+                2. * direct.f32()?.get(0).unwrap(),
+                reflected.f32()?.get(0).unwrap(),
+                epsilon = 1e-1
+            );
+            Ok(())
+        })?;
+
+    // the reflected wave arrives at the second point after traveling 250 mm
+    let arrival = Duration::from_secs_f32(250. * mm / option.sound_speed);
+    let (before, after): (Vec<_>, Vec<_>) = direct
+        .columns()
+        .iter()
+        .zip(reflected.columns())
+        .enumerate()
+        .partition(|(i, _)| (*i as u32 * option.time_step) < arrival);
+    before
+        .iter()
+        .try_for_each(|(_, (direct, reflected))| -> Result<(), EmulatorError> {
+            assert_eq!(
+                direct.f32()?.get(1).unwrap(),
+                reflected.f32()?.get(1).unwrap()
+            );
+            Ok(())
+        })?;
+    assert!(
+        after
+            .iter()
+            .map(|(_, (direct, reflected))| -> Result<f32, EmulatorError> {
+                Ok((direct.f32()?.get(1).unwrap() - reflected.f32()?.get(1).unwrap()).abs())
+            })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .fold(0., f32::max)
+            > 10.
+    );
+
+    Ok(())
+}
+
+#[test]
+fn record_sound_field_tilted_plane() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::new(10., 20., 30.),
+        rot: EulerAngle::ZYZ(30. * deg, 45. * deg, 0. * deg),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangePlane::from_device(&emulator[0], 0.0..=170., 0.0..=130., 100. * mm, 10.);
+    let points = range
+        .points()
+        .map(|(x, y, z)| Point3::new(x, y, z))
+        .collect::<Vec<_>>();
+    points.iter().for_each(|p| {
+        approx::assert_abs_diff_eq!(
+            100. * mm,
+            (*p - emulator[0][0].position()).dot(&emulator[0].axial_direction()),
+            epsilon = 1e-3
+        );
+    });
+
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(1),
+        ..Default::default()
+    };
+    let df = record
+        .sound_field(range, option.clone())?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    let expect = record
+        .sound_field(points, option)?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(2 * ULTRASOUND_PERIOD)?;
+    assert_eq!(expect, df);
+
+    Ok(())
+}
+
+#[test]
+fn record_sound_field_next_field() -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeYZ {
+        x: 0.,
+        y: -1.0..=1.0,
+        z: 100.0..=102.0,
+        resolution: 1.,
+    };
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(5),
+        ..Default::default()
+    };
+    let field = record
+        .sound_field(range.clone(), option.clone())?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next_field(ULTRASOUND_PERIOD)?;
+    assert_eq!(vec![5, 3, 3, 1], field.shape());
+    assert_eq!(Duration::from_micros(125), field.time[0]);
+
+    let df = record
+        .sound_field(range, option)?
+        .skip(5 * ULTRASOUND_PERIOD)?
+        .next(ULTRASOUND_PERIOD)?;
+    (0..5).try_for_each(|t| -> Result<(), EmulatorError> {
+        let v = df[t].f32()?.into_no_null_iter().collect::<Vec<_>>();
+        (0..3).for_each(|iz| {
+            (0..3).for_each(|iy| {
+                assert_eq!(Some(v[iy + 3 * iz]), field.get(&[t, iz, iy, 0]));
+            })
+        });
+        Ok(())
+    })?;
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(Interpolation::Nearest, 5e-2, false)]
+#[case(Interpolation::Cubic, 1e-3, false)]
+#[case(Interpolation::WindowedSinc, 1e-3, false)]
+#[cfg_attr(feature = "gpu", case(Interpolation::Nearest, 5e-2, true))]
+#[cfg_attr(feature = "gpu", case(Interpolation::Cubic, 1e-3, true))]
+#[cfg_attr(feature = "gpu", case(Interpolation::WindowedSinc, 1e-3, true))]
+#[test]
+fn record_sound_field_interpolation(
+    #[case] interpolation: Interpolation,
+    #[case] tolerance: f32,
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(20 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeX {
+        x: emulator.center().x - 20.0..=emulator.center().x + 20.0,
+        y: emulator.center().y,
+        z: emulator.center().z + 30.,
+        resolution: 5.,
+    };
+    let field = |interpolation| -> Result<Field, EmulatorError> {
+        record
+            .sound_field(
+                range.clone(),
+                InstantRecordOption {
+                    time_step: Duration::from_nanos(125),
+                    interpolation,
+                    #[cfg(feature = "gpu")]
+                    gpu,
+                    ..Default::default()
+                },
+            )?
+            .next_field(20 * ULTRASOUND_PERIOD)
+    };
+
+    let expect = field(Interpolation::Linear)?;
+    let v = field(interpolation)?;
+    let max = expect
+        .values
+        .iter()
+        .fold(0., |acc: f32, v| acc.max(v.abs()));
+    assert!(max > 0.);
+    expect
+        .values
+        .iter()
+        .zip(v.values.iter())
+        .for_each(|(e, v)| approx::assert_abs_diff_eq!(e, v, epsilon = tolerance * max));
+
+    Ok(())
+}
+
+#[rstest::rstest]
+#[case(false)]
+#[cfg_attr(feature = "gpu", case(true))]
+#[test]
+fn record_sound_field_for_each_frame(
+    #[allow(unused_variables)]
+    #[case]
+    gpu: bool,
+) -> Result<(), EmulatorError> {
+    let emulator = Emulator::new([AUTD3 {
+        pos: Point3::origin(),
+        rot: UnitQuaternion::identity(),
+    }]);
+
+    let record = emulator.record(|autd| {
+        autd.send(Silencer::disable())?;
+        autd.send(Uniform {
+            phase: Phase::ZERO,
+            intensity: Intensity(0xFF),
+        })?;
+        autd.tick(10 * ULTRASOUND_PERIOD)?;
+        Ok(())
+    })?;
+
+    let range = RangeXY {
+        x: emulator.center().x - 10.0..=emulator.center().x + 10.0,
+        y: emulator.center().y - 10.0..=emulator.center().y + 10.0,
+        z: emulator.center().z + 10.,
+        resolution: 10.,
+    };
+    let option = InstantRecordOption {
+        time_step: Duration::from_micros(5),
+        #[cfg(feature = "gpu")]
+        gpu,
+        ..Default::default()
+    };
+
+    let expect = record
+        .sound_field(range.clone(), option.clone())?
+        .skip(2 * ULTRASOUND_PERIOD)?
+        .next(8 * ULTRASOUND_PERIOD)?;
+
+    let mut sound_field = record.sound_field(range, option)?;
+    sound_field.skip(2 * ULTRASOUND_PERIOD)?;
+    let mut n = 0;
+    sound_field.for_each_frame(8 * ULTRASOUND_PERIOD, |t, p| {
+        let column = &expect[n];
+        assert_eq!(format!("p[Pa]@{t}[ns]"), column.name().as_str());
+        assert_eq!(
+            column
+                .f32()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<_>>(),
+            p
+        );
+        n += 1;
+    })?;
+    assert_eq!(expect.width(), n);
+
+    Ok(())
+}